@@ -24,5 +24,8 @@ namespace Engine
 
         [DllImport(DllName, EntryPoint = "is_solvable")]
         public static extern byte IsSolvable(string json);
+
+        [DllImport(DllName, EntryPoint = "migrate")]
+        public static extern string Migrate(string json);
     }
 }
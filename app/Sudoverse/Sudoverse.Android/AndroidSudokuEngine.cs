@@ -19,5 +19,8 @@ namespace Sudoverse.Droid
 
         public byte IsSolvable(string json) =>
             EngineSo.IsSolvable(json);
+
+        public string Migrate(string json) =>
+            EngineSo.Migrate(json);
     }
 }
@@ -19,5 +19,8 @@ namespace Sudoverse.UWP
 
         public byte IsSolvable(string json) =>
             EngineDll.IsSolvable(json);
+
+        public string Migrate(string json) =>
+            EngineDll.Migrate(json);
     }
 }
@@ -1,4 +1,8 @@
-﻿using Sudoverse.SudokuModel;
+﻿using Newtonsoft.Json;
+using Newtonsoft.Json.Linq;
+
+using Sudoverse.SudokuModel;
+using Sudoverse.Util;
 
 namespace Sudoverse.Engine
 {
@@ -77,5 +81,36 @@ namespace Sudoverse.Engine
         /// <returns>The <see cref="Solvability"/> of the given Sudoku.</returns>
         public Solvability IsSolvable(Sudoku sudoku) =>
             (Solvability)engine.IsSolvable(sudoku.ToJson());
+
+        /// <summary>
+        /// Upgrades a stored Sudoku document, i.e. a puzzle or savegame, to the current schema
+        /// version of the engine, so it can be parsed by <see cref="Sudoku"/>. Fields the engine
+        /// does not know are kept as they are.
+        /// </summary>
+        /// <param name="json">The JSON code of the document to upgrade.</param>
+        /// <returns>The JSON code of the upgraded document.</returns>
+        /// <exception cref="ParseJsonException">If the document is not valid JSON, was written by
+        /// a newer version of the app, or cannot be upgraded for any other reason.</exception>
+        public string Migrate(string json)
+        {
+            var token = JToken.Parse(engine.Migrate(json));
+
+            if (!(token is JObject jobject))
+                throw new ParseJsonException(token.Type, JTokenType.Object);
+
+            var type = jobject.GetField<JValue>("type");
+
+            if (type.Type != JTokenType.String)
+                throw new ParseJsonException(type.Type, JTokenType.String);
+
+            switch ((string)type)
+            {
+                case "ok":
+                    var document = jobject.GetField<JObject>("value");
+                    return document.ToString(Formatting.None);
+                default:
+                    throw new ParseJsonException();
+            }
+        }
     }
 }
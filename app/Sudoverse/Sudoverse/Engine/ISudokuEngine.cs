@@ -43,5 +43,13 @@
         /// which can be obtained using <see cref="SudokuModel.Sudoku.ToJson()"/>.
         /// </summary>
         byte IsSolvable(string json);
+
+        /// <summary>
+        /// Upgrades a Sudoku document written by any earlier version of the engine or app to the
+        /// current schema version. Fields the engine does not know, such as the state of the cells
+        /// in savegames, are kept as they are. The result is returned as JSON, either an
+        /// <tt>ok</tt> response with the upgraded document or an <tt>error</tt> response.
+        /// </summary>
+        string Migrate(string json);
     }
 }
@@ -1,4 +1,5 @@
-﻿using Sudoverse.SudokuModel;
+﻿using Sudoverse.Engine;
+using Sudoverse.SudokuModel;
 using Sudoverse.Util;
 
 using System;
@@ -63,11 +64,18 @@ namespace Sudoverse
         public static bool HasCurrent() =>
             File.Exists(CurrentFileName);
 
+        /// <summary>
+        /// Reads the Sudoku document stored in the file with the given path and upgrades it to the
+        /// current schema version of the engine.
+        /// </summary>
+        private static string ReadDocument(string path) =>
+            SudokuEngineProvider.Engine.Migrate(File.ReadAllText(path));
+
         /// <summary>
         /// Loads the current Sudoku.
         /// </summary>
         public static Sudoku LoadCurrent() =>
-            Sudoku.ParseFullJson(File.ReadAllText(CurrentFileName));
+            Sudoku.ParseFullJson(ReadDocument(CurrentFileName));
 
         /// <summary>
         /// Stores the given Sudoku as the current one.
@@ -172,6 +180,6 @@ namespace Sudoverse
         /// Loads the Sudoku puzzle (i.e. without any pencilmarks) stored under the given name.
         /// </summary>
         public static Sudoku LoadPuzzle(string name, PencilmarkType pencilmarkType, bool locked) =>
-            Sudoku.ParseJson(File.ReadAllText(GetPuzzlePath(name)), pencilmarkType, locked);
+            Sudoku.ParseJson(ReadDocument(GetPuzzlePath(name)), pencilmarkType, locked);
     }
 }
@@ -13,6 +13,23 @@ namespace Sudoverse.SudokuModel
     /// </summary>
     public sealed class Sudoku
     {
+        /// <summary>
+        /// The version of the JSON representation of Sudoku written by this app. It must match
+        /// the schema version of the engine, which upgrades documents of older versions.
+        /// </summary>
+        public const int SchemaVersion = 1;
+
+        /// <summary>
+        /// The version of the savegame format written by <see cref="ToFullJson"/>. It covers the
+        /// app-specific parts of savegames, i.e. the state of the cells and the pencilmark type,
+        /// and is independent of <see cref="SchemaVersion"/>, which only covers the parts shared
+        /// with the engine. Savegames without this field have version 0.
+        /// </summary>
+        public const int SaveFormatVersion = 1;
+
+        private const string SchemaVersionField = "schema_version";
+        private const string SaveFormatVersionField = "save_format_version";
+
         /// <summary>
         /// The width of a single block of the Sudoku in number of cells. In an ordinary Sudoku,
         /// the blocks are 3x3, so this would be 3.
@@ -142,7 +159,7 @@ namespace Sudoverse.SudokuModel
             }
         }
 
-        private string ToJsonWith(Func<SudokuCell, JToken> cellConverter, bool addPencilmarkType)
+        private string ToJsonWith(Func<SudokuCell, JToken> cellConverter, bool savegame)
         {
             var cellsJson = new JArray();
 
@@ -157,11 +174,15 @@ namespace Sudoverse.SudokuModel
             grid.Add("cells", cellsJson);
 
             var sudoku = new JObject();
+            sudoku.Add(SchemaVersionField, SchemaVersion);
             sudoku.Add("grid", grid);
             sudoku.Add("constraint", ConstraintUtil.ToJson(Constraint));
 
-            if (addPencilmarkType)
+            if (savegame)
+            {
+                sudoku.Add(SaveFormatVersionField, SaveFormatVersion);
                 sudoku.Add("pencilmark_type", PencilmarkType.GetIdentifier());
+            }
 
             return JsonConvert.SerializeObject(sudoku);
         }
@@ -184,11 +205,39 @@ namespace Sudoverse.SudokuModel
 
         /// <summary>
         /// Converts the full state of this Sudoku into JSON. This includes all digits,
-        /// annotations, and the lock status of each cell. This is used for savegames.
+        /// annotations, and the lock status of each cell. This is used for savegames, which are
+        /// versioned by both <see cref="SchemaVersion"/> and <see cref="SaveFormatVersion"/>.
         /// </summary>
         public string ToFullJson() =>
             ToJsonWith(cell => cell.ToJson(), true);
 
+        /// <summary>
+        /// Upgrades the app-specific parts of the given savegame to the current
+        /// <see cref="SaveFormatVersion"/>.
+        /// </summary>
+        /// <exception cref="ParseJsonException">If the version is invalid or the savegame was
+        /// written by a newer version of the app.</exception>
+        private static void MigrateSaveFormat(JObject savegame)
+        {
+            int version = 0;
+
+            if (savegame.TryGetValue(SaveFormatVersionField, out JToken versionToken))
+            {
+                if (versionToken.Type != JTokenType.Integer)
+                    throw new ParseJsonException(versionToken.Type, JTokenType.Integer);
+
+                version = (int)versionToken;
+            }
+
+            if (version < 0 || version > SaveFormatVersion)
+                throw new ParseJsonException();
+
+            // Version 0 savegames have the same layout as version 1, so there is nothing to do
+            // yet. Migrations for later versions go here, in ascending order.
+
+            savegame[SaveFormatVersionField] = SaveFormatVersion;
+        }
+
         private static Sudoku ParseJsonWith(string json,
             Func<JToken, PencilmarkType, SudokuCell> cellParser, PencilmarkType pencilmarkType)
         {
@@ -197,6 +246,14 @@ namespace Sudoverse.SudokuModel
             if (!(sudokuToken is JObject sudokuObject))
                 throw new ParseJsonException();
 
+            var schemaVersion = sudokuObject.GetField<JValue>(SchemaVersionField);
+
+            if (schemaVersion.Type != JTokenType.Integer || (int)schemaVersion != SchemaVersion)
+                throw new ParseJsonException();
+
+            if (pencilmarkType == null)
+                MigrateSaveFormat(sudokuObject);
+
             if (!sudokuObject.TryGetValue("constraint", out JToken constraintToken))
                 throw new ParseJsonException();
 
@@ -237,7 +294,9 @@ namespace Sudoverse.SudokuModel
         /// digit are transcribed, and all state except that digit is disregarded. This is used for
         /// communication with the engine. The pencilmark type must be provided, since it is not
         /// stored in the JSON data. The parameter <tt>locked</tt> determines whether the cells
-        /// containing digits will be locked, i.e. unable to be modified.
+        /// containing digits will be locked, i.e. unable to be modified. The JSON data must be of
+        /// the current <see cref="SchemaVersion"/>, so stored puzzles must be upgraded with
+        /// <see cref="Engine.EngineWrapper.Migrate(string)"/> first.
         /// </summary>
         public static Sudoku ParseJson(string json, PencilmarkType pencilmarkType,
                 bool locked = true) =>
@@ -247,7 +306,9 @@ namespace Sudoverse.SudokuModel
 
         /// <summary>
         /// Parses the full state of this Sudoku from JSON. This includes all digits, annotations,
-        /// and the lock status of each cell. This is used for savegames.
+        /// and the lock status of each cell. This is used for savegames, which must be upgraded
+        /// with <see cref="Engine.EngineWrapper.Migrate(string)"/> first. Savegames of older
+        /// <see cref="SaveFormatVersion"/>s are upgraded while parsing.
         /// </summary>
         public static Sudoku ParseFullJson(string json) =>
             ParseJsonWith(json, (token, ptype) => SudokuCell.ParseJson(token, ptype), null);
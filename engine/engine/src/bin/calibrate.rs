@@ -0,0 +1,122 @@
+//! Runs calibration trials for the difficulty ladders of the engine and
+//! prints a report for every calibrated constraint and difficulty.
+//!
+//! ```text
+//! calibrate [--trials N] [--ladder FILE] [--json] [CONSTRAINT [DIFFICULTY]]
+//! ```
+//!
+//! Without a constraint, all constraints are calibrated. Without a
+//! difficulty, all levels of the ladder are calibrated.
+
+use engine::calibrate::{self, CalibrationConfig};
+
+use std::env;
+use std::fs;
+use std::process;
+
+const DEFAULT_TRIALS: usize = 1000;
+const CONSTRAINTS: [i32; 6] = [0, 1, 2, 3, 4, 5];
+const USAGE: &str = "usage: calibrate [--trials N] [--ladder FILE] [--json] \
+    [CONSTRAINT [DIFFICULTY]]";
+
+struct Args {
+    trials: usize,
+    ladder_json: Option<String>,
+    json: bool,
+    constraint: Option<i32>,
+    difficulty: Option<i32>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn parse_number<T: std::str::FromStr>(value: Option<String>, name: &str)
+        -> T {
+    value.and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| fail(&format!("invalid {}", name)))
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        trials: DEFAULT_TRIALS,
+        ladder_json: None,
+        json: false,
+        constraint: None,
+        difficulty: None
+    };
+    let mut positional = Vec::new();
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--trials" => args.trials = parse_number(iter.next(), "trials"),
+            "--ladder" => {
+                let path = iter.next()
+                    .unwrap_or_else(|| fail("missing ladder file"));
+                let json = fs::read_to_string(&path).unwrap_or_else(
+                    |e| fail(&format!("cannot read {}: {}", path, e)));
+                args.ladder_json = Some(json);
+            },
+            "--json" => args.json = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0)
+            },
+            _ => positional.push(arg)
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    args.constraint = positional.next()
+        .map(|c| parse_number(Some(c), "constraint"));
+    args.difficulty = positional.next()
+        .map(|d| parse_number(Some(d), "difficulty"));
+
+    if positional.next().is_some() {
+        fail("too many arguments");
+    }
+
+    args
+}
+
+fn main() {
+    let args = parse_args();
+    let constraints = match args.constraint {
+        Some(constraint) => vec![constraint],
+        None => CONSTRAINTS.to_vec()
+    };
+    let mut reports = Vec::new();
+
+    for constraint in constraints {
+        let levels = calibrate::difficulty_levels(constraint,
+            args.ladder_json.as_deref())
+            .unwrap_or_else(|e| fail(&e.to_string())) as i32;
+        let difficulties = match args.difficulty {
+            Some(difficulty) => vec![difficulty],
+            None => (1..=levels).collect()
+        };
+
+        for difficulty in difficulties {
+            let report = calibrate::calibrate(&CalibrationConfig {
+                constraint,
+                difficulty,
+                trials: args.trials,
+                ladder_json: args.ladder_json.clone()
+            }).unwrap_or_else(|e| fail(&e.to_string()));
+
+            if args.json {
+                reports.push(report);
+            }
+            else {
+                println!("{}", report);
+            }
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    }
+}
@@ -0,0 +1,103 @@
+//! Renders a Sudoku as Unicode text, see [engine::render::render_text].
+//!
+//! ```text
+//! render [--candidates] [--entered LINE] [PUZZLE]
+//! ```
+//!
+//! The puzzle is given in the line format or as a Sudoku JSON document. If it
+//! is omitted, it is read from the standard input. Digits entered by a player
+//! can be given in the line format with `--entered`, where givens are
+//! ignored.
+
+use engine::line_format;
+use engine::render::{self, Annotations, TextOptions};
+use engine::schema;
+
+use std::env;
+use std::io::{self, Read};
+use std::process;
+
+const USAGE: &str =
+    "usage: render [--candidates] [--entered LINE] [PUZZLE]";
+
+struct Args {
+    candidates: bool,
+    entered: Option<String>,
+    puzzle: Option<String>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        candidates: false,
+        entered: None,
+        puzzle: None
+    };
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--candidates" => args.candidates = true,
+            "--entered" => args.entered = Some(iter.next()
+                .unwrap_or_else(|| fail("missing entered digits"))),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0)
+            },
+            _ if args.puzzle.is_none() => args.puzzle = Some(arg),
+            _ => fail("too many arguments")
+        }
+    }
+
+    args
+}
+
+fn main() {
+    let args = parse_args();
+    let input = match args.puzzle {
+        Some(puzzle) => puzzle,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).unwrap_or_else(
+                |e| fail(&format!("cannot read puzzle: {}", e)));
+            input
+        }
+    };
+    let sudoku = if input.trim_start().starts_with('{') {
+        schema::from_json(&input)
+            .unwrap_or_else(|e| fail(&format!("invalid puzzle: {}", e)))
+    }
+    else {
+        line_format::parse(&input)
+            .unwrap_or_else(|e| fail(&format!("invalid puzzle: {}", e)))
+    };
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let digits = match args.entered {
+        Some(line) => {
+            let entered = line_format::parse_with_dimensions(&line,
+                grid.block_width(), grid.block_height())
+                .unwrap_or_else(
+                    |e| fail(&format!("invalid entered digits: {}", e)));
+
+            (0..size * size)
+                .map(|i| entered.grid().get_cell(i % size, i / size).unwrap())
+                .collect()
+        },
+        None => Vec::new()
+    };
+    let annotations = Annotations {
+        digits,
+        pencilmarks: Vec::new()
+    };
+    let options = TextOptions {
+        candidates: args.candidates
+    };
+
+    print!("{}", render::render_text(&sudoku, &annotations, &options));
+}
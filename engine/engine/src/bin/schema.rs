@@ -0,0 +1,13 @@
+//! Prints the JSON Schema of the Sudoku documents read and written by the
+//! engine, which is published as `schema/sudoku.schema.json`.
+//!
+//! ```text
+//! schema > schema/sudoku.schema.json
+//! ```
+
+use engine::schema;
+
+fn main() {
+    let schema = schema::json_schema();
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
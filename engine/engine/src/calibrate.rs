@@ -0,0 +1,444 @@
+//! Empirical calibration of difficulty ladders. For a given constraint and
+//! difficulty, many generation trials are run with the regular generator
+//! threads, recording how often candidates are accepted or rejected at each
+//! bound, how long each trial takes, and which techniques the accepted
+//! puzzles require. From this, adjustments to the ladder are suggested.
+//!
+//! This is used by the `calibrate` binary, but can also be called directly.
+
+use crate::generate::{self, Ladder, Outcome, TaskError};
+use crate::sync::CancelHandle;
+use crate::workers::{self, Priority};
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// If fewer candidates than this fraction are accepted, a level is
+/// considered nearly empty.
+const NEARLY_EMPTY: f64 = 0.05;
+
+/// If more candidates than this fraction are accepted, a level is considered
+/// to be nearly always hit.
+const NEARLY_ALWAYS: f64 = 0.95;
+
+/// The configuration of a calibration run.
+pub struct CalibrationConfig {
+
+    /// The constraint identifier, see the crate-level documentation.
+    pub constraint: i32,
+
+    /// The difficulty, i.e. the 1-based index of the level on the ladder.
+    pub difficulty: i32,
+
+    /// The number of trials to run.
+    pub trials: usize,
+
+    /// The JSON code of a custom ladder to calibrate. If `None`, the built-in
+    /// ladder of the constraint is used.
+    pub ladder_json: Option<String>
+}
+
+/// The reasons why a calibration cannot be run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CalibrationError {
+
+    /// The custom ladder is not valid JSON or does not describe a valid
+    /// ladder. The reason is provided.
+    InvalidLadder(String),
+
+    /// The constraint identifier, which is provided, does not denote any
+    /// constraint.
+    InvalidConstraint(i32),
+
+    /// The ladder has no level for the difficulty, which is provided.
+    InvalidDifficulty(i32)
+}
+
+impl CalibrationError {
+    fn from_task_error(error: TaskError) -> CalibrationError {
+        match error {
+            TaskError::InvalidConstraint(constraint) =>
+                CalibrationError::InvalidConstraint(constraint),
+            TaskError::InvalidDifficulty(difficulty) =>
+                CalibrationError::InvalidDifficulty(difficulty),
+            error => CalibrationError::InvalidLadder(error.to_string())
+        }
+    }
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::InvalidLadder(reason) =>
+                write!(f, "invalid ladder: {}", reason),
+            CalibrationError::InvalidConstraint(constraint) =>
+                write!(f, "invalid constraint identifier: {}", constraint),
+            CalibrationError::InvalidDifficulty(difficulty) =>
+                write!(f, "invalid difficulty: {}", difficulty)
+        }
+    }
+}
+
+impl Error for CalibrationError { }
+
+fn parse_ladder(constraint: i32, ladder_json: Option<&str>)
+        -> Result<Ladder, CalibrationError> {
+    match ladder_json {
+        Some(json) => {
+            let ladder: Ladder = serde_json::from_str(json)
+                .map_err(|e| CalibrationError::InvalidLadder(e.to_string()))?;
+            ladder.validate().map_err(CalibrationError::from_task_error)?;
+            Ok(ladder)
+        },
+        None => Ladder::builtin(constraint)
+            .map_err(CalibrationError::from_task_error)
+    }
+}
+
+/// Gets the number of difficulty levels of the built-in ladder of the given
+/// constraint, or of the given custom ladder if provided.
+///
+/// # Errors
+///
+/// * [CalibrationError::InvalidLadder] if the ladder JSON is invalid.
+/// * [CalibrationError::InvalidConstraint] if no ladder JSON is provided and
+/// the constraint identifier is invalid.
+pub fn difficulty_levels(constraint: i32, ladder_json: Option<&str>)
+        -> Result<usize, CalibrationError> {
+    Ok(parse_ladder(constraint, ladder_json)?.levels.len())
+}
+
+/// A summary of a distribution of durations in milliseconds.
+#[derive(Serialize)]
+pub struct TimeDistribution {
+
+    /// The arithmetic mean.
+    pub mean_millis: f64,
+
+    /// The shortest duration.
+    pub min_millis: f64,
+
+    /// The 50th percentile.
+    pub median_millis: f64,
+
+    /// The 90th percentile.
+    pub p90_millis: f64,
+
+    /// The 99th percentile.
+    pub p99_millis: f64,
+
+    /// The longest duration.
+    pub max_millis: f64
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl TimeDistribution {
+    fn from_samples(mut samples: Vec<Duration>) -> Option<TimeDistribution> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        samples.sort();
+        let last = samples.len() - 1;
+        let percentile = |p: f64|
+            millis(samples[(p * last as f64).round() as usize]);
+        let total: Duration = samples.iter().sum();
+
+        Some(TimeDistribution {
+            mean_millis: millis(total) / samples.len() as f64,
+            min_millis: percentile(0.0),
+            median_millis: percentile(0.5),
+            p90_millis: percentile(0.9),
+            p99_millis: percentile(0.99),
+            max_millis: percentile(1.0)
+        })
+    }
+}
+
+/// How often a technique was used to solve the accepted puzzles.
+#[derive(Default, Serialize)]
+pub struct TechniqueUsage {
+
+    /// The number of accepted puzzles that required the technique at least
+    /// once.
+    pub puzzles: usize,
+
+    /// The total number of times the technique made progress.
+    pub applications: usize
+}
+
+/// The results of a calibration run. It can be serialized as JSON or
+/// displayed in a human-readable form.
+#[derive(Serialize)]
+pub struct CalibrationReport {
+
+    /// The constraint identifier, see the crate-level documentation.
+    pub constraint: i32,
+
+    /// The calibrated difficulty.
+    pub difficulty: i32,
+
+    /// The number of trials that were run.
+    pub trials: usize,
+
+    /// The number of candidates within the bounds of the level.
+    pub accepted: usize,
+
+    /// The number of candidates the solver of the previous level could
+    /// solve.
+    pub lower_rejections: usize,
+
+    /// The number of candidates the solver of the level could not solve.
+    pub upper_rejections: usize,
+
+    /// The number of generator threads.
+    pub threads: usize,
+
+    /// The wall time of the entire run in milliseconds.
+    pub wall_time_millis: f64,
+
+    /// The average wall time per accepted puzzle in milliseconds, which is
+    /// what generating a puzzle of this difficulty takes in practice. `None`
+    /// if no puzzle was accepted.
+    pub millis_per_accepted: Option<f64>,
+
+    /// The distribution of the time a single trial took on one thread.
+    /// `None` if no trial was run.
+    pub trial_time: Option<TimeDistribution>,
+
+    /// The techniques required by the accepted puzzles, keyed by their name
+    /// in the ladder format.
+    pub techniques: BTreeMap<String, TechniqueUsage>,
+
+    /// Human-readable suggestions for adjusting the ladder.
+    pub suggestions: Vec<String>
+}
+
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    }
+    else {
+        count as f64 / total as f64
+    }
+}
+
+impl CalibrationReport {
+
+    /// The fraction of candidates that were accepted.
+    pub fn acceptance_rate(&self) -> f64 {
+        rate(self.accepted, self.trials)
+    }
+
+    /// The fraction of candidates that were too easy.
+    pub fn lower_rejection_rate(&self) -> f64 {
+        rate(self.lower_rejections, self.trials)
+    }
+
+    /// The fraction of candidates that were too hard.
+    pub fn upper_rejection_rate(&self) -> f64 {
+        rate(self.upper_rejections, self.trials)
+    }
+
+    fn suggest(&self) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        let difficulty = self.difficulty;
+
+        if self.trials == 0 {
+            return suggestions;
+        }
+
+        if self.acceptance_rate() < NEARLY_EMPTY {
+            if self.lower_rejections >= self.upper_rejections {
+                suggestions.push(format!(
+                    "Level {} is nearly empty: {:.1}% of candidates are \
+                    solvable by level {}. Consider a generator solver that \
+                    removes more clues, or a weaker solver for level {}.",
+                    difficulty, self.lower_rejection_rate() * 100.0,
+                    difficulty - 1, difficulty - 1));
+            }
+            else {
+                suggestions.push(format!(
+                    "Level {} is nearly empty: {:.1}% of candidates are not \
+                    solvable by its solver. Consider a stronger solver for \
+                    level {}, or a weaker generator solver.",
+                    difficulty, self.upper_rejection_rate() * 100.0,
+                    difficulty));
+            }
+        }
+        else if self.acceptance_rate() > NEARLY_ALWAYS && difficulty > 1 {
+            suggestions.push(format!(
+                "Level {} is nearly always hit: only {:.1}% of candidates are \
+                solvable by level {}. Consider a stronger solver for level {} \
+                to separate the levels, or splitting level {}.",
+                difficulty, self.lower_rejection_rate() * 100.0,
+                difficulty - 1, difficulty - 1, difficulty));
+        }
+
+        suggestions
+    }
+}
+
+fn write_count(f: &mut Formatter<'_>, label: &str, count: usize, total: usize)
+        -> fmt::Result {
+    writeln!(f, "  {:<18}{:>8} ({:.1}%)", label, count,
+        rate(count, total) * 100.0)
+}
+
+impl Display for CalibrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "constraint {}, difficulty {}: {} trials in {:.1} s on {} \
+            threads", self.constraint, self.difficulty, self.trials,
+            self.wall_time_millis / 1000.0, self.threads)?;
+        write_count(f, "accepted:", self.accepted, self.trials)?;
+        write_count(f, "too easy:", self.lower_rejections, self.trials)?;
+        write_count(f, "too hard:", self.upper_rejections, self.trials)?;
+
+        if let Some(time) = &self.trial_time {
+            writeln!(f, "  {:<18}mean {:.1}, min {:.1}, median {:.1}, \
+                p90 {:.1}, p99 {:.1}, max {:.1}", "trial time (ms):",
+                time.mean_millis, time.min_millis, time.median_millis,
+                time.p90_millis, time.p99_millis, time.max_millis)?;
+        }
+
+        if let Some(millis) = self.millis_per_accepted {
+            writeln!(f, "  {:<18}{:.1} ms", "time per puzzle:", millis)?;
+        }
+
+        if !self.techniques.is_empty() {
+            writeln!(f, "  techniques (puzzles / applications):")?;
+
+            for (name, usage) in &self.techniques {
+                writeln!(f, "    {:<30}{:>8} / {}", name, usage.puzzles,
+                    usage.applications)?;
+            }
+        }
+
+        for suggestion in &self.suggestions {
+            writeln!(f, "  suggestion: {}", suggestion)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a calibration with the given configuration on 9x9 Sudoku using the
+/// shared worker pool.
+///
+/// # Errors
+///
+/// * [CalibrationError::InvalidLadder] if the ladder JSON is invalid.
+/// * [CalibrationError::InvalidConstraint] if the constraint identifier is
+/// invalid.
+/// * [CalibrationError::InvalidDifficulty] if the ladder has no level for the
+/// difficulty.
+pub fn calibrate(config: &CalibrationConfig)
+        -> Result<CalibrationReport, CalibrationError> {
+    let ladder =
+        parse_ladder(config.constraint, config.ladder_json.as_deref())?;
+    let task = generate::ladder_generation_task(&ladder, config.constraint,
+        config.difficulty, 3, 3)
+        .map_err(CalibrationError::from_task_error)?;
+    let threads = workers::workers().threads();
+    let mut accepted = 0;
+    let mut lower_rejections = 0;
+    let mut upper_rejections = 0;
+    let mut samples = Vec::with_capacity(config.trials);
+    let mut techniques: BTreeMap<String, TechniqueUsage> = BTreeMap::new();
+    let start = Instant::now();
+
+    let trials = task.run_trials(config.trials, Priority::Normal,
+            &CancelHandle::new(), |report| {
+        samples.push(report.elapsed);
+
+        match &report.outcome {
+            Outcome::Accepted(sudoku) => {
+                accepted += 1;
+                let counts = ladder.count_techniques(config.difficulty, sudoku);
+
+                for (name, applications) in counts {
+                    let usage = techniques.entry(name.to_owned()).or_default();
+                    usage.puzzles += 1;
+                    usage.applications += applications;
+                }
+            },
+            Outcome::TooEasy(_) => lower_rejections += 1,
+            Outcome::TooHard(_) => upper_rejections += 1
+        }
+    });
+
+    let wall_time_millis = millis(start.elapsed());
+    let millis_per_accepted = match accepted {
+        0 => None,
+        _ => Some(wall_time_millis / accepted as f64)
+    };
+    let mut report = CalibrationReport {
+        constraint: config.constraint,
+        difficulty: config.difficulty,
+        trials,
+        accepted,
+        lower_rejections,
+        upper_rejections,
+        threads,
+        wall_time_millis,
+        millis_per_accepted,
+        trial_time: TimeDistribution::from_samples(samples),
+        techniques,
+        suggestions: Vec::new()
+    };
+    report.suggestions = report.suggest();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// The constraints whose ladders are derived from the default one with
+    /// variant-specific techniques: diagonals, knight's move, king's move, and
+    /// Chess Sudoku.
+    const VARIANT_CONSTRAINTS: [i32; 4] = [1, 2, 3, 4];
+
+    const TRIALS: usize = 200;
+
+    /// Checks that no level of the variant ladders is nearly empty or nearly
+    /// always hit. This takes several minutes, so it is ignored by default.
+    /// Run it with `cargo test --release -- --ignored` after changing any of
+    /// the ladders, and tune the levels with the `calibrate` binary until it
+    /// passes.
+    #[test]
+    #[ignore]
+    fn variant_ladders_are_calibrated() {
+        let mut failures = Vec::new();
+
+        for constraint in VARIANT_CONSTRAINTS {
+            let levels = difficulty_levels(constraint, None).unwrap() as i32;
+
+            for difficulty in 1..=levels {
+                let report = calibrate(&CalibrationConfig {
+                    constraint,
+                    difficulty,
+                    trials: TRIALS,
+                    ladder_json: None
+                }).unwrap();
+                let rate = report.acceptance_rate();
+
+                if rate < NEARLY_EMPTY ||
+                        (difficulty > 1 && rate > NEARLY_ALWAYS) {
+                    failures.push(report.to_string());
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "acceptance rates outside of [{}, {}]:\n\
+            {}", NEARLY_EMPTY, NEARLY_ALWAYS, failures.join("\n"));
+    }
+}
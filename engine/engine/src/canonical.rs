@@ -0,0 +1,593 @@
+//! Canonical forms of Sudoku for duplicate detection. Two Sudoku that are
+//! equivalent under the transformations their constraint allows (see
+//! [crate::transform]) have the same canonical form, which is the smallest
+//! equivalent Sudoku when comparing cells in row-major order, with empty cells
+//! being smallest, and then the constraint.
+//!
+//! The considered transformations are
+//!
+//! * for classic Sudoku, all permutations of bands, stacks, and the rows and
+//! columns within them, transposition, and relabelling,
+//! * for diagonals Sudoku, those which permute rows and columns symmetrically,
+//! * for knight's move, king's move, and sandwich Sudoku, rotations and
+//! reflections, together with relabelling, which is restricted to swapping
+//! the largest digit with 1 for sandwich Sudoku.
+//!
+//! Enumerating all permutations of bands and rows is only feasible up to 9x9,
+//! so larger Sudoku use only reversals of the row and column order instead.
+
+use crate::constraint::AnyConstraint;
+use crate::daily;
+use crate::transform::{self, Transform};
+
+use std::cmp::Ordering;
+
+use sudoku_variants::Sudoku;
+
+/// The maximum number of row or column orders that are enumerated. Above
+/// this, only the identity and reversal are used.
+const MAX_LINE_ORDERS: usize = 1296;
+
+/// An order of the rows or columns of a Sudoku which keeps the blocks
+/// intact, i.e. a permutation of the bands (or stacks) together with a
+/// permutation of the lines within each of them.
+#[derive(Clone)]
+struct LineOrder {
+
+    /// The old indices of the chunks in their new order.
+    chunks: Vec<usize>,
+
+    /// For each chunk, given by its old index, the old indices of its lines
+    /// in their new order.
+    within: Vec<Vec<usize>>,
+
+    /// The old indices of all lines in their new order.
+    flat: Vec<usize>
+}
+
+fn permutations(len: usize) -> Vec<Vec<usize>> {
+    if len == 0 {
+        return vec![Vec::new()];
+    }
+
+    permutations(len - 1).into_iter()
+        .flat_map(|permutation| (0..len).map(move |position| {
+            let mut permutation = permutation.clone();
+            permutation.insert(position, len - 1);
+            permutation
+        }))
+        .collect()
+}
+
+impl LineOrder {
+
+    fn new(chunks: Vec<usize>, within: Vec<Vec<usize>>) -> LineOrder {
+        let len = within.first().map(Vec::len).unwrap_or(0);
+        let flat = chunks.iter()
+            .flat_map(|&chunk| within[chunk].iter()
+                .map(move |&line| chunk * len + line))
+            .collect();
+
+        LineOrder {
+            chunks,
+            within,
+            flat
+        }
+    }
+
+    /// Gets the order that corresponds to the given order of all lines, or
+    /// `None` if it does not keep the chunks intact.
+    fn from_flat(flat: &[usize], len: usize) -> Option<LineOrder> {
+        let count = flat.len() / len;
+        let chunks = (0..count)
+            .map(|position| flat[position * len] / len)
+            .collect::<Vec<_>>();
+        let mut within = vec![Vec::new(); count];
+
+        for (position, &chunk) in chunks.iter().enumerate() {
+            let lines = &flat[position * len..(position + 1) * len];
+
+            if lines.iter().any(|line| line / len != chunk) {
+                return None;
+            }
+
+            within[chunk] = lines.iter().map(|line| line % len).collect();
+        }
+
+        Some(LineOrder::new(chunks, within))
+    }
+
+    fn identity(count: usize, len: usize) -> LineOrder {
+        LineOrder::new((0..count).collect(), vec![(0..len).collect(); count])
+    }
+
+    fn reversed(count: usize, len: usize) -> LineOrder {
+        LineOrder::new((0..count).rev().collect(),
+            vec![(0..len).rev().collect(); count])
+    }
+
+    /// Gets all orders of `count` chunks of `len` lines each, or only the
+    /// identity and reversal if there are more than [MAX_LINE_ORDERS].
+    fn all(count: usize, len: usize) -> Vec<LineOrder> {
+        let chunk_orders = permutations(count);
+        let line_orders = permutations(len);
+        let total = (0..count)
+            .try_fold(chunk_orders.len(),
+                |total, _| total.checked_mul(line_orders.len()));
+
+        if total.map(|total| total > MAX_LINE_ORDERS).unwrap_or(true) {
+            return vec![
+                LineOrder::identity(count, len),
+                LineOrder::reversed(count, len)
+            ];
+        }
+
+        let mut withins = vec![Vec::new()];
+
+        for _ in 0..count {
+            withins = withins.into_iter()
+                .flat_map(|within: Vec<Vec<usize>>| line_orders.iter()
+                    .map(move |order| {
+                        let mut within = within.clone();
+                        within.push(order.clone());
+                        within
+                    }))
+                .collect();
+        }
+
+        chunk_orders.iter()
+            .flat_map(|chunks| withins.iter()
+                .map(move |within| LineOrder::new(chunks.clone(),
+                    within.clone())))
+            .collect()
+    }
+}
+
+/// A rearrangement of the cells of a Sudoku, where the cell in row `r` and
+/// column `c` is taken from row `rows.flat[r]` and column `columns.flat[c]`
+/// of the (possibly transposed) original.
+struct Candidate<'a> {
+    transpose: bool,
+    rows: &'a LineOrder,
+    columns: &'a LineOrder
+}
+
+impl Candidate<'_> {
+
+    /// Gets the equivalent [Transform], followed by the given relabelling.
+    fn to_transform(&self, relabel: Vec<usize>) -> Transform {
+        let mut parts = Vec::new();
+
+        if self.transpose {
+            parts.push(Transform::Transpose);
+        }
+
+        for (band, order) in self.rows.within.iter().enumerate() {
+            parts.push(Transform::PermuteRows {
+                band,
+                order: order.clone()
+            });
+        }
+
+        parts.push(Transform::PermuteBands(self.rows.chunks.clone()));
+
+        for (stack, order) in self.columns.within.iter().enumerate() {
+            parts.push(Transform::PermuteColumns {
+                stack,
+                order: order.clone()
+            });
+        }
+
+        parts.push(Transform::PermuteStacks(self.columns.chunks.clone()));
+        parts.push(Transform::Relabel(relabel));
+        Transform::Composite(parts)
+    }
+}
+
+/// The properties of a constraint that determine the allowed
+/// transformations.
+#[derive(Default)]
+struct Features {
+    diagonals: bool,
+    relations: bool,
+    sandwich: bool
+}
+
+impl Features {
+    fn of(constraint: &AnyConstraint) -> Features {
+        let mut features = Features::default();
+        features.add(constraint);
+        features
+    }
+
+    fn add(&mut self, constraint: &AnyConstraint) {
+        match constraint {
+            AnyConstraint::Default => { },
+            AnyConstraint::Diagonals => self.diagonals = true,
+            AnyConstraint::KnightsMove | AnyConstraint::KingsMove =>
+                self.relations = true,
+            AnyConstraint::Sandwich(_) => self.sandwich = true,
+            AnyConstraint::Composite(cs) =>
+                cs.iter().for_each(|c| self.add(c))
+        }
+    }
+}
+
+fn rank(constraint: &AnyConstraint) -> usize {
+    match constraint {
+        AnyConstraint::Default => 0,
+        AnyConstraint::Diagonals => 1,
+        AnyConstraint::KnightsMove => 2,
+        AnyConstraint::KingsMove => 3,
+        AnyConstraint::Sandwich(_) => 4,
+        AnyConstraint::Composite(_) => 5
+    }
+}
+
+fn flatten(constraint: &AnyConstraint, constraints: &mut Vec<AnyConstraint>) {
+    match constraint {
+        AnyConstraint::Composite(cs) =>
+            cs.iter().for_each(|c| flatten(c, constraints)),
+        c => constraints.push(c.clone())
+    }
+}
+
+/// Brings the given constraint into a normal form, in which composite
+/// constraints are flat, their components are sorted, duplicates are
+/// removed, and a composite constraint with one component is replaced by it.
+fn normalize_constraint(constraint: &AnyConstraint) -> AnyConstraint {
+    let mut constraints = Vec::new();
+    flatten(constraint, &mut constraints);
+    constraints.sort_by_key(rank);
+    constraints.dedup_by(|a, b| rank(a) == rank(b) && rank(a) < 4);
+
+    if constraints.len() == 1 {
+        constraints.pop().unwrap()
+    }
+    else {
+        AnyConstraint::Composite(constraints)
+    }
+}
+
+/// Calls `visit` with every rearrangement that may preserve a constraint
+/// with the given features. Those for diagonals and relations are only
+/// candidates and must still be checked. The rearrangements are not collected
+/// since there are millions of them for classic 9x9 Sudoku.
+fn for_each_candidate<F>(features: &Features, block_width: usize,
+    block_height: usize, mut visit: F)
+where
+    F: FnMut(&Candidate)
+{
+    let transposes: &[bool] = if block_width == block_height {
+        &[false, true]
+    }
+    else {
+        &[false]
+    };
+    let (row_orders, column_orders) = if features.relations ||
+            features.sandwich {
+        (vec![
+            LineOrder::identity(block_width, block_height),
+            LineOrder::reversed(block_width, block_height)
+        ], vec![
+            LineOrder::identity(block_height, block_width),
+            LineOrder::reversed(block_height, block_width)
+        ])
+    }
+    else {
+        (LineOrder::all(block_width, block_height),
+            LineOrder::all(block_height, block_width))
+    };
+
+    if features.diagonals {
+
+        // Both diagonals must be preserved, so every column goes where its
+        // row goes, or where the mirrored row goes.
+
+        let last = block_width * block_height - 1;
+
+        for rows in &row_orders {
+            let complement = rows.flat.iter()
+                .map(|line| last - line)
+                .collect::<Vec<_>>();
+            let column_orders = [&rows.flat, &complement].into_iter()
+                .filter_map(|flat| LineOrder::from_flat(flat, block_width))
+                .collect::<Vec<_>>();
+
+            for &transpose in transposes {
+                for columns in &column_orders {
+                    visit(&Candidate {
+                        transpose,
+                        rows,
+                        columns
+                    });
+                }
+            }
+        }
+
+        return;
+    }
+
+    for &transpose in transposes {
+        for rows in &row_orders {
+            for columns in &column_orders {
+                visit(&Candidate {
+                    transpose,
+                    rows,
+                    columns
+                });
+            }
+        }
+    }
+}
+
+/// Writes the cells of the given candidate in row-major order into `key`,
+/// where empty cells are 0, and compares them to `best`. The comparison stops
+/// as soon as the key is known to be greater, in which case `key` is
+/// incomplete. If `best` is empty, the key is always considered less.
+///
+/// `labels` receives the relabelling of every digit, where 0 marks digits
+/// that do not occur if relabelling is free.
+fn evaluate(sources: &[Vec<usize>; 2], size: usize, candidate: &Candidate,
+        relabel: Option<&[usize]>, best: &[usize], key: &mut Vec<usize>,
+        labels: &mut Vec<usize>) -> Ordering {
+    let source = &sources[candidate.transpose as usize];
+    let mut ordering = if best.is_empty() {
+        Ordering::Less
+    }
+    else {
+        Ordering::Equal
+    };
+    let mut next_label = 1;
+    key.clear();
+    labels.clear();
+
+    match relabel {
+        Some(relabel) => labels.extend_from_slice(relabel),
+        None => labels.resize(size + 1, 0)
+    }
+
+    for &row in &candidate.rows.flat {
+        for &column in &candidate.columns.flat {
+            let digit = source[row * size + column];
+            let value = if digit == 0 {
+                0
+            }
+            else {
+                if labels[digit] == 0 {
+                    labels[digit] = next_label;
+                    next_label += 1;
+                }
+
+                labels[digit]
+            };
+
+            if ordering == Ordering::Equal {
+                ordering = value.cmp(&best[key.len()]);
+
+                if ordering == Ordering::Greater {
+                    return ordering;
+                }
+            }
+
+            key.push(value);
+        }
+    }
+
+    ordering
+}
+
+/// Completes a free relabelling computed by [evaluate] by assigning the
+/// remaining labels to the digits that do not occur, in ascending order.
+/// Returns the relabelling as expected by [Transform::Relabel].
+fn complete_labels(labels: &[usize], size: usize) -> Vec<usize> {
+    let mut next_label = labels.iter().max().copied().unwrap_or(0) + 1;
+
+    (1..=size)
+        .map(|digit| {
+            if labels[digit] == 0 {
+                next_label += 1;
+                next_label - 1
+            }
+            else {
+                labels[digit]
+            }
+        })
+        .collect()
+}
+
+/// Computes the canonical form of the given Sudoku, i.e. the smallest Sudoku
+/// that is equivalent under the transformations its constraint allows. The
+/// constraint is brought into a normal form as well, in which composite
+/// constraints are flat and sorted.
+pub(crate) fn canonicalize(sudoku: &Sudoku<AnyConstraint>)
+        -> Sudoku<AnyConstraint> {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let constraint = normalize_constraint(sudoku.constraint());
+    let sudoku = Sudoku::new_with_grid(grid.clone(), constraint);
+    let features = Features::of(sudoku.constraint());
+    let cell = |column: usize, row: usize|
+        grid.get_cell(column, row).unwrap().unwrap_or(0);
+    let sources: [Vec<usize>; 2] = [
+        (0..size * size).map(|i| cell(i % size, i / size)).collect(),
+        (0..size * size).map(|i| cell(i / size, i % size)).collect()
+    ];
+    let relabels = if features.sandwich {
+
+        // Sandwich clues only stay valid if the crusts are 1 and the largest
+        // digit, so these may only be swapped.
+
+        let identity = (0..=size).collect::<Vec<_>>();
+        let mut swapped = identity.clone();
+        swapped.swap(1, size);
+        vec![Some(identity), Some(swapped)]
+    }
+    else {
+        vec![None]
+    };
+    let check = features.diagonals || features.relations;
+    let mut best = Vec::new();
+    let mut best_transforms = Vec::new();
+    let mut key = Vec::new();
+    let mut labels = Vec::new();
+
+    for_each_candidate(&features, grid.block_width(), grid.block_height(),
+            |candidate| {
+        if check {
+            let transformation = candidate.to_transform((1..=size).collect());
+
+            if transform::transform(&sudoku, &transformation).is_err() {
+                return;
+            }
+        }
+
+        for relabel in &relabels {
+            let relabel = relabel.as_deref();
+            let ordering = evaluate(&sources, size, candidate, relabel,
+                &best, &mut key, &mut labels);
+
+            // Ties only matter if the constraint has clues that differ
+            // between equally small grids.
+
+            if ordering == Ordering::Less {
+                best.clone_from(&key);
+                best_transforms.clear();
+            }
+
+            if ordering == Ordering::Less ||
+                    (ordering == Ordering::Equal && features.sandwich) {
+                let relabel = match relabel {
+                    Some(relabel) => relabel[1..].to_vec(),
+                    None => complete_labels(&labels, size)
+                };
+                best_transforms.push(candidate.to_transform(relabel));
+            }
+        }
+    });
+
+    best_transforms.iter()
+        .map(|t| transform::transform(&sudoku, t).unwrap())
+        .min_by_key(|s| serde_json::to_string(s.constraint()).unwrap())
+        .unwrap()
+}
+
+/// Computes a stable fingerprint of the given Sudoku, which is the same for
+/// all equivalent Sudoku. It is the 64-bit FNV-1a hash of the JSON code of
+/// the canonical form (see [canonicalize]), so it is identical on every
+/// platform.
+pub(crate) fn fingerprint(sudoku: &Sudoku<AnyConstraint>) -> u64 {
+    let canonical = canonicalize(sudoku);
+    daily::fnv1a(serde_json::to_string(&canonical).unwrap().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::{assert_same_sudoku, sandwich, sudoku};
+
+    use rand::SeedableRng;
+
+    use rand_chacha::ChaCha8Rng;
+
+    fn assert_same_fingerprint(original: &Sudoku<AnyConstraint>,
+            transformed: &Sudoku<AnyConstraint>) {
+        assert_same_sudoku(&canonicalize(original), &canonicalize(transformed));
+        assert_eq!(fingerprint(original), fingerprint(transformed));
+    }
+
+    #[test]
+    fn random_transforms_share_fingerprint() {
+        let constraints = vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            AnyConstraint::KnightsMove,
+            AnyConstraint::KingsMove,
+            sandwich(9),
+            AnyConstraint::Composite(vec![
+                AnyConstraint::Diagonals,
+                AnyConstraint::KingsMove
+            ])
+        ];
+        let mut rng = ChaCha8Rng::seed_from_u64(47);
+
+        for constraint in constraints {
+            let original = sudoku(3, 3, constraint);
+
+            for _ in 0..3 {
+                let transformed =
+                    transform::random_transform(&original, &mut rng);
+                assert_same_fingerprint(&original, &transformed);
+            }
+        }
+    }
+
+    #[test]
+    fn specific_transforms_share_fingerprint() {
+        let original = sudoku(3, 3, AnyConstraint::Default);
+        let transforms = vec![
+            Transform::Transpose,
+            Transform::Rotate(1),
+            Transform::ReflectVertically,
+            Transform::PermuteBands(vec![2, 0, 1]),
+            Transform::PermuteColumns {
+                stack: 1,
+                order: vec![1, 2, 0]
+            },
+            Transform::Relabel(vec![9, 8, 7, 6, 5, 4, 3, 2, 1])
+        ];
+
+        for t in &transforms {
+            let transformed = transform::transform(&original, t).unwrap();
+            assert_same_fingerprint(&original, &transformed);
+        }
+
+        let composite = Transform::Composite(transforms);
+        let transformed = transform::transform(&original, &composite).unwrap();
+        assert_same_fingerprint(&original, &transformed);
+    }
+
+    #[test]
+    fn non_square_blocks_share_fingerprint() {
+        let original = sudoku(3, 2, AnyConstraint::Default);
+        let transformed = transform::transform(&original,
+            &Transform::Composite(vec![
+                Transform::ReflectHorizontally,
+                Transform::PermuteRows {
+                    band: 2,
+                    order: vec![1, 0]
+                },
+                Transform::Relabel(vec![2, 3, 4, 5, 6, 1])
+            ])).unwrap();
+
+        assert_same_fingerprint(&original, &transformed);
+    }
+
+    #[test]
+    fn composite_order_does_not_matter() {
+        let first = sudoku(3, 3, AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals
+        ]));
+        let second = sudoku(3, 3, AnyConstraint::Composite(vec![
+            AnyConstraint::Diagonals,
+            AnyConstraint::Composite(vec![AnyConstraint::Default])
+        ]));
+
+        assert_same_fingerprint(&first, &second);
+    }
+
+    #[test]
+    fn different_sudoku_differ() {
+        let original = sudoku(3, 3, AnyConstraint::Default);
+        let mut grid = original.grid().clone();
+        grid.set_cell(4, 4, 5).unwrap();
+        let extended = Sudoku::new_with_grid(grid, AnyConstraint::Default);
+        let diagonals = sudoku(3, 3, AnyConstraint::Diagonals);
+
+        assert_ne!(fingerprint(&original), fingerprint(&extended));
+        assert_ne!(fingerprint(&original), fingerprint(&diagonals));
+    }
+}
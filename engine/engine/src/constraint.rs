@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use sudoku_variants::SudokuGrid;
+use sudoku_variants::{Sudoku, SudokuGrid};
 use sudoku_variants::constraint::{
     CompositeConstraint,
     Constraint,
@@ -17,7 +17,7 @@ use sudoku_variants::constraint::sandwich::SandwichReduction;
 /// An enumeration that multiplexes all different reduction types of
 /// constraints that are used in the app. This is the reduction type of the
 /// [AnyConstraint] type (see [Constraint::Reduction]).
-pub(crate) enum AnyReduction {
+pub enum AnyReduction {
 
     /// A reduction of the [AnyConstraint::Sandwich] variant.
     Sandwich(SandwichReduction),
@@ -37,7 +37,7 @@ pub(crate) enum AnyReduction {
 /// An enumeration that multiplexes all different revert info types of
 /// constraints that are used in the app. This is the revert info type of the
 /// [AnyConstraint] type (see [Constraint::RevertInfo]).
-pub(crate) enum AnyRevertInfo {
+pub enum AnyRevertInfo {
 
     /// A revert info of the [AnyConstraint::Sandwich] variant.
     Sandwich(usize),
@@ -64,7 +64,7 @@ pub(crate) enum AnyRevertInfo {
 /// [From] trait.
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(tag = "type", content = "value")]
-pub(crate) enum AnyConstraint {
+pub enum AnyConstraint {
 
     /// Represents a [DefaultConstraint].
     #[serde(rename = "default")]
@@ -313,3 +313,73 @@ where
         AnyConstraint::Composite(constraints)
     }
 }
+
+/// Converts a Sudoku with any supported statically-typed constraint into one
+/// with the equivalent [AnyConstraint].
+pub(crate) fn into_any_sudoku<C>(sudoku: Sudoku<C>) -> Sudoku<AnyConstraint>
+where
+    C: Constraint + Clone + Into<AnyConstraint>
+{
+    let (grid, constraint) = sudoku.into_raw_parts();
+    let constraint: AnyConstraint = constraint.into();
+    Sudoku::new_with_grid(grid, constraint)
+}
+
+/// The clues of a [SandwichConstraint], where `None` denotes a missing clue.
+/// This mirrors the serialized form of the constraint, which is how the clues
+/// are read and written.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct SandwichClues {
+
+    /// The clues of all columns from left to right.
+    pub(crate) columns: Vec<Option<usize>>,
+
+    /// The clues of all rows from top to bottom.
+    pub(crate) rows: Vec<Option<usize>>
+}
+
+impl SandwichClues {
+
+    /// Gets the clues of the given sandwich constraint.
+    pub(crate) fn of(constraint: &SandwichConstraint) -> SandwichClues {
+        serde_json::from_value(serde_json::to_value(constraint).unwrap())
+            .unwrap()
+    }
+
+    /// Creates a sandwich constraint with these clues.
+    pub(crate) fn into_constraint(self) -> SandwichConstraint {
+        serde_json::from_value(serde_json::to_value(self).unwrap()).unwrap()
+    }
+}
+
+/// Fills in all sandwich clues of the given constraint according to the given
+/// full grid, including those of sandwich constraints nested in composite
+/// constraints. All other constraints are kept as they are.
+pub(crate) fn complete_constraint(constraint: AnyConstraint,
+        grid: &SudokuGrid) -> AnyConstraint {
+    match constraint {
+        AnyConstraint::Sandwich(_) =>
+            AnyConstraint::Sandwich(SandwichConstraint::new_full(grid)),
+        AnyConstraint::Composite(cs) =>
+            AnyConstraint::Composite(cs.into_iter()
+                .map(|c| complete_constraint(c, grid))
+                .collect()),
+        c => c
+    }
+}
+
+/// Removes all sandwich constraints from the given constraint, including those
+/// nested in composite constraints, since their clues are computed from the
+/// full grid by [complete_constraint]. The result is the constraint a full
+/// grid must satisfy before the sandwich clues are filled in.
+pub(crate) fn strip_sandwiches(constraint: &AnyConstraint) -> AnyConstraint {
+    match constraint {
+        AnyConstraint::Sandwich(_) => AnyConstraint::Composite(Vec::new()),
+        AnyConstraint::Composite(cs) =>
+            AnyConstraint::Composite(cs.iter()
+                .filter(|c| !matches!(c, AnyConstraint::Sandwich(_)))
+                .map(strip_sandwiches)
+                .collect()),
+        c => c.clone()
+    }
+}
@@ -0,0 +1,225 @@
+use crate::constraint::AnyConstraint;
+use crate::gen_metadata::GenMetadata;
+use crate::generate::{self, Seed};
+use crate::schema;
+
+use serde::Serialize;
+
+use sudoku_variants::Sudoku;
+
+/// The version of the algorithm which derives daily puzzles from their date.
+/// It is part of the seed and of every [DailyPuzzle], and must be incremented
+/// whenever a change to the engine alters the puzzle generated for any date,
+/// so clients can tell whether they agree on the daily puzzle.
+pub(crate) const DAILY_ALGORITHM_VERSION: u32 = 2;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS,
+        |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// The daily puzzle for some date, constraint, and difficulty. To be
+/// serialized for return.
+#[derive(Serialize)]
+pub(crate) struct DailyPuzzle {
+
+    /// The date in ISO 8601 format, i.e. `YYYY-MM-DD`.
+    date: String,
+
+    /// The constraint identifier, see the crate-level documentation.
+    constraint: i32,
+
+    /// The difficulty on a scale from 1 to 5 (both inclusive).
+    difficulty: i32,
+
+    /// The [DAILY_ALGORITHM_VERSION] with which the puzzle was derived.
+    algorithm_version: u32,
+
+    /// The puzzle itself.
+    #[serde(serialize_with = "schema::serialize")]
+    sudoku: Sudoku<AnyConstraint>,
+
+    /// Information about how the puzzle was generated. Unlike the puzzle, the
+    /// timing information differs between devices.
+    metadata: GenMetadata
+}
+
+/// The reasons why no daily puzzle can be generated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum DailyError {
+
+    /// The date does not exist or its year is not from 1 to 9999 (both
+    /// inclusive).
+    InvalidDate,
+
+    /// The source of full Sudoku could not provide any before a Sudoku of the
+    /// requested difficulty was found.
+    Exhausted
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Gets the number of days of the given month, from 1 to 12 (both inclusive),
+/// of the given year in the proleptic Gregorian calendar. Returns `None` if
+/// the month is invalid.
+fn days_in_month(year: i32, month: i32) -> Option<i32> {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if is_leap_year(year) => Some(29),
+        2 => Some(28),
+        _ => None
+    }
+}
+
+/// Indicates whether the given year, month, and day form a date which can be
+/// written in the `YYYY-MM-DD` format, i.e. whose year is from 1 to 9999
+/// (both inclusive).
+fn is_valid_date(year: i32, month: i32, day: i32) -> bool {
+    (1..=9999).contains(&year) && days_in_month(year, month)
+        .is_some_and(|days| (1..=days).contains(&day))
+}
+
+/// Derives the seed for the daily puzzle from its identifying data. The seed
+/// is made up of four FNV-1a hashes of the data, each with a different salt.
+fn daily_seed(date: &str, constraint: i32, difficulty: i32) -> Seed {
+    let mut seed = [0; 32];
+
+    for (salt, chunk) in seed.chunks_mut(8).enumerate() {
+        let data = format!("sudoverse-daily/{}/{}/{}/{}/{}",
+            DAILY_ALGORITHM_VERSION, salt, date, constraint, difficulty);
+        chunk.copy_from_slice(&fnv1a(data.as_bytes()).to_le_bytes());
+    }
+
+    seed
+}
+
+/// Generates the daily puzzle for the given date, constraint, and difficulty.
+/// The puzzle is derived deterministically from these parameters, so every
+/// engine with the same [DAILY_ALGORITHM_VERSION] yields the same puzzle on
+/// every platform.
+///
+/// # Errors
+///
+/// * [DailyError::InvalidDate] if the date does not exist, e.g. February 29
+/// of a year that is not a leap year, or its year is not from 1 to 9999 (both
+/// inclusive).
+/// * [DailyError::Exhausted] if no Sudoku could be generated.
+///
+/// # Panics
+///
+/// If the constraint identifier or difficulty is invalid.
+pub(crate) fn daily(year: i32, month: i32, day: i32, constraint: i32,
+        difficulty: i32) -> Result<DailyPuzzle, DailyError> {
+    if !is_valid_date(year, month, day) {
+        return Err(DailyError::InvalidDate);
+    }
+
+    let date = format!("{:04}-{:02}-{:02}", year, month, day);
+    let seed = daily_seed(&date, constraint, difficulty);
+    let generated = generate::generation_task(constraint, difficulty, 3, 3)
+        .run_seeded(seed)
+        .map_err(|_| DailyError::Exhausted)?;
+
+    Ok(DailyPuzzle {
+        date,
+        constraint,
+        difficulty,
+        algorithm_version: DAILY_ALGORITHM_VERSION,
+        sudoku: generated.sudoku,
+        metadata: generated.metadata
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::generate::portable;
+    use crate::line_format;
+    use crate::sync::CancelHandle;
+
+    use rand::SeedableRng;
+
+    use rand_chacha::ChaCha8Rng;
+
+    use sudoku_variants::constraint::DefaultConstraint;
+
+    /// The seed of the classic daily puzzle of difficulty 1 for 2024-01-01.
+    const GOLDEN_SEED: Seed = [
+        0xcb, 0x0a, 0xb9, 0x79, 0xa4, 0x65, 0x37, 0x84,
+        0x56, 0x85, 0xaa, 0xee, 0x3f, 0x80, 0xb9, 0x29,
+        0x11, 0x93, 0x89, 0x72, 0x96, 0xab, 0xcb, 0x02,
+        0xbc, 0xa2, 0xab, 0x8f, 0xd1, 0xcc, 0x2a, 0x16
+    ];
+
+    /// The first full grid that is generated from [GOLDEN_SEED], from which
+    /// the first candidate for the daily puzzle is reduced.
+    const GOLDEN_FULL_GRID: &str = "829346571341257698756819234683421957294\
+        675813175938426512764389967583142438192765";
+
+    #[test]
+    fn month_lengths() {
+        assert!(is_valid_date(2026, 1, 31));
+        assert!(is_valid_date(2026, 4, 30));
+        assert!(!is_valid_date(2026, 4, 31));
+        assert!(!is_valid_date(2026, 2, 31));
+        assert!(!is_valid_date(2026, 13, 1));
+        assert!(!is_valid_date(2026, 0, 1));
+        assert!(!is_valid_date(2026, 6, 0));
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_valid_date(2024, 2, 29));
+        assert!(is_valid_date(2000, 2, 29));
+        assert!(!is_valid_date(2026, 2, 29));
+        assert!(!is_valid_date(1900, 2, 29));
+    }
+
+    #[test]
+    fn year_range() {
+        assert!(is_valid_date(1, 1, 1));
+        assert!(is_valid_date(9999, 12, 31));
+        assert!(!is_valid_date(0, 1, 1));
+        assert!(!is_valid_date(10000, 1, 1));
+    }
+
+    #[test]
+    fn golden_seed() {
+        assert_eq!(GOLDEN_SEED, daily_seed("2024-01-01", 0, 1));
+    }
+
+    #[test]
+    fn golden_full_grid() {
+        let mut rng = ChaCha8Rng::from_seed(GOLDEN_SEED);
+        let sudoku = portable::generate(3, 3, DefaultConstraint, &mut rng,
+            &CancelHandle::new()).unwrap().unwrap();
+        let (grid, _) = sudoku.into_raw_parts();
+        let sudoku = Sudoku::new_with_grid(grid, AnyConstraint::Default);
+
+        assert_eq!(GOLDEN_FULL_GRID, line_format::serialize(&sudoku));
+    }
+
+    #[test]
+    fn daily_is_deterministic() {
+        let first = daily(2024, 1, 1, 0, 1).unwrap();
+        let second = daily(2024, 1, 1, 0, 1).unwrap();
+
+        assert_eq!("2024-01-01", first.date);
+        assert_eq!(schema::to_value(&first.sudoku),
+            schema::to_value(&second.sudoku));
+    }
+
+    #[test]
+    fn daily_invalid_date() {
+        assert_eq!(Some(DailyError::InvalidDate),
+            daily(2023, 2, 29, 0, 1).err());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::daily::{DailyError, DailyPuzzle};
+
+use serde::Serialize;
+
+/// The response to a `daily` call to the engine. To be serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum DailyResponse {
+
+    /// Indicates that the daily puzzle was generated successfully. It is
+    /// provided together with its date and algorithm version.
+    #[serde(rename = "ok")]
+    Ok(DailyPuzzle),
+
+    /// Indicates that the provided date does not exist.
+    #[serde(rename = "invalid-date")]
+    InvalidDate,
+
+    /// Indicates that no Sudoku of the requested difficulty could be
+    /// generated.
+    #[serde(rename = "exhausted")]
+    Exhausted
+}
+
+impl DailyResponse {
+
+    /// Wraps the daily puzzle or the reason why there is none in a daily
+    /// response.
+    pub(crate) fn from_result(result: Result<DailyPuzzle, DailyError>)
+            -> DailyResponse {
+        match result {
+            Ok(puzzle) => DailyResponse::Ok(puzzle),
+            Err(DailyError::InvalidDate) => DailyResponse::InvalidDate,
+            Err(DailyError::Exhausted) => DailyResponse::Exhausted
+        }
+    }
+}
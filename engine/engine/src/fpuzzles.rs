@@ -0,0 +1,507 @@
+//! Conversion between Sudoku and the JSON format of
+//! [f-puzzles](https://f-puzzles.com), in which most variant puzzles are
+//! published. A puzzle is an object with its `size`, a `grid` of cells, each
+//! of which may hold a `value` that is `given`, and one entry per constraint,
+//! such as `"antiknight": true` or a list of `sandwichsum` clues placed
+//! outside the grid (`R0C3` above the third column, `R3C0` left of the third
+//! row).
+//!
+//! Supported are givens, both diagonals (`diagonal+` and `diagonal-`
+//! together), `antiknight`, `antiking`, and `sandwichsum`. Importing a puzzle
+//! that uses anything else fails with [FPuzzlesError::Unsupported], listing
+//! the features, so that no constraint is silently dropped.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+
+use serde::Serialize;
+
+use serde_json::{Map, Value};
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+
+const MIN_SIZE: usize = 3;
+const MAX_SIZE: usize = 16;
+
+/// The entries of an f-puzzles object that are converted.
+const SUPPORTED: [&str; 7] = [
+    "size", "grid", "diagonal+", "diagonal-", "antiknight", "antiking",
+    "sandwichsum"
+];
+
+/// The entries of an f-puzzles object that do not affect the puzzle itself
+/// and are thus ignored. Apart from the metadata, these are the settings of
+/// the f-puzzles user interface, which it writes into every export.
+const IGNORED: [&str; 8] = [
+    "title", "author", "ruleset", "solution", "disabledlogic",
+    "highlightConflicts", "highlightSeenCells", "truecandidatesoptions"
+];
+
+/// The feature reported for cells whose region differs from the default.
+const IRREGULAR_REGIONS: &str = "regions";
+
+/// The feature reported for cells with given pencil marks.
+const GIVEN_PENCIL_MARKS: &str = "givenPencilMarks";
+
+/// The reasons why a puzzle cannot be converted.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum FPuzzlesError {
+
+    /// The input is not a JSON object. The message of the JSON parser is
+    /// provided.
+    #[serde(rename = "invalid-json")]
+    InvalidJson(String),
+
+    /// The size is missing or not supported by f-puzzles, which allows sizes
+    /// from 3 to 16.
+    #[serde(rename = "invalid-size")]
+    InvalidSize(Option<usize>),
+
+    /// The grid is missing or does not have the stated size.
+    #[serde(rename = "invalid-grid")]
+    InvalidGrid,
+
+    /// A given is not a digit between 1 and the size.
+    #[serde(rename = "invalid-given")]
+    InvalidGiven {
+
+        /// The 0-based column of the given.
+        column: usize,
+
+        /// The 0-based row of the given.
+        row: usize
+    },
+
+    /// A sandwich sum is not placed outside the top or left edge of the grid,
+    /// or its value is not a non-negative number. The f-puzzles cell
+    /// reference of the clue is provided.
+    #[serde(rename = "invalid-sandwich-sum")]
+    InvalidSandwichSum(String),
+
+    /// The puzzle uses features that are not supported. Their f-puzzles names
+    /// are provided, with `regions` denoting irregular regions.
+    #[serde(rename = "unsupported")]
+    Unsupported(Vec<String>)
+}
+
+impl Display for FPuzzlesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FPuzzlesError::InvalidJson(message) =>
+                write!(f, "invalid JSON: {}", message),
+            FPuzzlesError::InvalidSize(Some(size)) =>
+                write!(f, "unsupported size {}", size),
+            FPuzzlesError::InvalidSize(None) => write!(f, "missing size"),
+            FPuzzlesError::InvalidGrid =>
+                write!(f, "grid missing or of wrong size"),
+            FPuzzlesError::InvalidGiven { column, row } =>
+                write!(f, "invalid given in row {}, column {}", row + 1,
+                    column + 1),
+            FPuzzlesError::InvalidSandwichSum(cell) =>
+                write!(f, "invalid sandwich sum at {}", cell),
+            FPuzzlesError::Unsupported(features) =>
+                write!(f, "unsupported features: {}", features.join(", "))
+        }
+    }
+}
+
+impl Error for FPuzzlesError { }
+
+/// Gets the block width and height f-puzzles uses for a grid of the given
+/// size, i.e. the blocks are as high as the largest divisor of the size that
+/// does not exceed its square root.
+pub(crate) fn default_dimensions(size: usize) -> (usize, usize) {
+    let block_height = (1..=size)
+        .take_while(|height| height * height <= size)
+        .filter(|height| size % height == 0)
+        .last()
+        .unwrap_or(1);
+    (size / block_height, block_height)
+}
+
+fn region(column: usize, row: usize, block_width: usize, block_height: usize,
+        size: usize) -> usize {
+    (row / block_height) * (size / block_width) + column / block_width
+}
+
+/// Indicates whether an entry of an f-puzzles object is in use, which is not
+/// the case for `false`, `null`, and empty strings, lists, and objects.
+pub(crate) fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => false,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        _ => true
+    }
+}
+
+fn flag(puzzle: &Map<String, Value>, key: &str) -> bool {
+    puzzle.get(key).map(is_present).unwrap_or(false)
+}
+
+/// Parses an f-puzzles cell reference such as `R1C2` into 1-based row and
+/// column.
+fn parse_cell_ref(cell: &str) -> Option<(usize, usize)> {
+    let cell = cell.to_ascii_uppercase();
+    let rest = cell.strip_prefix('R')?;
+    let (row, column) = rest.split_once('C')?;
+    Some((row.parse().ok()?, column.parse().ok()?))
+}
+
+/// Parses a non-negative integer given as a JSON number or string.
+pub(crate) fn parse_sum(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(n) => n.as_u64().map(|n| n as usize),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None
+    }
+}
+
+fn parse_sandwich_sums(sums: &Value, size: usize)
+        -> Result<SandwichClues, FPuzzlesError> {
+    let mut clues = SandwichClues {
+        columns: vec![None; size],
+        rows: vec![None; size]
+    };
+    let sums = sums.as_array()
+        .ok_or_else(|| FPuzzlesError::InvalidSandwichSum(String::new()))?;
+
+    for sum in sums {
+        let cell = sum.get("cell").and_then(Value::as_str).unwrap_or("");
+        let invalid = || FPuzzlesError::InvalidSandwichSum(cell.to_owned());
+        let value = sum.get("value").and_then(parse_sum)
+            .ok_or_else(invalid)?;
+
+        match parse_cell_ref(cell) {
+            Some((0, column)) if (1..=size).contains(&column) =>
+                clues.columns[column - 1] = Some(value),
+            Some((row, 0)) if (1..=size).contains(&row) =>
+                clues.rows[row - 1] = Some(value),
+            _ => return Err(invalid())
+        }
+    }
+
+    Ok(clues)
+}
+
+fn parse_grid(puzzle: &Map<String, Value>, size: usize,
+        unsupported: &mut Vec<String>) -> Result<SudokuGrid, FPuzzlesError> {
+    let (block_width, block_height) = default_dimensions(size);
+    let mut grid = SudokuGrid::new(block_width, block_height)
+        .map_err(|_| FPuzzlesError::InvalidSize(Some(size)))?;
+    let rows = puzzle.get("grid")
+        .and_then(Value::as_array)
+        .filter(|rows| rows.len() == size)
+        .ok_or(FPuzzlesError::InvalidGrid)?;
+
+    for (row, cells) in rows.iter().enumerate() {
+        let cells = cells.as_array()
+            .filter(|cells| cells.len() == size)
+            .ok_or(FPuzzlesError::InvalidGrid)?;
+
+        for (column, cell) in cells.iter().enumerate() {
+            let cell = cell.as_object().ok_or(FPuzzlesError::InvalidGrid)?;
+
+            if let Some(r) = cell.get("region").and_then(Value::as_u64) {
+                let default =
+                    region(column, row, block_width, block_height, size);
+
+                if r as usize != default {
+                    unsupported.push(IRREGULAR_REGIONS.to_owned());
+                }
+            }
+
+            if flag(cell, GIVEN_PENCIL_MARKS) {
+                unsupported.push(GIVEN_PENCIL_MARKS.to_owned());
+            }
+
+            if !cell.get("given").and_then(Value::as_bool).unwrap_or(false) {
+                continue;
+            }
+
+            let invalid = || FPuzzlesError::InvalidGiven { column, row };
+            let digit = cell.get("value")
+                .and_then(Value::as_u64)
+                .ok_or_else(invalid)?;
+            grid.set_cell(column, row, digit as usize)
+                .map_err(|_| invalid())?;
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Imports a Sudoku from its f-puzzles JSON representation. Only the givens
+/// are imported, digits entered by a player are ignored, as are the title,
+/// author, and rules. The constraint is the default constraint combined with
+/// all supported constraints used by the puzzle.
+///
+/// # Errors
+///
+/// If the JSON does not describe an f-puzzles puzzle, or the puzzle uses
+/// features that are not supported. In the latter case, all of these
+/// features are listed in [FPuzzlesError::Unsupported].
+pub fn import(json: &str) -> Result<Sudoku<AnyConstraint>, FPuzzlesError> {
+    let puzzle: Map<String, Value> = serde_json::from_str(json)
+        .map_err(|e| FPuzzlesError::InvalidJson(e.to_string()))?;
+    let size = puzzle.get("size")
+        .and_then(Value::as_u64)
+        .ok_or(FPuzzlesError::InvalidSize(None))? as usize;
+
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(FPuzzlesError::InvalidSize(Some(size)));
+    }
+
+    let mut unsupported = puzzle.iter()
+        .filter(|(key, value)| is_present(value) &&
+            !SUPPORTED.contains(&key.as_str()) &&
+            !IGNORED.contains(&key.as_str()))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let grid = parse_grid(&puzzle, size, &mut unsupported)?;
+    let mut constraints = vec![AnyConstraint::Default];
+
+    match (flag(&puzzle, "diagonal+"), flag(&puzzle, "diagonal-")) {
+        (true, true) => constraints.push(AnyConstraint::Diagonals),
+        (true, false) => unsupported.push("diagonal+".to_owned()),
+        (false, true) => unsupported.push("diagonal-".to_owned()),
+        (false, false) => { }
+    }
+
+    if flag(&puzzle, "antiknight") {
+        constraints.push(AnyConstraint::KnightsMove);
+    }
+
+    if flag(&puzzle, "antiking") {
+        constraints.push(AnyConstraint::KingsMove);
+    }
+
+    if flag(&puzzle, "sandwichsum") {
+        let clues = parse_sandwich_sums(&puzzle["sandwichsum"], size)?;
+        constraints.push(AnyConstraint::Sandwich(clues.into_constraint()));
+    }
+
+    if !unsupported.is_empty() {
+        unsupported.sort();
+        unsupported.dedup();
+        return Err(FPuzzlesError::Unsupported(unsupported));
+    }
+
+    let constraint = if constraints.len() == 1 {
+        AnyConstraint::Default
+    }
+    else {
+        AnyConstraint::Composite(constraints)
+    };
+
+    Ok(Sudoku::new_with_grid(grid, constraint))
+}
+
+/// Adds the f-puzzles entries of the given constraint, including those nested
+/// in composite constraints, to the given puzzle object.
+fn export_constraint(constraint: &AnyConstraint,
+        puzzle: &mut Map<String, Value>) {
+    match constraint {
+        AnyConstraint::Default => { },
+        AnyConstraint::Diagonals => {
+            puzzle.insert("diagonal+".to_owned(), Value::Bool(true));
+            puzzle.insert("diagonal-".to_owned(), Value::Bool(true));
+        },
+        AnyConstraint::KnightsMove => {
+            puzzle.insert("antiknight".to_owned(), Value::Bool(true));
+        },
+        AnyConstraint::KingsMove => {
+            puzzle.insert("antiking".to_owned(), Value::Bool(true));
+        },
+        AnyConstraint::Sandwich(c) => {
+            let clues = SandwichClues::of(c);
+            let columns = clues.columns.iter().enumerate()
+                .map(|(column, &clue)| (0, column + 1, clue));
+            let rows = clues.rows.iter().enumerate()
+                .map(|(row, &clue)| (row + 1, 0, clue));
+            let sums = columns.chain(rows)
+                .filter_map(|(row, column, clue)| clue.map(|clue| {
+                    let mut sum = Map::new();
+                    sum.insert("cell".to_owned(),
+                        Value::from(format!("R{}C{}", row, column)));
+                    sum.insert("value".to_owned(),
+                        Value::from(clue.to_string()));
+                    Value::Object(sum)
+                }))
+                .collect::<Vec<_>>();
+            puzzle.insert("sandwichsum".to_owned(), Value::Array(sums));
+        },
+        AnyConstraint::Composite(cs) => {
+            for c in cs {
+                export_constraint(c, puzzle);
+            }
+        }
+    }
+}
+
+/// Exports the given Sudoku to the f-puzzles JSON format as a JSON value.
+/// Filled cells are exported as givens. If the blocks of the Sudoku differ
+/// from the default regions f-puzzles uses for its size, every cell states
+/// its region explicitly.
+///
+/// # Errors
+///
+/// If the Sudoku is smaller than 3x3 or larger than 16x16, which f-puzzles
+/// does not support.
+pub fn export_value(sudoku: &Sudoku<AnyConstraint>)
+        -> Result<Value, FPuzzlesError> {
+    let grid = sudoku.grid();
+    let size = grid.size();
+
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(FPuzzlesError::InvalidSize(Some(size)));
+    }
+
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+    let explicit_regions =
+        default_dimensions(size) != (block_width, block_height);
+    let rows = (0..size)
+        .map(|row| Value::Array((0..size)
+            .map(|column| {
+                let mut cell = Map::new();
+
+                if let Some(digit) = grid.get_cell(column, row).unwrap() {
+                    cell.insert("value".to_owned(), Value::from(digit));
+                    cell.insert("given".to_owned(), Value::Bool(true));
+                }
+
+                if explicit_regions {
+                    let region =
+                        region(column, row, block_width, block_height, size);
+                    cell.insert("region".to_owned(), Value::from(region));
+                }
+
+                Value::Object(cell)
+            })
+            .collect()))
+        .collect();
+    let mut puzzle = Map::new();
+    puzzle.insert("size".to_owned(), Value::from(size));
+    puzzle.insert("grid".to_owned(), Value::Array(rows));
+    export_constraint(sudoku.constraint(), &mut puzzle);
+
+    Ok(Value::Object(puzzle))
+}
+
+/// Exports the given Sudoku to the f-puzzles JSON format. See [export_value]
+/// for details.
+///
+/// # Errors
+///
+/// If the Sudoku is smaller than 3x3 or larger than 16x16, which f-puzzles
+/// does not support.
+pub fn export(sudoku: &Sudoku<AnyConstraint>)
+        -> Result<String, FPuzzlesError> {
+    export_value(sudoku).map(|value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const CLASSIC: &str = include_str!("../fixtures/fpuzzles/classic.json");
+    const CHESS_DIAGONALS: &str =
+        include_str!("../fixtures/fpuzzles/chess-diagonals.json");
+    const SANDWICH: &str = include_str!("../fixtures/fpuzzles/sandwich.json");
+    const SIX_BY_SIX: &str = include_str!("../fixtures/fpuzzles/6x6.json");
+    const KILLER_THERMO: &str =
+        include_str!("../fixtures/fpuzzles/killer-thermo.json");
+
+    fn givens(sudoku: &Sudoku<AnyConstraint>) -> usize {
+        let grid = sudoku.grid();
+        let size = grid.size();
+
+        (0..size)
+            .flat_map(|row| (0..size).map(move |column| (column, row)))
+            .filter(|&(column, row)|
+                grid.get_cell(column, row).unwrap().is_some())
+            .count()
+    }
+
+    fn assert_constraint(expected: AnyConstraint,
+            sudoku: &Sudoku<AnyConstraint>) {
+        assert_eq!(serde_json::to_value(expected).unwrap(),
+            serde_json::to_value(sudoku.constraint()).unwrap());
+    }
+
+    fn assert_round_trip(json: &str) {
+        let sudoku = import(json).unwrap();
+        let exported = import(&export(&sudoku).unwrap()).unwrap();
+
+        assert_eq!(serde_json::to_value(&sudoku).unwrap(),
+            serde_json::to_value(&exported).unwrap());
+    }
+
+    #[test]
+    fn classic_export() {
+        let sudoku = import(CLASSIC).unwrap();
+
+        assert_eq!(16, givens(&sudoku));
+        assert_eq!(Some(1), sudoku.grid().get_cell(0, 0).unwrap());
+        assert_eq!(None, sudoku.grid().get_cell(1, 0).unwrap());
+        assert_eq!(None, sudoku.grid().get_cell(2, 0).unwrap());
+        assert_constraint(AnyConstraint::Default, &sudoku);
+        assert_round_trip(CLASSIC);
+    }
+
+    #[test]
+    fn chess_diagonals_export() {
+        let sudoku = import(CHESS_DIAGONALS).unwrap();
+
+        assert_constraint(AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            AnyConstraint::KnightsMove,
+            AnyConstraint::KingsMove
+        ]), &sudoku);
+        assert_round_trip(CHESS_DIAGONALS);
+    }
+
+    #[test]
+    fn sandwich_export() {
+        let sudoku = import(SANDWICH).unwrap();
+        let clues = SandwichClues {
+            columns: vec![Some(35), None, Some(11), None, Some(10), None,
+                Some(19), None, Some(9)],
+            rows: vec![None, Some(0), None, Some(0), None, Some(0), None,
+                Some(0), None]
+        };
+
+        assert_eq!(2, givens(&sudoku));
+        assert_constraint(AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Sandwich(clues.into_constraint())
+        ]), &sudoku);
+        assert_round_trip(SANDWICH);
+    }
+
+    #[test]
+    fn six_by_six_export() {
+        let sudoku = import(SIX_BY_SIX).unwrap();
+
+        assert_eq!(3, sudoku.grid().block_width());
+        assert_eq!(2, sudoku.grid().block_height());
+        assert_eq!(12, givens(&sudoku));
+        assert_round_trip(SIX_BY_SIX);
+    }
+
+    #[test]
+    fn unsupported_constraints() {
+        let expected =
+            vec!["killercage".to_owned(), "thermometer".to_owned()];
+
+        assert_eq!(Err(FPuzzlesError::Unsupported(expected)),
+            import(KILLER_THERMO).map(|_| ()));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::fpuzzles::FPuzzlesError;
+
+use serde::Serialize;
+
+/// The response to an `import_fpuzzles` or `export_fpuzzles` call to the
+/// engine. To be serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum FPuzzlesResponse<T> {
+
+    /// Indicates that the conversion was successful. The imported Sudoku or
+    /// the exported f-puzzles JSON object is provided.
+    #[serde(rename = "ok")]
+    Ok(T),
+
+    /// Indicates that the conversion failed. The reason is provided.
+    #[serde(rename = "error")]
+    Error(FPuzzlesError)
+}
+
+impl<T> FPuzzlesResponse<T> {
+
+    /// Wraps the result of a conversion in an f-puzzles response.
+    pub(crate) fn from_result(result: Result<T, FPuzzlesError>)
+            -> FPuzzlesResponse<T> {
+        match result {
+            Ok(value) => FPuzzlesResponse::Ok(value),
+            Err(e) => FPuzzlesResponse::Error(e)
+        }
+    }
+}
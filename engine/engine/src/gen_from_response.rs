@@ -0,0 +1,105 @@
+use crate::constraint::AnyConstraint;
+use crate::gen_metadata::GeneratedSudoku;
+use crate::gen_response::GenResponse;
+use crate::generate::{Generated, RunError, TaskError};
+use crate::schema;
+
+use serde::{Deserialize, Serialize};
+
+use sudoku_variants::Sudoku;
+
+/// A request to generate a Sudoku from a setter-provided starting point,
+/// i.e. the argument to a `gen_from_partial` or `gen_from_solution` call to
+/// the engine. To be deserialized from the input.
+#[derive(Deserialize)]
+pub(crate) struct GenFromRequest {
+
+    /// The partial Sudoku to complete or the full solution to use.
+    #[serde(deserialize_with = "schema::deserialize")]
+    pub(crate) sudoku: Sudoku<AnyConstraint>,
+
+    /// The cells, given as column and row, whose digits must be kept in the
+    /// generated Sudoku.
+    #[serde(default)]
+    pub(crate) locked: Vec<(usize, usize)>
+}
+
+fn default_block_size() -> usize {
+    3
+}
+
+/// A request to generate a random Sudoku with an arbitrary combination of
+/// constraints, i.e. the argument to a `gen_from_template` call to the engine.
+/// To be deserialized from the input.
+#[derive(Deserialize)]
+pub(crate) struct GenTemplateRequest {
+
+    /// The constraint of the generated Sudoku. Sandwich clues are computed
+    /// from the generated grid, so those of the template are ignored.
+    pub(crate) constraint: AnyConstraint,
+
+    /// The width of the blocks of the generated Sudoku. Defaults to 3.
+    #[serde(default = "default_block_size")]
+    pub(crate) block_width: usize,
+
+    /// The height of the blocks of the generated Sudoku. Defaults to 3.
+    #[serde(default = "default_block_size")]
+    pub(crate) block_height: usize
+}
+
+/// The response to a `gen_from_partial`, `gen_from_solution`, or
+/// `gen_from_template` call to the engine. To be serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum GenFromResponse {
+
+    /// Indicates that a Sudoku was generated successfully. It is provided
+    /// together with its metadata.
+    #[serde(rename = "ok")]
+    Ok(GeneratedSudoku),
+
+    /// Indicates that no Sudoku of the requested difficulty was found before
+    /// the deadline expired, e.g. because the locked cells keep it too easy.
+    /// The closest Sudoku found is provided with its measured difficulty.
+    #[serde(rename = "fallback")]
+    Fallback(GenResponse),
+
+    /// Indicates that the provided partial Sudoku cannot be completed, or
+    /// that no full grid satisfies the provided template.
+    #[serde(rename = "unsatisfiable")]
+    Unsatisfiable,
+
+    /// Indicates that the provided partial Sudoku could not be completed, or
+    /// no Sudoku at all was found, before the deadline expired.
+    #[serde(rename = "timeout")]
+    Timeout,
+
+    /// Indicates that the provided solution is not full or violates its
+    /// constraint.
+    #[serde(rename = "invalid-solution")]
+    InvalidSolution,
+
+    /// Indicates that the request is malformed or its parameters, such as the
+    /// block dimensions of a template, are invalid. The reason is provided.
+    #[serde(rename = "error")]
+    Error(TaskError)
+}
+
+impl GenFromResponse {
+
+    /// Wraps the result of a generation run in a gen-from response. If the
+    /// source of full Sudoku was exhausted, it is reported as
+    /// [GenFromResponse::Unsatisfiable], and if the run was cancelled, as
+    /// [GenFromResponse::Timeout].
+    pub(crate) fn from_run(run: Result<Generated<AnyConstraint>, RunError>)
+            -> GenFromResponse {
+        match run {
+            Ok(generated) if generated.fallback => GenFromResponse::Fallback(
+                GenResponse::from_generated(generated)),
+            Ok(generated) =>
+                GenFromResponse::Ok(GeneratedSudoku::from_generated(generated)),
+            Err(RunError::Exhausted) => GenFromResponse::Unsatisfiable,
+            Err(RunError::Cancelled) => GenFromResponse::Timeout
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::constraint::AnyConstraint;
+use crate::generate::{Generated, RunCounts, Seed};
+use crate::schema::{self, SCHEMA_VERSION};
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+use sudoku_variants::Sudoku;
+use sudoku_variants::constraint::{
+    Constraint,
+    SandwichConstraint,
+    Subconstraint
+};
+
+/// Information about how a Sudoku was generated, e.g. for tuning the
+/// generator, bug reports, or showing puzzle info to the user. To be
+/// serialized alongside the generated Sudoku.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct GenMetadata {
+
+    /// The number of Sudoku that were reduced and classified by all generator
+    /// threads together, including the returned one.
+    attempts: usize,
+
+    /// The number of Sudoku that were rejected because they were too easy.
+    lower_rejections: usize,
+
+    /// The number of Sudoku that were rejected because they were too hard.
+    upper_rejections: usize,
+
+    /// The time it took to generate the Sudoku in milliseconds.
+    wall_time_millis: u64,
+
+    /// The number of generator threads.
+    threads: usize,
+
+    /// The seed of the generator thread that found the Sudoku in hexadecimal
+    /// form.
+    seed: String,
+
+    /// The number of filled cells of the Sudoku.
+    clues: usize,
+
+    /// The number of remaining sandwich clues, or `None` if the Sudoku has no
+    /// sandwich constraint.
+    sandwich_clues: Option<usize>,
+
+    /// The version of the engine that generated the Sudoku.
+    engine_version: String
+}
+
+fn clue_count(sudoku: &Sudoku<AnyConstraint>) -> usize {
+    let grid = sudoku.grid();
+    let size = grid.size();
+
+    (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .filter(|&(column, row)| grid.get_cell(column, row).unwrap().is_some())
+        .count()
+}
+
+fn sandwich_clue_count(sudoku: &Sudoku<AnyConstraint>) -> Option<usize> {
+    let constraint = sudoku.constraint();
+
+    if constraint.has_subconstraint::<SandwichConstraint>() {
+        // Sandwich clues are the only reducible part of any constraint, and
+        // each of them can be removed by exactly one reduction.
+        Some(constraint.list_reductions(sudoku.grid()).len())
+    }
+    else {
+        None
+    }
+}
+
+impl GenMetadata {
+
+    /// Creates the metadata for the given generated Sudoku.
+    ///
+    /// # Arguments
+    ///
+    /// * `counts`: The counts of the work done to find the Sudoku.
+    /// * `wall_time`: The time it took to find the Sudoku.
+    /// * `threads`: The number of generator threads.
+    /// * `seed`: The seed of the generator thread that found the Sudoku.
+    /// * `sudoku`: The generated Sudoku.
+    pub(crate) fn new(counts: RunCounts, wall_time: Duration, threads: usize,
+            seed: Seed, sudoku: &Sudoku<AnyConstraint>) -> GenMetadata {
+        GenMetadata {
+            attempts: counts.attempts,
+            lower_rejections: counts.lower_rejections,
+            upper_rejections: counts.upper_rejections,
+            wall_time_millis: wall_time.as_millis() as u64,
+            threads,
+            seed: seed.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            clues: clue_count(sudoku),
+            sandwich_clues: sandwich_clue_count(sudoku),
+            engine_version: env!("CARGO_PKG_VERSION").to_owned()
+        }
+    }
+
+    /// Creates placeholder metadata for the given Sudoku whose generation was
+    /// not recorded, e.g. because it was stored by an older version of the
+    /// engine. The clues are counted, all other numbers are zero, and the seed
+    /// and engine version are empty.
+    pub(crate) fn unknown(sudoku: &Sudoku<AnyConstraint>) -> GenMetadata {
+        GenMetadata {
+            attempts: 0,
+            lower_rejections: 0,
+            upper_rejections: 0,
+            wall_time_millis: 0,
+            threads: 0,
+            seed: String::new(),
+            clues: clue_count(sudoku),
+            sandwich_clues: sandwich_clue_count(sudoku),
+            engine_version: String::new()
+        }
+    }
+}
+
+/// A generated Sudoku together with its [GenMetadata]. It is serialized as the
+/// Sudoku document itself with an additional `metadata` field, so it can be
+/// read wherever a Sudoku is expected.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GeneratedSudoku {
+
+    /// The [SCHEMA_VERSION] of the document. Older documents must be
+    /// upgraded with [schema::migrate] before they are deserialized.
+    pub(crate) schema_version: u64,
+
+    /// The generated Sudoku.
+    #[serde(flatten)]
+    pub(crate) sudoku: Sudoku<AnyConstraint>,
+
+    /// Information about how the Sudoku was generated.
+    pub(crate) metadata: GenMetadata
+}
+
+impl GeneratedSudoku {
+
+    /// Drops the difficulty information of the given generated Sudoku.
+    pub(crate) fn from_generated(generated: Generated<AnyConstraint>)
+            -> GeneratedSudoku {
+        GeneratedSudoku {
+            schema_version: SCHEMA_VERSION,
+            sudoku: generated.sudoku,
+            metadata: generated.metadata
+        }
+    }
+
+    /// Wraps the given Sudoku, whose generation was not recorded, with
+    /// [GenMetadata::unknown].
+    pub(crate) fn without_metadata(sudoku: Sudoku<AnyConstraint>)
+            -> GeneratedSudoku {
+        GeneratedSudoku {
+            schema_version: SCHEMA_VERSION,
+            metadata: GenMetadata::unknown(&sudoku),
+            sudoku
+        }
+    }
+}
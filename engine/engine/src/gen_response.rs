@@ -0,0 +1,43 @@
+use crate::constraint::AnyConstraint;
+use crate::gen_metadata::GenMetadata;
+use crate::generate::Generated;
+use crate::schema;
+
+use serde::Serialize;
+
+use sudoku_variants::Sudoku;
+
+/// The response to a `gen_with_deadline` call to the engine. To be serialized
+/// for return.
+#[derive(Serialize)]
+pub(crate) struct GenResponse {
+
+    /// The generated Sudoku.
+    #[serde(serialize_with = "schema::serialize")]
+    sudoku: Sudoku<AnyConstraint>,
+
+    /// The measured difficulty of the generated Sudoku. This equals the
+    /// requested difficulty unless `fallback` is set.
+    difficulty: i32,
+
+    /// Indicates that the deadline expired before a Sudoku of the requested
+    /// difficulty was found, so the closest candidate was returned instead.
+    fallback: bool,
+
+    /// Information about how the Sudoku was generated.
+    metadata: GenMetadata
+}
+
+impl GenResponse {
+
+    /// Wraps the given generated Sudoku in a gen response.
+    pub(crate) fn from_generated(generated: Generated<AnyConstraint>)
+            -> GenResponse {
+        GenResponse {
+            sudoku: generated.sudoku,
+            difficulty: generated.difficulty,
+            fallback: generated.fallback,
+            metadata: generated.metadata
+        }
+    }
+}
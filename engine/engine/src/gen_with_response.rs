@@ -0,0 +1,60 @@
+use crate::constraint::AnyConstraint;
+use crate::gen_metadata::GeneratedSudoku;
+use crate::gen_response::GenResponse;
+use crate::generate::{Generated, RunError, TaskError};
+
+use serde::Serialize;
+
+/// The response to a `gen_with_ladder`, `gen_with_technique`, or
+/// `gen_sandwich_with_profile` call to the engine. To be serialized for
+/// return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum GenWithResponse {
+
+    /// Indicates that a Sudoku was generated successfully. It is provided
+    /// together with its metadata.
+    #[serde(rename = "ok")]
+    Ok(GeneratedSudoku),
+
+    /// Indicates that no Sudoku of the requested difficulty was found before
+    /// the deadline expired, e.g. because the required technique is rarely
+    /// needed at the requested level or the sandwich profile keeps too many
+    /// clues. The closest Sudoku found is provided with its measured
+    /// difficulty.
+    #[serde(rename = "fallback")]
+    Fallback(GenResponse),
+
+    /// Indicates that no full grid satisfies the constraint.
+    #[serde(rename = "unsatisfiable")]
+    Unsatisfiable,
+
+    /// Indicates that no Sudoku at all was found before the deadline
+    /// expired.
+    #[serde(rename = "timeout")]
+    Timeout,
+
+    /// Indicates that the supplied parameters are invalid. The reason is
+    /// provided.
+    #[serde(rename = "error")]
+    Error(TaskError)
+}
+
+impl GenWithResponse {
+
+    /// Wraps the result of a generation run in a gen-with response. If the
+    /// source of full Sudoku was exhausted, it is reported as
+    /// [GenWithResponse::Unsatisfiable], and if the run was cancelled, as
+    /// [GenWithResponse::Timeout].
+    pub(crate) fn from_run(run: Result<Generated<AnyConstraint>, RunError>)
+            -> GenWithResponse {
+        match run {
+            Ok(generated) if generated.fallback => GenWithResponse::Fallback(
+                GenResponse::from_generated(generated)),
+            Ok(generated) =>
+                GenWithResponse::Ok(GeneratedSudoku::from_generated(generated)),
+            Err(RunError::Exhausted) => GenWithResponse::Unsatisfiable,
+            Err(RunError::Cancelled) => GenWithResponse::Timeout
+        }
+    }
+}
@@ -0,0 +1,1019 @@
+//! Declarative descriptions of difficulty ladders. A ladder is a sequence of
+//! difficulty levels, each of which is described by a tree of named
+//! strategies. Ladders can be (de)serialized and are built into solvers at
+//! runtime, so custom ladders can be supplied without recompiling the engine.
+
+use crate::constraint::AnyConstraint;
+use crate::generate::{
+    self,
+    CancellableBacktrackingSolver,
+    CancellableStrategy,
+    DummyPerfectSolver,
+    GenerationTask,
+    Locks,
+    NeighbourEliminationStrategy,
+    NotSolver,
+    PointingStrategy,
+    Relation,
+    SourceResult,
+    TaskError
+};
+use crate::sync::CancelHandle;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use rand_chacha::ChaCha8Rng;
+
+use sudoku_variants::Sudoku;
+use sudoku_variants::constraint::{
+    Constraint,
+    SandwichConstraint,
+    Subconstraint
+};
+use sudoku_variants::generator::{Reduction, ReductionPrioritizer};
+use sudoku_variants::solver::{Solution, Solver};
+use sudoku_variants::solver::strategy::{
+    BoundedCellsBacktrackingStrategy,
+    BoundedOptionsBacktrackingStrategy,
+    CompositeStrategy,
+    NakedSingleStrategy,
+    NoStrategy,
+    OnlyCellStrategy,
+    StrategicSolver,
+    Strategy,
+    SudokuInfo,
+    TupleStrategy
+};
+use sudoku_variants::solver::strategy::specific::{
+    SandwichBunPlacementStrategy,
+    SandwichPossibilitiesStrategy
+};
+
+const DEFAULT_LADDER_JSON: &str = include_str!("ladders/default.json");
+const SANDWICH_LADDER_JSON: &str = include_str!("ladders/sandwich.json");
+const DIAGONALS_LADDER_JSON: &str = include_str!("ladders/diagonals.json");
+const KNIGHTS_MOVE_LADDER_JSON: &str =
+    include_str!("ladders/knights-move.json");
+const KINGS_MOVE_LADDER_JSON: &str = include_str!("ladders/kings-move.json");
+const CHESS_LADDER_JSON: &str = include_str!("ladders/chess.json");
+
+/// A serializable description of a [Strategy]. Parameters that the strategies
+/// of `sudoku-variants` compute from the grid size are given as constants.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum StrategySpec {
+
+    /// Describes a [NoStrategy], which never makes progress.
+    #[serde(rename = "none")]
+    None,
+
+    /// Describes an [OnlyCellStrategy].
+    #[serde(rename = "only-cell")]
+    OnlyCell,
+
+    /// Describes a [NakedSingleStrategy].
+    #[serde(rename = "naked-single")]
+    NakedSingle,
+
+    /// Describes a [TupleStrategy].
+    #[serde(rename = "tuple")]
+    Tuple {
+
+        /// The maximum size of the considered tuples.
+        max_size: usize
+    },
+
+    /// Describes a [BoundedCellsBacktrackingStrategy].
+    #[serde(rename = "bounded-cells-backtracking")]
+    BoundedCellsBacktracking {
+
+        /// The maximum number of cells in a group in which a digit may be
+        /// placed for it to be considered for backtracking.
+        max_cells: usize,
+
+        /// The maximum number of times the continuation strategy is applied
+        /// per attempted digit, or `None` if it is unlimited.
+        max_applications: Option<usize>,
+
+        /// The strategy used to find contradictions after placing a digit.
+        continuation: Box<StrategySpec>
+    },
+
+    /// Describes a [BoundedOptionsBacktrackingStrategy].
+    #[serde(rename = "bounded-options-backtracking")]
+    BoundedOptionsBacktracking {
+
+        /// The maximum number of options a cell may have for it to be
+        /// considered for backtracking.
+        max_options: usize,
+
+        /// The maximum number of times the continuation strategy is applied
+        /// per attempted option, or `None` if it is unlimited.
+        max_applications: Option<usize>,
+
+        /// The strategy used to find contradictions after placing a digit.
+        continuation: Box<StrategySpec>
+    },
+
+    /// Describes a [NeighbourEliminationStrategy].
+    #[serde(rename = "neighbour-elimination")]
+    NeighbourElimination {
+
+        /// The relation of the cells from which digits are eliminated.
+        relation: Relation
+    },
+
+    /// Describes a [PointingStrategy].
+    #[serde(rename = "pointing")]
+    Pointing {
+
+        /// The relations by which cells see each other in addition to sharing
+        /// a group.
+        #[serde(default)]
+        relations: Vec<Relation>
+    },
+
+    /// Describes a [SandwichBunPlacementStrategy].
+    #[serde(rename = "sandwich-bun-placement")]
+    SandwichBunPlacement,
+
+    /// Describes a [SandwichPossibilitiesStrategy].
+    #[serde(rename = "sandwich-possibilities")]
+    SandwichPossibilities,
+
+    /// Describes a [CompositeStrategy] of all wrapped strategies in the given
+    /// order. An empty composite never makes progress.
+    #[serde(rename = "composite")]
+    Composite(Vec<StrategySpec>)
+}
+
+type SizeFn = Box<dyn Fn(usize) -> usize + Send + Sync>;
+type LimitFn = Box<dyn Fn(usize) -> Option<usize> + Send + Sync>;
+
+fn constant_size(size: usize) -> SizeFn {
+    Box::new(move |_| size)
+}
+
+fn constant_limit(limit: Option<usize>) -> LimitFn {
+    Box::new(move |_| limit)
+}
+
+/// The continuation of a backtracking [RuntimeStrategy]. The indirection is
+/// required since the backtracking strategies contain it by value.
+struct Continuation(Box<CancellableStrategy<RuntimeStrategy>>);
+
+impl Strategy for Continuation {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        self.0.apply(sudoku_info)
+    }
+}
+
+/// Counts how often each technique made progress, keyed by the name of its
+/// [StrategySpec] (see [StrategySpec::name]).
+pub(crate) type TechniqueCounts = BTreeMap<&'static str, usize>;
+
+type TechniqueCounter = Arc<Mutex<TechniqueCounts>>;
+
+/// A strategy wrapper which records in a [TechniqueCounter] whenever the
+/// wrapped strategy makes progress.
+struct CountingStrategy {
+    strategy: RuntimeStrategy,
+    name: &'static str,
+    counter: TechniqueCounter
+}
+
+impl Strategy for CountingStrategy {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        let progress = self.strategy.apply(sudoku_info);
+
+        if progress {
+            *self.counter.lock().unwrap().entry(self.name).or_insert(0) += 1;
+        }
+
+        progress
+    }
+}
+
+/// A strategy which applies the required technique of a
+/// [SolverSpec::Requiring] only once the strategy and the next weaker variant
+/// of the technique (see [StrategySpec::weaker]) make no progress, and records
+/// in a [TechniqueCounter] whenever the technique does. Progress is therefore
+/// only recorded if it is due to the technique itself, e.g. a triple rather
+/// than a pair for a tuple technique of size 3.
+struct RequirementStrategy {
+    strategy: RuntimeStrategy,
+    weaker: Option<RuntimeStrategy>,
+    technique: RuntimeStrategy,
+    counter: TechniqueCounter
+}
+
+impl Strategy for RequirementStrategy {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        if self.strategy.apply(sudoku_info) {
+            return true;
+        }
+
+        if let Some(weaker) = &self.weaker {
+            if weaker.apply(sudoku_info) {
+                return true;
+            }
+        }
+
+        let progress = self.technique.apply(sudoku_info);
+
+        if progress {
+            *self.counter.lock().unwrap().entry("requirement").or_insert(0) +=
+                1;
+        }
+
+        progress
+    }
+}
+
+/// A strategy built at runtime from a [StrategySpec].
+enum RuntimeStrategy {
+    None(NoStrategy),
+    OnlyCell(OnlyCellStrategy),
+    NakedSingle(NakedSingleStrategy),
+    Tuple(TupleStrategy<SizeFn>),
+    BoundedCellsBacktracking(
+        BoundedCellsBacktrackingStrategy<SizeFn, LimitFn, Continuation>),
+    BoundedOptionsBacktracking(
+        BoundedOptionsBacktrackingStrategy<SizeFn, LimitFn, Continuation>),
+    NeighbourElimination(NeighbourEliminationStrategy),
+    Pointing(PointingStrategy),
+    SandwichBunPlacement(SandwichBunPlacementStrategy),
+    SandwichPossibilities(SandwichPossibilitiesStrategy),
+    Composite(Box<CompositeStrategy<RuntimeStrategy, RuntimeStrategy>>),
+    Counting(Box<CountingStrategy>),
+    Requirement(Box<RequirementStrategy>)
+}
+
+impl Strategy for RuntimeStrategy {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        match self {
+            RuntimeStrategy::None(s) => s.apply(sudoku_info),
+            RuntimeStrategy::OnlyCell(s) => s.apply(sudoku_info),
+            RuntimeStrategy::NakedSingle(s) => s.apply(sudoku_info),
+            RuntimeStrategy::Tuple(s) => s.apply(sudoku_info),
+            RuntimeStrategy::BoundedCellsBacktracking(s) =>
+                s.apply(sudoku_info),
+            RuntimeStrategy::BoundedOptionsBacktracking(s) =>
+                s.apply(sudoku_info),
+            RuntimeStrategy::NeighbourElimination(s) => s.apply(sudoku_info),
+            RuntimeStrategy::Pointing(s) => s.apply(sudoku_info),
+            RuntimeStrategy::SandwichBunPlacement(s) => s.apply(sudoku_info),
+            RuntimeStrategy::SandwichPossibilities(s) => s.apply(sudoku_info),
+            RuntimeStrategy::Composite(s) => s.apply(sudoku_info),
+            RuntimeStrategy::Counting(s) => s.apply(sudoku_info),
+            RuntimeStrategy::Requirement(s) => s.apply(sudoku_info)
+        }
+    }
+}
+
+impl StrategySpec {
+
+    /// Gets the name under which this strategy is serialized, e.g.
+    /// `"naked-single"`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            StrategySpec::None => "none",
+            StrategySpec::OnlyCell => "only-cell",
+            StrategySpec::NakedSingle => "naked-single",
+            StrategySpec::Tuple { .. } => "tuple",
+            StrategySpec::BoundedCellsBacktracking { .. } =>
+                "bounded-cells-backtracking",
+            StrategySpec::BoundedOptionsBacktracking { .. } =>
+                "bounded-options-backtracking",
+            StrategySpec::NeighbourElimination { .. } =>
+                "neighbour-elimination",
+            StrategySpec::Pointing { .. } => "pointing",
+            StrategySpec::SandwichBunPlacement => "sandwich-bun-placement",
+            StrategySpec::SandwichPossibilities => "sandwich-possibilities",
+            StrategySpec::Composite(_) => "composite"
+        }
+    }
+
+    /// Creates a strategy that applies this one first and the given ones
+    /// afterwards. If this is a composite, the strategies are appended to it.
+    fn with_strategies(&self, strategies: &[StrategySpec]) -> StrategySpec {
+        let mut specs = match self {
+            StrategySpec::Composite(specs) => specs.clone(),
+            spec => vec![spec.clone()]
+        };
+        specs.extend_from_slice(strategies);
+        StrategySpec::Composite(specs)
+    }
+
+    /// Gets the next weaker variant of this technique, if it has one, i.e. the
+    /// tuple strategy of the next smaller size for a tuple strategy.
+    fn weaker(&self) -> Option<StrategySpec> {
+        match self {
+            StrategySpec::Tuple { max_size } if *max_size > 1 =>
+                Some(StrategySpec::Tuple { max_size: max_size - 1 }),
+            _ => None
+        }
+    }
+
+    /// Creates a strategy that is equivalent to this one, except that the
+    /// given technique is never applied, including in continuations. A tuple
+    /// technique also removes all larger tuples, so without naked triples only
+    /// pairs remain.
+    fn without(&self, technique: &StrategySpec) -> StrategySpec {
+        if self == technique {
+            return StrategySpec::None;
+        }
+
+        match (self, technique) {
+            (StrategySpec::Tuple { max_size },
+                    StrategySpec::Tuple { max_size: excluded })
+                    if max_size >= excluded => match excluded {
+                0 | 1 => StrategySpec::None,
+                _ => StrategySpec::Tuple { max_size: excluded - 1 }
+            },
+            (StrategySpec::BoundedCellsBacktracking {
+                max_cells,
+                max_applications,
+                continuation
+            }, _) => StrategySpec::BoundedCellsBacktracking {
+                max_cells: *max_cells,
+                max_applications: *max_applications,
+                continuation: Box::new(continuation.without(technique))
+            },
+            (StrategySpec::BoundedOptionsBacktracking {
+                max_options,
+                max_applications,
+                continuation
+            }, _) => StrategySpec::BoundedOptionsBacktracking {
+                max_options: *max_options,
+                max_applications: *max_applications,
+                continuation: Box::new(continuation.without(technique))
+            },
+            (StrategySpec::Composite(specs), _) =>
+                StrategySpec::Composite(specs.iter()
+                    .map(|spec| spec.without(technique))
+                    .filter(|spec| spec != &StrategySpec::None)
+                    .collect()),
+            (spec, _) => spec.clone()
+        }
+    }
+
+    fn build_continuation(&self, handle: &CancelHandle) -> Continuation {
+        Continuation(Box::new(CancellableStrategy::new(
+            self.build(handle, None), handle.clone())))
+    }
+
+    /// Builds the described strategy. All continuations of backtracking
+    /// strategies stop making progress once the given handle is cancelled.
+    /// If a counter is given, every technique outside of continuations
+    /// records its progress in it.
+    fn build(&self, handle: &CancelHandle, counter: Option<&TechniqueCounter>)
+            -> RuntimeStrategy {
+        let strategy = match self {
+            StrategySpec::None => RuntimeStrategy::None(NoStrategy),
+            StrategySpec::OnlyCell =>
+                RuntimeStrategy::OnlyCell(OnlyCellStrategy),
+            StrategySpec::NakedSingle =>
+                RuntimeStrategy::NakedSingle(NakedSingleStrategy),
+            StrategySpec::Tuple { max_size } => RuntimeStrategy::Tuple(
+                TupleStrategy::new(constant_size(*max_size))),
+            StrategySpec::BoundedCellsBacktracking {
+                max_cells,
+                max_applications,
+                continuation
+            } => RuntimeStrategy::BoundedCellsBacktracking(
+                BoundedCellsBacktrackingStrategy::new(
+                    constant_size(*max_cells),
+                    constant_limit(*max_applications),
+                    continuation.build_continuation(handle))),
+            StrategySpec::BoundedOptionsBacktracking {
+                max_options,
+                max_applications,
+                continuation
+            } => RuntimeStrategy::BoundedOptionsBacktracking(
+                BoundedOptionsBacktrackingStrategy::new(
+                    constant_size(*max_options),
+                    constant_limit(*max_applications),
+                    continuation.build_continuation(handle))),
+            StrategySpec::NeighbourElimination { relation } =>
+                RuntimeStrategy::NeighbourElimination(
+                    NeighbourEliminationStrategy::new(*relation)),
+            StrategySpec::Pointing { relations } => RuntimeStrategy::Pointing(
+                PointingStrategy::new(relations.clone())),
+            StrategySpec::SandwichBunPlacement =>
+                RuntimeStrategy::SandwichBunPlacement(
+                    SandwichBunPlacementStrategy),
+            StrategySpec::SandwichPossibilities =>
+                RuntimeStrategy::SandwichPossibilities(
+                    SandwichPossibilitiesStrategy),
+            StrategySpec::Composite(specs) => return specs.iter()
+                .map(|spec| spec.build(handle, counter))
+                .reduce(|first, second| RuntimeStrategy::Composite(
+                    Box::new(CompositeStrategy::new(first, second))))
+                .unwrap_or(RuntimeStrategy::None(NoStrategy))
+        };
+
+        match counter {
+            Some(counter) => RuntimeStrategy::Counting(Box::new(
+                CountingStrategy {
+                    strategy,
+                    name: self.name(),
+                    counter: Arc::clone(counter)
+                })),
+            None => strategy
+        }
+    }
+}
+
+fn collect_relations(constraint: &AnyConstraint,
+        relations: &mut Vec<Relation>) {
+    let relation = match constraint {
+        AnyConstraint::KnightsMove => Relation::Knight,
+        AnyConstraint::KingsMove => Relation::King,
+        AnyConstraint::Composite(cs) => {
+            for c in cs {
+                collect_relations(c, relations);
+            }
+
+            return;
+        },
+        _ => return
+    };
+
+    if !relations.contains(&relation) {
+        relations.push(relation);
+    }
+}
+
+/// Gets the strategies that are required in addition to the general ones to
+/// deal with the given constraint efficiently. Techniques which only make
+/// levels harder to separate, such as pointing, are left to the built-in
+/// ladders of the individual variants.
+fn specific_strategies(constraint: &AnyConstraint) -> Vec<StrategySpec> {
+    let mut strategies = Vec::new();
+    let mut relations = Vec::new();
+    collect_relations(constraint, &mut relations);
+
+    for relation in relations {
+        strategies.push(StrategySpec::NeighbourElimination { relation });
+    }
+
+    if constraint.has_subconstraint::<SandwichConstraint>() {
+        strategies.push(StrategySpec::SandwichBunPlacement);
+        strategies.push(StrategySpec::SandwichPossibilities);
+    }
+
+    strategies
+}
+
+/// A serializable description of a [Solver] that is used in a [Ladder].
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum SolverSpec {
+
+    /// A solver that cannot solve any Sudoku.
+    #[serde(rename = "none")]
+    None,
+
+    /// A solver that pretends to solve every Sudoku. This is used as the
+    /// upper bound of the hardest difficulty level.
+    #[serde(rename = "perfect")]
+    Perfect,
+
+    /// A [StrategicSolver] with the described strategy.
+    #[serde(rename = "strategic")]
+    Strategic(StrategySpec),
+
+    /// A solver which backtracks whenever the described strategy makes no
+    /// progress, like the `StrategicBacktrackingSolver` of `sudoku-variants`.
+    #[serde(rename = "strategic-backtracking")]
+    StrategicBacktracking(StrategySpec),
+
+    /// A [StrategicSolver] which applies the technique whenever the strategy
+    /// and the next weaker variant of the technique make no progress. It only
+    /// considers a Sudoku solved if the technique made progress at least once,
+    /// i.e. if it appears in the solve path. For a tuple technique, this
+    /// means a tuple of exactly its size.
+    #[serde(rename = "requiring")]
+    Requiring {
+
+        /// The strategy which is applied first.
+        strategy: StrategySpec,
+
+        /// The technique which must be applied to solve the Sudoku.
+        technique: StrategySpec
+    }
+}
+
+/// A solver built at runtime from a [SolverSpec].
+enum RuntimeSolver {
+    None(NotSolver),
+    Perfect(DummyPerfectSolver),
+    Strategic(StrategicSolver<CancellableStrategy<RuntimeStrategy>>),
+    StrategicBacktracking(CancellableBacktrackingSolver<RuntimeStrategy>),
+    Requiring {
+        solver: StrategicSolver<CancellableStrategy<RuntimeStrategy>>,
+        counter: TechniqueCounter
+    }
+}
+
+impl Solver for RuntimeSolver {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static
+    {
+        match self {
+            RuntimeSolver::None(s) => s.solve(sudoku),
+            RuntimeSolver::Perfect(s) => s.solve(sudoku),
+            RuntimeSolver::Strategic(s) => s.solve(sudoku),
+            RuntimeSolver::StrategicBacktracking(s) => s.solve(sudoku),
+            RuntimeSolver::Requiring { solver, counter } => {
+                counter.lock().unwrap().clear();
+
+                match solver.solve(sudoku) {
+                    Solution::Unique(_) if counter.lock().unwrap().is_empty()
+                        => Solution::Ambiguous,
+                    solution => solution
+                }
+            }
+        }
+    }
+}
+
+impl SolverSpec {
+
+    /// Creates a solver whose strategy additionally applies the given
+    /// strategies after its own. Solvers without a strategy are unchanged.
+    fn with_strategies(&self, strategies: &[StrategySpec]) -> SolverSpec {
+        match self {
+            SolverSpec::Strategic(spec) =>
+                SolverSpec::Strategic(spec.with_strategies(strategies)),
+            SolverSpec::StrategicBacktracking(spec) =>
+                SolverSpec::StrategicBacktracking(
+                    spec.with_strategies(strategies)),
+            SolverSpec::Requiring { strategy, technique } =>
+                SolverSpec::Requiring {
+                    strategy: strategy.with_strategies(strategies),
+                    technique: technique.clone()
+                },
+            spec => spec.clone()
+        }
+    }
+
+    /// Builds the described solver. Its strategy stops making progress and
+    /// its backtracking stops once the given handle is cancelled. If a
+    /// counter is given, the techniques of the strategy record their progress
+    /// in it.
+    fn build(&self, handle: CancelHandle, counter: Option<&TechniqueCounter>)
+            -> RuntimeSolver {
+        match self {
+            SolverSpec::None => RuntimeSolver::None(NotSolver),
+            SolverSpec::Perfect => RuntimeSolver::Perfect(DummyPerfectSolver),
+            SolverSpec::Strategic(spec) => {
+                let strategy = spec.build(&handle, counter);
+                RuntimeSolver::Strategic(StrategicSolver::new(
+                    CancellableStrategy::new(strategy, handle)))
+            },
+            SolverSpec::StrategicBacktracking(spec) => {
+                let strategy = spec.build(&handle, counter);
+                RuntimeSolver::StrategicBacktracking(
+                    CancellableBacktrackingSolver::new(strategy, handle))
+            },
+            SolverSpec::Requiring { strategy, technique } => {
+                let requirement = Arc::new(Mutex::new(TechniqueCounts::new()));
+                let strategy = RuntimeStrategy::Requirement(Box::new(
+                    RequirementStrategy {
+                        strategy: strategy.build(&handle, counter),
+                        weaker: technique.weaker()
+                            .map(|weaker| weaker.build(&handle, counter)),
+                        technique: technique.build(&handle, counter),
+                        counter: Arc::clone(&requirement)
+                    }));
+                RuntimeSolver::Requiring {
+                    solver: StrategicSolver::new(
+                        CancellableStrategy::new(strategy, handle)),
+                    counter: requirement
+                }
+            }
+        }
+    }
+}
+
+/// A single level of a [Ladder].
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct LadderLevel {
+
+    /// The solver that must be able to solve Sudoku of this level. Sudoku of
+    /// the next level must not be solvable by it.
+    pub(crate) solver: SolverSpec,
+
+    /// The solver used to reduce Sudoku when generating for this level.
+    pub(crate) generator: SolverSpec
+}
+
+/// A serializable difficulty ladder. Difficulty `d` corresponds to the level
+/// at index `d - 1`. A Sudoku has difficulty `d` if the solver of that level
+/// can solve it, but the solver of the previous level cannot. The first level
+/// has no lower bound.
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Ladder {
+
+    /// The difficulty levels in ascending order of difficulty.
+    pub(crate) levels: Vec<LadderLevel>
+}
+
+impl Ladder {
+
+    /// The built-in ladder for constraints without specific strategies, i.e.
+    /// classic Sudoku.
+    pub(crate) fn default_ladder() -> Ladder {
+        serde_json::from_str(DEFAULT_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for sandwich Sudoku.
+    pub(crate) fn sandwich_ladder() -> Ladder {
+        serde_json::from_str(SANDWICH_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for diagonals Sudoku.
+    pub(crate) fn diagonals_ladder() -> Ladder {
+        serde_json::from_str(DIAGONALS_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for knight's move Sudoku.
+    pub(crate) fn knights_move_ladder() -> Ladder {
+        serde_json::from_str(KNIGHTS_MOVE_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for king's move Sudoku.
+    pub(crate) fn kings_move_ladder() -> Ladder {
+        serde_json::from_str(KINGS_MOVE_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for Chess Sudoku.
+    pub(crate) fn chess_ladder() -> Ladder {
+        serde_json::from_str(CHESS_LADDER_JSON).unwrap()
+    }
+
+    /// The built-in ladder for the constraint of the given identifier (see
+    /// the crate-level documentation).
+    ///
+    /// # Errors
+    ///
+    /// [TaskError::InvalidConstraint] if the constraint identifier is invalid.
+    pub(crate) fn builtin(constraint: i32) -> Result<Ladder, TaskError> {
+        match constraint {
+            0 => Ok(Ladder::default_ladder()),
+            1 => Ok(Ladder::diagonals_ladder()),
+            2 => Ok(Ladder::knights_move_ladder()),
+            3 => Ok(Ladder::kings_move_ladder()),
+            4 => Ok(Ladder::chess_ladder()),
+            5 => Ok(Ladder::sandwich_ladder()),
+            _ => Err(TaskError::InvalidConstraint(constraint))
+        }
+    }
+
+    /// Builds a ladder for an arbitrary constraint by extending every solver
+    /// of the default ladder with the strategies specific to the parts of the
+    /// constraint, analogously to the strategy chosen by the solver. For a
+    /// plain sandwich constraint, this yields the sandwich ladder.
+    pub(crate) fn for_constraint(constraint: &AnyConstraint) -> Ladder {
+        let strategies = specific_strategies(constraint);
+        let ladder = Ladder::default_ladder();
+
+        if strategies.is_empty() {
+            return ladder;
+        }
+
+        Ladder {
+            levels: ladder.levels.iter()
+                .map(|level| LadderLevel {
+                    solver: level.solver.with_strategies(&strategies),
+                    generator: level.generator.with_strategies(&strategies)
+                })
+                .collect()
+        }
+    }
+
+    /// Checks that Sudoku can be generated for every level of this ladder.
+    /// The generator of every level must be a [SolverSpec::Strategic] or
+    /// [SolverSpec::StrategicBacktracking], and only the solver of the last
+    /// level may be [SolverSpec::Perfect], since it pretends to solve every
+    /// Sudoku. The built-in ladders are always valid.
+    ///
+    /// # Errors
+    ///
+    /// * [TaskError::InvalidGenerator] if the generator of a level is of any
+    /// other kind.
+    /// * [TaskError::MisplacedPerfectSolver] if a level other than the last
+    /// has a perfect solver.
+    pub(crate) fn validate(&self) -> Result<(), TaskError> {
+        for (index, level) in self.levels.iter().enumerate() {
+            let difficulty = index as i32 + 1;
+
+            match level.generator {
+                SolverSpec::Strategic(_) |
+                    SolverSpec::StrategicBacktracking(_) => { },
+                _ => return Err(TaskError::InvalidGenerator(difficulty))
+            }
+
+            if let SolverSpec::Perfect = level.solver {
+                if index + 1 < self.levels.len() {
+                    return Err(TaskError::MisplacedPerfectSolver(difficulty));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a [GenerationTask] for Sudoku of the given difficulty on this
+    /// ladder. See [generate::gen_with_difficulty] for the other arguments.
+    ///
+    /// # Errors
+    ///
+    /// [TaskError::InvalidDifficulty] if this ladder has no level for the
+    /// given difficulty.
+    pub(crate) fn generation_task<C, FS, P>(&self, difficulty: i32,
+        full_sudoku_source: FS, reduction_prioritizer: P, locks: Locks)
+        -> Result<GenerationTask, TaskError>
+    where
+        C: Constraint + Clone + Into<AnyConstraint> + Send + 'static,
+        FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C> + Send
+            + Sync + Clone + 'static,
+        P: ReductionPrioritizer<Reduction<C::Reduction>> + Clone + Copy
+            + Send + Sync + 'static
+    {
+        let level = self.level(difficulty)?;
+        let lower_bound = match difficulty {
+            1 => SolverSpec::None,
+            _ => self.levels[difficulty as usize - 2].solver.clone()
+        };
+        let upper_bound = level.solver.clone();
+        let generator = level.generator.clone();
+
+        Ok(generate::gen_with_difficulty(
+            difficulty,
+            move |handle| lower_bound.build(handle, None),
+            move |handle| upper_bound.build(handle, None),
+            move |handle| generator.build(handle, None),
+            full_sudoku_source,
+            reduction_prioritizer,
+            locks))
+    }
+
+    /// Gets the level of the given difficulty.
+    ///
+    /// # Errors
+    ///
+    /// [TaskError::InvalidDifficulty] if this ladder has no level for the
+    /// given difficulty.
+    pub(crate) fn level(&self, difficulty: i32)
+            -> Result<&LadderLevel, TaskError> {
+        if difficulty < 1 || difficulty as usize > self.levels.len() {
+            return Err(TaskError::InvalidDifficulty(difficulty));
+        }
+
+        Ok(&self.levels[difficulty as usize - 1])
+    }
+
+    /// Creates a ladder with two levels for Sudoku which require the given
+    /// technique. The solver of the level of the given difficulty, without the
+    /// technique, is the first level. The second level consists of the Sudoku
+    /// which become solvable once the technique is added, and in whose solve
+    /// path it appears. Difficulty 2 on the created ladder therefore yields
+    /// the desired Sudoku.
+    ///
+    /// # Errors
+    ///
+    /// * [TaskError::InvalidDifficulty] if this ladder has no level for the
+    /// given difficulty.
+    /// * [TaskError::NoStrategicSolver] if the solver of that level is not a
+    /// [SolverSpec::Strategic].
+    /// * [TaskError::TechniqueNotInLevel] if removing the technique does not
+    /// change the solver of that level, e.g. a sandwich technique on a classic
+    /// ladder or a pointing technique with different relations. Generation
+    /// would never find a Sudoku that requires it.
+    pub(crate) fn technique_ladder(&self, difficulty: i32,
+            technique: &StrategySpec) -> Result<Ladder, TaskError> {
+        let spec = match &self.level(difficulty)?.solver {
+            SolverSpec::Strategic(spec) => spec,
+            _ => return Err(TaskError::NoStrategicSolver(difficulty))
+        };
+        let base = spec.without(technique);
+
+        if &base == spec {
+            return Err(TaskError::TechniqueNotInLevel);
+        }
+
+        let extended = base.with_strategies(&[technique.clone()]);
+
+        Ok(Ladder {
+            levels: vec![
+                LadderLevel {
+                    solver: SolverSpec::Strategic(base.clone()),
+                    generator: SolverSpec::Strategic(base.clone())
+                },
+                LadderLevel {
+                    solver: SolverSpec::Requiring {
+                        strategy: base,
+                        technique: technique.clone()
+                    },
+                    generator: SolverSpec::Strategic(extended)
+                }
+            ]
+        })
+    }
+
+    /// Counts the techniques the solver of the level of the given difficulty
+    /// uses to solve the given Sudoku. For levels without a strategic solver,
+    /// such as the hardest one, the generator solver is used instead, in which
+    /// case the counts include techniques applied while backtracking.
+    ///
+    /// # Panics
+    ///
+    /// If the ladder has no level for the given difficulty.
+    pub(crate) fn count_techniques(&self, difficulty: i32,
+            sudoku: &Sudoku<AnyConstraint>) -> TechniqueCounts {
+        if difficulty < 1 || difficulty as usize > self.levels.len() {
+            panic!("Invalid difficulty: {}", difficulty);
+        }
+
+        let level = &self.levels[difficulty as usize - 1];
+        let spec = match level.solver {
+            SolverSpec::None | SolverSpec::Perfect => &level.generator,
+            ref solver => solver
+        };
+        let counter = Arc::new(Mutex::new(TechniqueCounts::new()));
+        spec.build(CancelHandle::new(), Some(&counter)).solve(sudoku);
+        let counts = counter.lock().unwrap();
+        counts.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const TRIPLE: StrategySpec = StrategySpec::Tuple { max_size: 3 };
+
+    #[test]
+    fn technique_ladder_invalid_difficulty() {
+        let ladder = Ladder::default_ladder();
+
+        assert_eq!(Some(TaskError::InvalidDifficulty(0)),
+            ladder.technique_ladder(0, &TRIPLE).err());
+        assert_eq!(Some(TaskError::InvalidDifficulty(6)),
+            ladder.technique_ladder(6, &TRIPLE).err());
+    }
+
+    #[test]
+    fn technique_ladder_no_strategic_solver() {
+        let ladder = Ladder::default_ladder();
+
+        assert_eq!(Some(TaskError::NoStrategicSolver(5)),
+            ladder.technique_ladder(5, &TRIPLE).err());
+    }
+
+    #[test]
+    fn technique_ladder_technique_not_in_level() {
+        let ladder = Ladder::default_ladder();
+
+        assert_eq!(Some(TaskError::TechniqueNotInLevel),
+            ladder.technique_ladder(2, &TRIPLE).err());
+        assert_eq!(Some(TaskError::TechniqueNotInLevel),
+            ladder.technique_ladder(4, &StrategySpec::SandwichPossibilities)
+                .err());
+    }
+
+    #[test]
+    fn technique_ladder_technique_in_level() {
+        let ladder = Ladder::default_ladder().technique_ladder(4, &TRIPLE)
+            .ok()
+            .unwrap();
+
+        assert_eq!(2, ladder.levels.len());
+    }
+
+    #[test]
+    fn generation_task_invalid_difficulty() {
+        let ladder = Ladder::default_ladder();
+        let empty = Ladder {
+            levels: Vec::new()
+        };
+
+        assert_eq!(Some(TaskError::InvalidDifficulty(0)),
+            generate::ladder_generation_task(&ladder, 0, 0, 3, 3).err());
+        assert_eq!(Some(TaskError::InvalidDifficulty(6)),
+            generate::ladder_generation_task(&ladder, 5, 6, 3, 3).err());
+        assert_eq!(Some(TaskError::InvalidDifficulty(1)),
+            generate::ladder_generation_task(&empty, 0, 1, 3, 3).err());
+        assert!(generate::ladder_generation_task(&ladder, 0, 5, 3, 3).is_ok());
+    }
+
+    #[test]
+    fn technique_generation_task_reports_requested_difficulty() {
+        let task = generate::technique_generation_task(0, 4, &TRIPLE, 3, 3)
+            .ok()
+            .unwrap();
+        let expected = generate::ReportedDifficulties {
+            accepted: 4,
+            too_easy: 4,
+            too_hard: 5
+        };
+
+        assert_eq!(expected, task.difficulties);
+    }
+
+    #[test]
+    fn ladder_generation_task_reports_adjacent_difficulties() {
+        let ladder = Ladder::default_ladder();
+        let task = generate::ladder_generation_task(&ladder, 0, 3, 3, 3)
+            .ok()
+            .unwrap();
+
+        assert_eq!(generate::ReportedDifficulties::adjacent(3),
+            task.difficulties);
+    }
+
+    #[test]
+    fn invalid_constraint() {
+        let ladder = Ladder::default_ladder();
+        let technique = generate::technique_generation_task(6, 4, &TRIPLE, 3,
+            3);
+
+        assert_eq!(Some(TaskError::InvalidConstraint(6)),
+            Ladder::builtin(6).err());
+        assert_eq!(Some(TaskError::InvalidConstraint(-1)),
+            generate::ladder_generation_task(&ladder, -1, 1, 3, 3).err());
+        assert_eq!(Some(TaskError::InvalidConstraint(6)), technique.err());
+    }
+
+    #[test]
+    fn builtin_ladders_are_valid() {
+        for constraint in 0..=5 {
+            let ladder = Ladder::builtin(constraint).ok().unwrap();
+
+            assert_eq!(Ok(()), ladder.validate());
+
+            if let Ok(technique_ladder) = ladder.technique_ladder(4, &TRIPLE) {
+                assert_eq!(Ok(()), technique_ladder.validate());
+            }
+        }
+    }
+
+    #[test]
+    fn validate_invalid_generator() {
+        for generator in [SolverSpec::None, SolverSpec::Perfect] {
+            let mut ladder = Ladder::default_ladder();
+            ladder.levels[2].generator = generator;
+
+            assert_eq!(Err(TaskError::InvalidGenerator(3)), ladder.validate());
+        }
+
+        let mut ladder = Ladder::default_ladder();
+        ladder.levels[0].generator = SolverSpec::Requiring {
+            strategy: StrategySpec::NakedSingle,
+            technique: TRIPLE
+        };
+
+        assert_eq!(Err(TaskError::InvalidGenerator(1)), ladder.validate());
+    }
+
+    #[test]
+    fn validate_misplaced_perfect_solver() {
+        let mut ladder = Ladder::default_ladder();
+        ladder.levels[3].solver = SolverSpec::Perfect;
+
+        assert_eq!(Err(TaskError::MisplacedPerfectSolver(4)),
+            ladder.validate());
+
+        ladder.levels.truncate(4);
+
+        assert_eq!(Ok(()), ladder.validate());
+    }
+
+    #[test]
+    fn weaker_tuple() {
+        assert!(TRIPLE.weaker() ==
+            Some(StrategySpec::Tuple { max_size: 2 }));
+        assert!(StrategySpec::Tuple { max_size: 1 }.weaker().is_none());
+        assert!(StrategySpec::NakedSingle.weaker().is_none());
+    }
+}
@@ -1,24 +1,42 @@
-use crate::sync::CancelHandle;
+use crate::canonical;
+use crate::constraint::{self, AnyConstraint, SandwichClues};
+use crate::gen_metadata::GenMetadata;
+use crate::sync::{Cancelled, CancelHandle};
+use crate::workers::{self, Priority};
 
-use std::sync::mpsc::{self, Sender};
-use std::thread;
+use serde::Serialize;
+
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+
+use rand_chacha::ChaCha8Rng;
 
 use sudoku_variants::{Sudoku, SudokuGrid};
-use sudoku_variants::constraint::Constraint;
-use sudoku_variants::generator::{
-    Generator,
-    Reducer,
-    Reduction,
-    ReductionPrioritizer
+use sudoku_variants::constraint::{
+    Constraint,
+    SandwichConstraint,
+    Subconstraint
 };
+use sudoku_variants::generator::{Reduction, ReductionPrioritizer};
 use sudoku_variants::solver::{Solution, Solver};
 use sudoku_variants::solver::strategy::{Strategy, SudokuInfo};
 
+mod ladder;
+pub(crate) mod portable;
 mod sandwich;
 mod simple;
+mod variant;
 
+pub(crate) use ladder::*;
 pub(crate) use sandwich::*;
 pub(crate) use simple::*;
+pub(crate) use variant::*;
 
 /// A dummy solver which always returns `Solution::Amibguous`. This is used as
 /// a lower bound for the easiest difficulty to prevent special cases.
@@ -47,6 +65,10 @@ impl Solver for DummyPerfectSolver {
     }
 }
 
+/// A strategy wrapper which makes no progress once the given handle is
+/// cancelled. A solver using it thus gives up quickly. Its result is
+/// meaningless if the handle was cancelled while it ran, which is why
+/// [CancellableSolver::try_solve] discards it in that case.
 struct CancellableStrategy<S> {
     strategy: S,
     handle: CancelHandle
@@ -66,79 +88,970 @@ impl<S: Strategy> Strategy for CancellableStrategy<S> {
     where
         C: Constraint + Clone + 'static
     {
-        self.handle.assert_not_cancelled();
+        if self.handle.is_cancelled() {
+            return false;
+        }
+
         self.strategy.apply(sudoku_info)
     }
 }
 
-fn difficulty_0(_: CancelHandle) -> impl Solver {
-    NotSolver
+/// A solver which applies the wrapped strategy as long as it makes progress
+/// and then guesses a digit of the cell with the fewest options, recursively,
+/// like the `StrategicBacktrackingSolver` of `sudoku-variants`. Unlike that
+/// solver, it polls the given handle after every application of the strategy
+/// and before every guess, and stops as soon as it is cancelled. Otherwise, a
+/// cancelled strategy, which makes no progress, would leave the whole search
+/// tree to brute force.
+struct CancellableBacktrackingSolver<S> {
+    strategy: S,
+    handle: CancelHandle
+}
+
+impl<S: Strategy> CancellableBacktrackingSolver<S> {
+    fn new(strategy: S, handle: CancelHandle)
+            -> CancellableBacktrackingSolver<S> {
+        CancellableBacktrackingSolver {
+            strategy,
+            handle
+        }
+    }
+
+    fn solve_rec<C>(&self, sudoku_info: &mut SudokuInfo<C>)
+        -> Result<Solution, Cancelled>
+    where
+        C: Constraint + Clone + 'static
+    {
+        self.handle.check()?;
+
+        while self.strategy.apply(sudoku_info) {
+            self.handle.check()?;
+        }
+
+        if !sudoku_info.sudoku().is_valid() {
+            return Ok(Solution::Impossible);
+        }
+
+        let size = sudoku_info.size();
+        let mut guess: Option<(usize, usize, Vec<usize>)> = None;
+
+        for row in 0..size {
+            for column in 0..size {
+                if sudoku_info.get_cell(column, row).unwrap().is_some() {
+                    continue;
+                }
+
+                let options = sudoku_info.get_options(column, row).unwrap()
+                    .iter()
+                    .collect::<Vec<_>>();
+
+                if guess.as_ref()
+                        .is_none_or(|(_, _, o)| options.len() < o.len()) {
+                    guess = Some((column, row, options));
+                }
+            }
+        }
+
+        let (column, row, options) = match guess {
+            Some(guess) => guess,
+            None => return Ok(Solution::Unique(
+                sudoku_info.sudoku().grid().clone()))
+        };
+        let mut solution = Solution::Impossible;
+
+        for number in options {
+            self.handle.check()?;
+
+            let mut next_info = sudoku_info.clone();
+            next_info.enter_cell(column, row, number).unwrap();
+
+            solution = match (solution, self.solve_rec(&mut next_info)?) {
+                (Solution::Impossible, next) => next,
+                (solution, Solution::Impossible) => solution,
+                _ => Solution::Ambiguous
+            };
+
+            if let Solution::Ambiguous = solution {
+                break;
+            }
+        }
+
+        Ok(solution)
+    }
+}
+
+impl<S: Strategy> Solver for CancellableBacktrackingSolver<S> {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static
+    {
+        let mut sudoku_info = SudokuInfo::from_sudoku(sudoku.clone());
+        self.solve_rec(&mut sudoku_info).unwrap_or(Solution::Ambiguous)
+    }
+}
+
+/// A solver wrapper which does not start solving once the given handle is
+/// cancelled. It complements [CancellableStrategy] and
+/// [CancellableBacktrackingSolver], which make the wrapped solver give up
+/// quickly, by reporting [Cancelled] instead of the meaningless result of a
+/// solver that gave up.
+struct CancellableSolver<S> {
+    solver: S,
+    handle: CancelHandle
 }
 
-fn difficulty_inf(_: CancelHandle) -> impl Solver {
-    DummyPerfectSolver
+impl<S: Solver> CancellableSolver<S> {
+    fn new(solver: S, handle: CancelHandle) -> CancellableSolver<S> {
+        CancellableSolver {
+            solver,
+            handle
+        }
+    }
+
+    /// Solves the given Sudoku, unless the handle is cancelled before or
+    /// while solving, in which case `Err(Cancelled)` is returned.
+    fn try_solve<C>(&self, sudoku: &Sudoku<C>) -> Result<Solution, Cancelled>
+    where
+        C: Constraint + Clone + 'static
+    {
+        self.handle.check()?;
+        let solution = self.solver.solve(sudoku);
+        self.handle.check()?;
+        Ok(solution)
+    }
+}
+
+impl<S: Solver> Solver for CancellableSolver<S> {
+    fn solve<C>(&self, sudoku: &Sudoku<C>) -> Solution
+    where
+        C: Constraint + Clone + 'static
+    {
+        self.try_solve(sudoku).unwrap_or(Solution::Ambiguous)
+    }
 }
 
-fn can_solve<C, S>(sudoku: &Sudoku<C>, solver: &S) -> bool
+fn can_solve<C, S>(sudoku: &Sudoku<C>, solver: &CancellableSolver<S>)
+    -> Result<bool, Cancelled>
 where
     C: Constraint + Clone + 'static,
     S: Solver
 {
-    matches!(solver.solve(sudoku), Solution::Unique(_))
+    Ok(matches!(solver.try_solve(sudoku)?, Solution::Unique(_)))
+}
+
+/// Classifies the given reduced Sudoku with respect to the difficulty bounds
+/// given by the solvers. Returns `Err(Cancelled)` if either solver is
+/// cancelled, since the classification is meaningless in that case.
+fn classify<C, SL, SU>(sudoku: Sudoku<C>,
+    lower_difficulty_bound_solver: &CancellableSolver<SL>,
+    upper_difficulty_bound_solver: &CancellableSolver<SU>)
+    -> Result<Outcome<C>, Cancelled>
+where
+    C: Constraint + Clone + 'static,
+    SL: Solver,
+    SU: Solver
+{
+    if can_solve(&sudoku, lower_difficulty_bound_solver)? {
+        Ok(Outcome::TooEasy(sudoku))
+    }
+    else if !can_solve(&sudoku, upper_difficulty_bound_solver)? {
+        Ok(Outcome::TooHard(sudoku))
+    }
+    else {
+        Ok(Outcome::Accepted(sudoku))
+    }
 }
 
 fn constraint_identity<C>(constraint: C, _: &SudokuGrid) -> C {
     constraint
 }
 
-fn gen_with_difficulty_thread<SL, SU, SG, C1, C2, FC1, FC2, P>(
-    lower_difficulty_bound_solver: SL,
-    upper_difficulty_bound_solver: SU, generator_solver: SG,
-    constraint_cons: FC1, constraint_transform: FC2, reduction_prioritizer: P,
-    cancel_handle: CancelHandle, result_sender: Sender<Sudoku<C2>>)
+/// The classification of a reduced Sudoku with respect to the difficulty
+/// bounds of a generation run.
+pub(crate) enum Outcome<C> {
+
+    /// The Sudoku lies within the requested difficulty bounds.
+    Accepted(Sudoku<C>),
+
+    /// The Sudoku could be solved by the lower difficulty bound solver, i.e.
+    /// it is easier than requested.
+    TooEasy(Sudoku<C>),
+
+    /// The Sudoku could not be solved by the upper difficulty bound solver,
+    /// i.e. it is harder than requested.
+    TooHard(Sudoku<C>)
+}
+
+impl<C> Outcome<C> {
+
+    /// Ranks outcomes by how well they serve as a result. Lower is better. A
+    /// Sudoku that is too easy is preferred over one that is too hard, since
+    /// the former is at least known to be solvable by the next easier
+    /// difficulty level, whereas the latter may be arbitrarily hard.
+    fn rank(&self) -> u8 {
+        match self {
+            Outcome::Accepted(_) => 0,
+            Outcome::TooEasy(_) => 1,
+            Outcome::TooHard(_) => 2
+        }
+    }
+
+    pub(crate) fn sudoku(&self) -> &Sudoku<C> {
+        match self {
+            Outcome::Accepted(sudoku) => sudoku,
+            Outcome::TooEasy(sudoku) => sudoku,
+            Outcome::TooHard(sudoku) => sudoku
+        }
+    }
+
+    fn map_sudoku<D, F>(self, f: F) -> Outcome<D>
+    where
+        F: FnOnce(Sudoku<C>) -> Sudoku<D>
+    {
+        match self {
+            Outcome::Accepted(sudoku) => Outcome::Accepted(f(sudoku)),
+            Outcome::TooEasy(sudoku) => Outcome::TooEasy(f(sudoku)),
+            Outcome::TooHard(sudoku) => Outcome::TooHard(f(sudoku))
+        }
+    }
+
+    /// Converts this outcome into a [Generated] result, given the
+    /// difficulties reported for each outcome and the metadata of the
+    /// generation run.
+    pub(crate) fn into_generated(self, difficulties: ReportedDifficulties,
+            metadata: GenMetadata) -> Generated<C> {
+        match self {
+            Outcome::Accepted(sudoku) => Generated {
+                sudoku,
+                difficulty: difficulties.accepted,
+                fallback: false,
+                metadata
+            },
+            Outcome::TooEasy(sudoku) => Generated {
+                sudoku,
+                difficulty: difficulties.too_easy,
+                fallback: true,
+                metadata
+            },
+            Outcome::TooHard(sudoku) => Generated {
+                sudoku,
+                difficulty: difficulties.too_hard,
+                fallback: true,
+                metadata
+            }
+        }
+    }
+}
+
+/// The difficulties that a [GenerationTask] reports for the [Outcome]s of its
+/// candidates, on the scale of the requested difficulty.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ReportedDifficulties {
+
+    /// The difficulty of accepted Sudoku, i.e. the requested one.
+    pub(crate) accepted: i32,
+
+    /// The difficulty of Sudoku that are too easy.
+    pub(crate) too_easy: i32,
+
+    /// The difficulty of Sudoku that are too hard.
+    pub(crate) too_hard: i32
+}
+
+impl ReportedDifficulties {
+
+    /// The difficulties for a level of a ladder, where Sudoku that miss it
+    /// belong to the adjacent levels.
+    pub(crate) fn adjacent(difficulty: i32) -> ReportedDifficulties {
+        ReportedDifficulties {
+            accepted: difficulty,
+            too_easy: difficulty - 1,
+            too_hard: difficulty + 1
+        }
+    }
+}
+
+/// An [Outcome] reported by a generator thread, together with the seed of
+/// that thread and the time it took to produce it.
+pub(crate) struct Report<C> {
+
+    /// The classified Sudoku.
+    pub(crate) outcome: Outcome<C>,
+
+    /// The seed of the generator thread that produced the Sudoku.
+    pub(crate) seed: Seed,
+
+    /// The time it took to produce, reduce, and classify the Sudoku.
+    pub(crate) elapsed: Duration,
+
+    /// The fingerprint of the Sudoku (see [canonical::fingerprint]). It is
+    /// only computed by threads in [ThreadMode::Continuous], so that the
+    /// expensive canonicalization runs in parallel instead of on the thread
+    /// which receives the reports.
+    pub(crate) fingerprint: Option<u64>
+}
+
+/// Determines which outcomes a generator thread reports and when it stops.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum ThreadMode {
+
+    /// Report every outcome and stop after the first accepted Sudoku.
+    Single,
+
+    /// Report only accepted Sudoku and keep generating.
+    Continuous,
+
+    /// Report every outcome and keep generating.
+    Trials
+}
+
+/// Counts of the work done by the generator threads of a run.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct RunCounts {
+
+    /// The number of Sudoku that were reduced and classified.
+    pub(crate) attempts: usize,
+
+    /// The number of Sudoku that were rejected because the lower difficulty
+    /// bound solver could solve them.
+    pub(crate) lower_rejections: usize,
+
+    /// The number of Sudoku that were rejected because the upper difficulty
+    /// bound solver could not solve them.
+    pub(crate) upper_rejections: usize
+}
+
+impl RunCounts {
+
+    /// Gets the counts of work that was done after the given earlier counts
+    /// were taken.
+    fn since(&self, earlier: &RunCounts) -> RunCounts {
+        RunCounts {
+            attempts: self.attempts - earlier.attempts,
+            lower_rejections: self.lower_rejections - earlier.lower_rejections,
+            upper_rejections: self.upper_rejections - earlier.upper_rejections
+        }
+    }
+}
+
+/// The [RunCounts] of a run, shared by all of its generator threads.
+#[derive(Default)]
+struct RunStats {
+    attempts: AtomicUsize,
+    lower_rejections: AtomicUsize,
+    upper_rejections: AtomicUsize
+}
+
+impl RunStats {
+    fn counts(&self) -> RunCounts {
+        RunCounts {
+            attempts: self.attempts.load(Ordering::Relaxed),
+            lower_rejections: self.lower_rejections.load(Ordering::Relaxed),
+            upper_rejections: self.upper_rejections.load(Ordering::Relaxed)
+        }
+    }
+}
+
+/// Everything a generator thread needs that differs between the threads and
+/// runs of a [GenerationTask].
+struct ThreadContext {
+
+    /// The cancel handle of the run.
+    cancel_handle: CancelHandle,
+
+    /// The sender to which outcomes are reported.
+    result_sender: Sender<Report<AnyConstraint>>,
+
+    /// The statistics of the run, which the thread updates.
+    stats: Arc<RunStats>,
+
+    /// Which outcomes the thread reports and when it stops.
+    mode: ThreadMode,
+
+    /// The seed of the random number generator of the thread.
+    seed: Seed
+}
+
+/// A generated Sudoku together with its measured difficulty.
+pub(crate) struct Generated<C> {
+
+    /// The generated Sudoku.
+    pub(crate) sudoku: Sudoku<C>,
+
+    /// The difficulty of the Sudoku on the same scale as the requested one.
+    /// If `fallback` is set, this is usually the adjacent difficulty level on
+    /// the side the Sudoku missed the requested bounds (see
+    /// [ReportedDifficulties]).
+    pub(crate) difficulty: i32,
+
+    /// Indicates that no Sudoku of the requested difficulty was found before
+    /// the deadline expired, so the closest candidate was returned instead.
+    pub(crate) fallback: bool,
+
+    /// Information about how the Sudoku was generated.
+    pub(crate) metadata: GenMetadata
+}
+
+/// The reasons why a generation run yields no Sudoku at all.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RunError {
+
+    /// The run was cancelled via its [CancelHandle] before a Sudoku was found.
+    Cancelled,
+
+    /// The source of full Sudoku could not provide any, e.g. because no full
+    /// grid satisfies the constraint.
+    Exhausted
+}
+
+/// The reasons why no [GenerationTask] can be created from the parameters
+/// supplied by the host, such as a custom ladder or a required technique.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum TaskError {
+
+    /// The parameters are not valid JSON or do not have the expected layout.
+    /// The message of the parser is provided.
+    #[serde(rename = "invalid-json")]
+    InvalidJson(String),
+
+    /// The ladder has no level for the requested difficulty, which is
+    /// provided.
+    #[serde(rename = "invalid-difficulty")]
+    InvalidDifficulty(i32),
+
+    /// The constraint identifier, which is provided, does not denote any
+    /// constraint (see the crate-level documentation).
+    #[serde(rename = "invalid-constraint")]
+    InvalidConstraint(i32),
+
+    /// The level of the requested difficulty, which is provided, has no
+    /// strategic solver from which a technique could be removed.
+    #[serde(rename = "no-strategic-solver")]
+    NoStrategicSolver(i32),
+
+    /// The required technique is not part of the solver of the requested
+    /// level, so no Sudoku can require it there.
+    #[serde(rename = "technique-not-in-level")]
+    TechniqueNotInLevel,
+
+    /// The generator of the level of the given difficulty, which is provided,
+    /// is neither a strategic nor a strategic-backtracking solver, so it
+    /// would not reduce full Sudoku to uniquely solvable ones.
+    #[serde(rename = "invalid-generator")]
+    InvalidGenerator(i32),
+
+    /// The solver of the level of the given difficulty, which is provided,
+    /// is the perfect solver, but the level is not the last one. Since the
+    /// perfect solver pretends to solve every Sudoku, no higher level could
+    /// contain any.
+    #[serde(rename = "misplaced-perfect-solver")]
+    MisplacedPerfectSolver(i32),
+
+    /// The minimum number of sandwich clues of a profile exceeds the maximum.
+    /// Both are provided in this order.
+    #[serde(rename = "invalid-clue-range")]
+    InvalidClueRange(usize, usize),
+
+    /// The block dimensions, which are provided as width and height, are zero
+    /// or the Sudoku would be larger than [MAX_TEMPLATE_SIZE].
+    #[serde(rename = "invalid-dimensions")]
+    InvalidDimensions(usize, usize),
+
+    /// A sandwich constraint of the template has clues for a different number
+    /// of columns or rows than the Sudoku has.
+    #[serde(rename = "mismatched-sandwich")]
+    MismatchedSandwich
+}
+
+impl Display for TaskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::InvalidJson(message) =>
+                write!(f, "invalid JSON: {}", message),
+            TaskError::InvalidDifficulty(difficulty) =>
+                write!(f, "invalid difficulty: {}", difficulty),
+            TaskError::InvalidConstraint(constraint) =>
+                write!(f, "invalid constraint identifier: {}", constraint),
+            TaskError::NoStrategicSolver(difficulty) =>
+                write!(f, "level {} has no strategic solver", difficulty),
+            TaskError::TechniqueNotInLevel =>
+                write!(f, "technique is not part of the level"),
+            TaskError::InvalidGenerator(difficulty) =>
+                write!(f, "level {} has an invalid generator", difficulty),
+            TaskError::MisplacedPerfectSolver(difficulty) =>
+                write!(f, "level {} has a perfect solver, but is not the last",
+                    difficulty),
+            TaskError::InvalidClueRange(min, max) =>
+                write!(f, "minimum of {} clues exceeds maximum of {}", min,
+                    max),
+            TaskError::InvalidDimensions(width, height) =>
+                write!(f, "invalid block dimensions: {}x{}", width, height),
+            TaskError::MismatchedSandwich =>
+                write!(f, "sandwich clues do not match the size")
+        }
+    }
+}
+
+/// The largest size, i.e. product of the block dimensions, of Sudoku that are
+/// generated from a constraint template.
+const MAX_TEMPLATE_SIZE: usize = 16;
+
+/// Restrictions on the reductions which may be applied while reducing a full
+/// Sudoku.
+#[derive(Clone, Default)]
+pub(crate) struct Locks {
+
+    /// The cells, given as column and row, whose digits must not be removed.
+    pub(crate) cells: Vec<(usize, usize)>,
+
+    /// The number of clues of the constraint, such as sandwich clues, that
+    /// must remain. Once only this many are left, the constraint is not
+    /// reduced any further.
+    pub(crate) min_constraint_clues: usize
+}
+
+impl Locks {
+
+    /// Creates locks which keep the digits in the given cells, given as column
+    /// and row, and do not restrict the constraint.
+    pub(crate) fn with_cells(cells: Vec<(usize, usize)>) -> Locks {
+        Locks {
+            cells,
+            min_constraint_clues: 0
+        }
+    }
+}
+
+fn gen_with_difficulty_thread<SL, SU, SG, C, FS, P>(
+    lower_difficulty_bound_solver: CancellableSolver<SL>,
+    upper_difficulty_bound_solver: CancellableSolver<SU>,
+    generator_solver: CancellableSolver<SG>,
+    full_sudoku_source: FS, reduction_prioritizer: P,
+    locks: Locks, context: ThreadContext)
 where
     SL: Solver,
     SU: Solver,
     SG: Solver,
-    C1: Constraint + Clone + 'static,
-    C2: Constraint + Clone + 'static,
-    FC1: Fn() -> C1,
-    FC2: Fn(C1, &SudokuGrid) -> C2,
-    P: ReductionPrioritizer<Reduction<C2::Reduction>> + Clone
+    C: Constraint + Clone + Into<AnyConstraint> + 'static,
+    FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C>,
+    P: ReductionPrioritizer<Reduction<C::Reduction>> + Clone
 {
-    let mut generator = Generator::new_default();
-    let mut reducer = Reducer::new(generator_solver, rand::thread_rng());
+    let ThreadContext {
+        cancel_handle,
+        result_sender,
+        stats,
+        mode,
+        seed
+    } = context;
+    let mut rng = ChaCha8Rng::from_seed(seed);
 
     while !cancel_handle.is_cancelled() {
-        let constraint = constraint_cons();
-        let sudoku = generator.generate(3, 3, constraint).unwrap();
-        let (grid, constraint) = sudoku.into_raw_parts();
-        let constraint = constraint_transform(constraint, &grid);
-        let mut sudoku = Sudoku::new_with_grid(grid, constraint);
-        reducer.reduce_with_priority(&mut sudoku,
-            reduction_prioritizer.clone());
+        let start = Instant::now();
+        let sudoku = match full_sudoku_source(&mut rng, &cancel_handle) {
+            Ok(Some(sudoku)) => sudoku,
+            Ok(None) | Err(Cancelled) => break
+        };
+        let sudoku = match portable::reduce(sudoku, &generator_solver,
+                reduction_prioritizer.clone(), &locks, &mut rng,
+                &cancel_handle) {
+            Ok(sudoku) => sudoku,
+            Err(Cancelled) => break
+        };
 
-        if can_solve(&sudoku, &lower_difficulty_bound_solver) {
-            continue;
+        let outcome = match classify(sudoku, &lower_difficulty_bound_solver,
+                &upper_difficulty_bound_solver) {
+            Ok(outcome) => outcome,
+            Err(Cancelled) => break
+        };
+
+        stats.attempts.fetch_add(1, Ordering::Relaxed);
+
+        let rejections = match outcome {
+            Outcome::TooEasy(_) => Some(&stats.lower_rejections),
+            Outcome::TooHard(_) => Some(&stats.upper_rejections),
+            Outcome::Accepted(_) => None
+        };
+
+        if let Some(rejections) = rejections {
+            rejections.fetch_add(1, Ordering::Relaxed);
         }
 
-        if !can_solve(&sudoku, &upper_difficulty_bound_solver) {
+        let accepted = matches!(outcome, Outcome::Accepted(_));
+
+        if mode == ThreadMode::Continuous && !accepted {
             continue;
         }
 
-        result_sender.send(sudoku).unwrap();
-        break;
+        let elapsed = start.elapsed();
+        let outcome = outcome.map_sudoku(constraint::into_any_sudoku);
+        let fingerprint = if mode == ThreadMode::Continuous {
+            Some(canonical::fingerprint(outcome.sudoku()))
+        }
+        else {
+            None
+        };
+        let report = Report {
+            outcome,
+            seed,
+            elapsed,
+            fingerprint
+        };
+
+        if result_sender.send(report).is_err() ||
+                (accepted && mode == ThreadMode::Single) {
+            break;
+        }
     }
 }
 
-/// Generates a Sudoku with a specific difficulty, determined by the provided
-/// solvers. Input parameters should be provided in a way that prevents
-/// generation to take too long, i.e. the generator solver should be able to
-/// yield a valid Sudoku quickly and the probability that it lies within the
-/// difficulty bounds should be reasonably high.
+/// Receives reports from the generator threads until one is accepted or the
+/// deadline expires. In the latter case, the best rejected report is
+/// returned. If no report at all has been received by the deadline, this
+/// waits for the first one. If all generator threads stop before that, the
+/// best rejected report is returned if there is one, and otherwise a
+/// [RunError] which tells whether the given handle of the run was cancelled
+/// or the source ran out of full Sudoku.
+fn receive_report<C>(receiver: Receiver<Report<C>>, deadline: Option<Instant>,
+        run_handle: &CancelHandle) -> Result<Report<C>, RunError> {
+    let mut fallback: Option<Report<C>> = None;
+
+    loop {
+        let received = match (deadline, &fallback) {
+            (Some(deadline), Some(_)) => {
+                let timeout =
+                    deadline.saturating_duration_since(Instant::now());
+                receiver.recv_timeout(timeout)
+            },
+            _ => receiver.recv().map_err(RecvTimeoutError::from)
+        };
+
+        let report = match received {
+            Ok(report) => report,
+            Err(_) if run_handle.is_cancelled() =>
+                return Err(RunError::Cancelled),
+            Err(_) => return fallback.ok_or(RunError::Exhausted)
+        };
+
+        if let Outcome::Accepted(_) = report.outcome {
+            return Ok(report);
+        }
+
+        fallback = match fallback {
+            Some(f) if f.outcome.rank() <= report.outcome.rank() => Some(f),
+            _ => Some(report)
+        };
+    }
+}
+
+/// The result of a source of full Sudoku, see [random_source]. It is `Ok(None)`
+/// if the source cannot provide any more Sudoku.
+pub(crate) type SourceResult<C> = Result<Option<Sudoku<C>>, Cancelled>;
+
+/// The seed of the random number generator of a generator thread. Given the
+/// same seed, a generator thread produces the same sequence of Sudoku on every
+/// platform.
+pub(crate) type Seed = [u8; 32];
+
+/// A closure that creates the body of one generator thread, given its
+/// [ThreadContext].
+type ThreadCons = dyn Fn(ThreadContext) -> Box<dyn FnOnce() + Send> + Send
+    + Sync;
+
+/// The generator threads of a run that were spawned on the worker pool.
+struct SpawnedRun {
+
+    /// The receiver of the reports of all threads.
+    receiver: Receiver<Report<AnyConstraint>>,
+
+    /// The cancel handle shared by all threads of the run.
+    run_handle: CancelHandle,
+
+    /// The statistics shared by all threads of the run.
+    stats: Arc<RunStats>,
+
+    /// The number of threads.
+    threads: usize
+}
+
+/// A type-erased description of the generation of Sudoku with a specific
+/// constraint and difficulty. It can be run to obtain a single Sudoku or a
+/// batch of Sudoku, using any number of generator threads.
+pub(crate) struct GenerationTask {
+    difficulties: ReportedDifficulties,
+    thread_cons: Box<ThreadCons>
+}
+
+impl GenerationTask {
+
+    /// Changes the difficulties reported for the generated Sudoku. This is
+    /// required if the task was created for a level of a ladder derived from
+    /// another one, on whose scale the difficulty was requested.
+    fn reporting(self, difficulties: ReportedDifficulties) -> GenerationTask {
+        GenerationTask {
+            difficulties,
+            ..self
+        }
+    }
+
+    fn spawn_threads(&self, mode: ThreadMode, priority: Priority,
+            cancel_handle: &CancelHandle) -> SpawnedRun {
+        let workers = workers::workers();
+        let threads = match priority {
+            Priority::Background => 1,
+            _ => workers.threads()
+        };
+        let run_handle = cancel_handle.child();
+        let stats = Arc::new(RunStats::default());
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..threads {
+            let thread_body = (self.thread_cons)(ThreadContext {
+                cancel_handle: run_handle.clone(),
+                result_sender: Sender::clone(&sender),
+                stats: Arc::clone(&stats),
+                mode,
+                seed: rand::thread_rng().gen()
+            });
+            workers.submit(priority, run_handle.clone(), thread_body);
+        }
+
+        SpawnedRun {
+            receiver,
+            run_handle,
+            stats,
+            threads
+        }
+    }
+
+    /// Generates a single Sudoku using the shared worker pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority`: The [Priority] with which the work is scheduled on the
+    /// worker pool. Work of [Priority::Background] occupies only one worker.
+    /// * `deadline`: An optional point in time after which generation is
+    /// aborted. In that case, the closest Sudoku found so far is returned with
+    /// the `fallback` flag set. If no Sudoku has been found at all, the first
+    /// one to be found is returned.
+    /// * `cancel_handle`: A [CancelHandle] which can be used to abort
+    /// generation from the outside.
+    ///
+    /// # Errors
+    ///
+    /// [RunError::Cancelled] if the handle is cancelled before a Sudoku is
+    /// found, and [RunError::Exhausted] if the source of full Sudoku cannot
+    /// provide any.
+    pub(crate) fn run(&self, priority: Priority, deadline: Option<Instant>,
+            cancel_handle: &CancelHandle)
+            -> Result<Generated<AnyConstraint>, RunError> {
+        let start = Instant::now();
+        let run =
+            self.spawn_threads(ThreadMode::Single, priority, cancel_handle);
+        let report = receive_report(run.receiver, deadline, &run.run_handle);
+        run.run_handle.cancel();
+        let report = report?;
+        let metadata = GenMetadata::new(run.stats.counts(), start.elapsed(),
+            run.threads, report.seed, report.outcome.sudoku());
+        Ok(report.outcome.into_generated(self.difficulties, metadata))
+    }
+
+    /// Generates a single Sudoku deterministically from the given seed. This
+    /// runs on the calling thread and cannot be cancelled, but it yields the
+    /// same Sudoku for the same seed on every platform.
+    ///
+    /// # Errors
+    ///
+    /// [RunError::Exhausted] if the source of full Sudoku cannot provide any
+    /// before a Sudoku is accepted.
+    pub(crate) fn run_seeded(&self, seed: Seed)
+            -> Result<Generated<AnyConstraint>, RunError> {
+        let start = Instant::now();
+        let stats = Arc::new(RunStats::default());
+        let (sender, receiver) = mpsc::channel();
+        let thread_body = (self.thread_cons)(ThreadContext {
+            cancel_handle: CancelHandle::new(),
+            result_sender: sender,
+            stats: Arc::clone(&stats),
+            mode: ThreadMode::Single,
+            seed
+        });
+        thread_body();
+
+        let report = receiver.try_iter()
+            .find(|report| matches!(report.outcome, Outcome::Accepted(_)))
+            .ok_or(RunError::Exhausted)?;
+        let metadata = GenMetadata::new(stats.counts(), start.elapsed(), 1,
+            seed, report.outcome.sudoku());
+        Ok(report.outcome.into_generated(self.difficulties, metadata))
+    }
+
+    /// Generates a batch of distinct Sudoku using the shared worker pool,
+    /// whose workers keep generating until the batch is complete. Every Sudoku
+    /// is passed to the given callback as soon as it is found. Sudoku that
+    /// are equivalent to one found earlier in the same batch, i.e. have the
+    /// same canonical form, are dropped. The metadata of each Sudoku counts
+    /// the work done since the previous one was found.
+    ///
+    /// # Arguments
+    ///
+    /// * `count`: The number of Sudoku to generate.
+    /// * `priority`: The [Priority] with which the work is scheduled on the
+    /// worker pool. Work of [Priority::Background] occupies only one worker.
+    /// * `cancel_handle`: A [CancelHandle] which can be used to abort
+    /// generation from the outside.
+    /// * `callback`: A closure that is called with every generated Sudoku.
+    /// Since only accepted Sudoku are passed, `fallback` is never set.
+    ///
+    /// # Returns
+    ///
+    /// The number of Sudoku that were passed to the callback. This is less
+    /// than `count` only if generation was cancelled.
+    pub(crate) fn run_batch<F>(&self, count: usize, priority: Priority,
+        cancel_handle: &CancelHandle, mut callback: F) -> usize
+    where
+        F: FnMut(Generated<AnyConstraint>)
+    {
+        if count == 0 {
+            return 0;
+        }
+
+        let run = self.spawn_threads(ThreadMode::Continuous, priority,
+            cancel_handle);
+        let mut seen = HashSet::new();
+        let mut last_time = Instant::now();
+        let mut last_counts = RunCounts::default();
+
+        for report in run.receiver.iter() {
+            let fingerprint = report.fingerprint.unwrap_or_else(
+                || canonical::fingerprint(report.outcome.sudoku()));
+
+            if !seen.insert(fingerprint) {
+                continue;
+            }
+
+            let now = Instant::now();
+            let counts = run.stats.counts();
+            let metadata = GenMetadata::new(counts.since(&last_counts),
+                now - last_time, run.threads, report.seed,
+                report.outcome.sudoku());
+            last_time = now;
+            last_counts = counts;
+            callback(
+                report.outcome.into_generated(self.difficulties, metadata));
+
+            if seen.len() == count {
+                break;
+            }
+        }
+
+        run.run_handle.cancel();
+        seen.len()
+    }
+
+    /// Runs the given number of generation trials using the shared worker
+    /// pool, reporting every reduced and classified Sudoku regardless of
+    /// whether it is accepted. This is intended for calibrating ladders.
+    ///
+    /// # Arguments
+    ///
+    /// * `count`: The number of trials to run.
+    /// * `priority`: The [Priority] with which the work is scheduled on the
+    /// worker pool. Work of [Priority::Background] occupies only one worker.
+    /// * `cancel_handle`: A [CancelHandle] which can be used to abort the
+    /// trials from the outside.
+    /// * `callback`: A closure that is called with the [Report] of every
+    /// trial.
+    ///
+    /// # Returns
+    ///
+    /// The number of reports that were passed to the callback. This is less
+    /// than `count` only if the trials were cancelled.
+    pub(crate) fn run_trials<F>(&self, count: usize, priority: Priority,
+        cancel_handle: &CancelHandle, mut callback: F) -> usize
+    where
+        F: FnMut(Report<AnyConstraint>)
+    {
+        if count == 0 {
+            return 0;
+        }
+
+        let run = self.spawn_threads(ThreadMode::Trials, priority,
+            cancel_handle);
+        let mut reported = 0;
+
+        for report in run.receiver.iter() {
+            callback(report);
+            reported += 1;
+
+            if reported == count {
+                break;
+            }
+        }
+
+        run.run_handle.cancel();
+        reported
+    }
+}
+
+/// Creates a source of full Sudoku for [gen_with_difficulty] which generates
+/// random Sudoku.
+///
+/// # Arguments
+///
+/// * `block_width`: The width of the blocks of the generated Sudoku.
+/// * `block_height`: The height of the blocks of the generated Sudoku.
+/// * `constraint_cons`: A closure that creates the initial constraint of the
+/// generated Sudoku. This is the constraint used for generation.
+/// * `constraint_transform`: A closure that takes the initial constraint
+/// produced by `constraint_cons` and the full Sudoku grid and returns the
+/// constraint of the Sudoku to reduce. For stateless constraints, this is the
+/// identity, but reducible constraints may compute things like sandwich sums
+/// or generate random Killer Sudoku cages here.
+pub(crate) fn random_source<C1, C2, FC1, FC2>(block_width: usize,
+    block_height: usize, constraint_cons: FC1, constraint_transform: FC2)
+    -> impl Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C2> + Send
+        + Sync + Clone + 'static
+where
+    C1: Constraint + Clone + 'static,
+    C2: Constraint + Clone + 'static,
+    FC1: Fn() -> C1 + Send + Sync + Clone + 'static,
+    FC2: Fn(C1, &SudokuGrid) -> C2 + Send + Sync + Clone + 'static
+{
+    move |rng: &mut ChaCha8Rng, cancel_handle: &CancelHandle| {
+        let constraint = constraint_cons();
+        let sudoku = match portable::generate(block_width, block_height,
+                constraint, rng, cancel_handle)? {
+            Some(sudoku) => sudoku,
+            None => return Ok(None)
+        };
+        let (grid, constraint) = sudoku.into_raw_parts();
+        let constraint = constraint_transform(constraint, &grid);
+        Ok(Some(Sudoku::new_with_grid(grid, constraint)))
+    }
+}
+
+/// Creates a source of full Sudoku for [gen_with_difficulty] which always
+/// yields the given full Sudoku.
+pub(crate) fn fixed_source<C>(solution: Sudoku<C>)
+    -> impl Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C> + Send
+        + Sync + Clone + 'static
+where
+    C: Constraint + Clone + Send + Sync + 'static
+{
+    move |_: &mut ChaCha8Rng, _: &CancelHandle| Ok(Some(solution.clone()))
+}
+
+/// Creates a [GenerationTask] which generates Sudoku with a specific
+/// difficulty, determined by the provided solvers. Input parameters should be
+/// provided in a way that prevents generation to take too long, i.e. the
+/// generator solver should be able to yield a valid Sudoku quickly and the
+/// probability that it lies within the difficulty bounds should be reasonably
+/// high.
 ///
 /// # Arguments
 ///
+/// * `difficulty`: The difficulty level described by the solvers.
 /// * `lower_difficulty_bound_solver_cons`: A closure that creates a solver
 /// which determines an exclusive lower bound on the difficulty. That is, it
 /// shall not be able to solve the returned Sudoku.
@@ -147,61 +1060,228 @@ where
 /// shall be able to solve the returned Sudoku.
 /// * `generator_solver_cons`: A closure that creates a solver used for
 /// reducing a generated Sudoku.
-/// * `constraint_cons`: A closure that creates the initial constraint of the
-/// generated Sudoku. This is the constraint used for generation.
-/// * `constraint_transform`: A closure that takes the initial constraint
-/// produced by `constraint_cons` and the full Sudoku grid and returns the
-/// constraint of the Sudoku to reduce. For stateless constraints, this is the
-/// identity, but reducible constraints may compute things like sandwich sums
-/// or generate random Killer Sudoku cages here.
+/// * `full_sudoku_source`: A closure that yields the full Sudoku to reduce,
+/// given the random number generator and the cancel handle of the generator
+/// thread. See [random_source] and [fixed_source]. If it yields `Ok(None)` or
+/// is cancelled, the generator thread stops.
 /// * `reduction_prioritizer`: The [ReductionPrioritizer] to use for
 /// prioritizing reductions.
-fn gen_with_difficulty<SL, FSL, SU, FSU, SG, FSG, C1, C2, FC1, FC2, P>(
-    lower_difficulty_bound_solver_cons: FSL,
+/// * `locks`: The [Locks] which restrict the reductions.
+fn gen_with_difficulty<SL, FSL, SU, FSU, SG, FSG, C, FS, P>(
+    difficulty: i32, lower_difficulty_bound_solver_cons: FSL,
     upper_difficulty_bound_solver_cons: FSU, generator_solver_cons: FSG,
-    constraint_cons: FC1, constraint_transform: FC2, reduction_prioritizer: P)
-    -> Sudoku<C2>
+    full_sudoku_source: FS, reduction_prioritizer: P, locks: Locks)
+    -> GenerationTask
 where
     SL: Solver + Send + 'static,
-    FSL: Fn(CancelHandle) -> SL,
+    FSL: Fn(CancelHandle) -> SL + Send + Sync + 'static,
     SU: Solver + Send + 'static,
-    FSU: Fn(CancelHandle) -> SU,
+    FSU: Fn(CancelHandle) -> SU + Send + Sync + 'static,
     SG: Solver + Send + 'static,
-    FSG: Fn(CancelHandle) -> SG,
-    C1: Constraint + Clone + 'static,
-    C2: Constraint + Clone + Send + 'static,
-    FC1: Fn() -> C1 + Send + Copy + 'static,
-    FC2: Fn(C1, &SudokuGrid) -> C2 + Send + Copy + 'static,
-    P: ReductionPrioritizer<Reduction<C2::Reduction>> + Clone + Copy + Send
-        + 'static
+    FSG: Fn(CancelHandle) -> SG + Send + Sync + 'static,
+    C: Constraint + Clone + Into<AnyConstraint> + Send + 'static,
+    FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C> + Send + Sync
+        + Clone + 'static,
+    P: ReductionPrioritizer<Reduction<C::Reduction>> + Clone + Copy + Send
+        + Sync + 'static
 {
-    // TODO replace with thread::available_parallelism once stable
-    let threads = num_cpus::get();
-    let mut cancel_handles = Vec::new();
-    let (sender, receiver) = mpsc::channel();
+    let thread_cons = move |context: ThreadContext| {
+        let cancel_handle = &context.cancel_handle;
+        let lower_difficulty_bound_solver = CancellableSolver::new(
+            lower_difficulty_bound_solver_cons(cancel_handle.clone()),
+            cancel_handle.clone());
+        let upper_difficulty_bound_solver = CancellableSolver::new(
+            upper_difficulty_bound_solver_cons(cancel_handle.clone()),
+            cancel_handle.clone());
+        let generator_solver = CancellableSolver::new(
+            generator_solver_cons(cancel_handle.clone()),
+            cancel_handle.clone());
+        let full_sudoku_source = full_sudoku_source.clone();
+        let locks = locks.clone();
+        let thread_body: Box<dyn FnOnce() + Send> =
+            Box::new(move || gen_with_difficulty_thread(
+                lower_difficulty_bound_solver, upper_difficulty_bound_solver,
+                generator_solver, full_sudoku_source, reduction_prioritizer,
+                locks, context));
+        thread_body
+    };
 
-    for _ in 0..threads {
-        let cancel_handle = CancelHandle::new();
-        cancel_handles.push(cancel_handle.clone());
-        let result_sender = Sender::clone(&sender);
-        let lower_difficulty_bound_solver =
-            lower_difficulty_bound_solver_cons(cancel_handle.clone());
-        let upper_difficulty_bound_solver =
-            upper_difficulty_bound_solver_cons(cancel_handle.clone());
-        let generator_solver = generator_solver_cons(cancel_handle.clone());
-        thread::spawn(move || gen_with_difficulty_thread(
-            lower_difficulty_bound_solver, upper_difficulty_bound_solver,
-            generator_solver, constraint_cons, constraint_transform,
-            reduction_prioritizer, cancel_handle, result_sender));
+    GenerationTask {
+        difficulties: ReportedDifficulties::adjacent(difficulty),
+        thread_cons: Box::new(thread_cons)
     }
+}
 
-    drop(sender);
+/// Creates a [GenerationTask] for Sudoku with the constraint of the given
+/// identifier (see the crate-level documentation) and the given difficulty on
+/// the built-in [Ladder] of that constraint.
+///
+/// # Panics
+///
+/// If the constraint identifier or difficulty is invalid.
+pub(crate) fn generation_task(constraint: i32, difficulty: i32,
+        block_width: usize, block_height: usize) -> GenerationTask {
+    Ladder::builtin(constraint)
+        .and_then(|ladder| ladder_generation_task(&ladder, constraint,
+            difficulty, block_width, block_height))
+        .unwrap_or_else(|e| panic!("Invalid parameters: {}", e))
+}
 
-    let result = receiver.recv().unwrap();
+/// Creates a [GenerationTask] for Sudoku with the constraint of the given
+/// identifier (see the crate-level documentation) and the given difficulty on
+/// the given [Ladder].
+///
+/// # Errors
+///
+/// * [TaskError::InvalidConstraint] if the constraint identifier is invalid.
+/// * [TaskError::InvalidDifficulty] if the ladder has no level for the given
+/// difficulty.
+pub(crate) fn ladder_generation_task(ladder: &Ladder, constraint: i32,
+        difficulty: i32, block_width: usize, block_height: usize)
+        -> Result<GenerationTask, TaskError> {
+    match constraint {
+        0 => gen_simple(ladder, difficulty, random_source(block_width,
+            block_height, default_constraint, constraint_identity),
+            Locks::default()),
+        1 => gen_simple(ladder, difficulty, random_source(block_width,
+            block_height, diagonals_constraint, constraint_identity),
+            Locks::default()),
+        2 => gen_simple(ladder, difficulty, random_source(block_width,
+            block_height, knights_move_constraint, constraint_identity),
+            Locks::default()),
+        3 => gen_simple(ladder, difficulty, random_source(block_width,
+            block_height, kings_move_constraint, constraint_identity),
+            Locks::default()),
+        4 => gen_simple(ladder, difficulty, random_source(block_width,
+            block_height, chess_constraint, constraint_identity),
+            Locks::default()),
+        5 => gen_sandwich(ladder, difficulty, random_source(block_width,
+            block_height, default_constraint, make_sandwich_constraint),
+            prioritize, Locks::default()),
+        _ => Err(TaskError::InvalidConstraint(constraint))
+    }
+}
+
+/// Creates a [GenerationTask] for Sudoku with the constraint of the given
+/// identifier (see the crate-level documentation) which cannot be solved by
+/// the solver of the given difficulty on the built-in ladder unless the given
+/// technique is added to it (see [Ladder::technique_ladder]).
+///
+/// # Errors
+///
+/// * [TaskError::InvalidConstraint] if the constraint identifier is invalid.
+/// * Any [TaskError] returned by [Ladder::technique_ladder].
+pub(crate) fn technique_generation_task(constraint: i32, difficulty: i32,
+        technique: &StrategySpec, block_width: usize, block_height: usize)
+        -> Result<GenerationTask, TaskError> {
+    let ladder =
+        Ladder::builtin(constraint)?.technique_ladder(difficulty, technique)?;
+    let task = ladder_generation_task(&ladder, constraint, 2, block_width,
+        block_height)?;
 
-    for cancel_handle in cancel_handles {
-        cancel_handle.cancel();
+    // Sudoku that do not need the technique can still be solved at the
+    // requested level, while those that cannot be solved with it are harder.
+    Ok(task.reporting(ReportedDifficulties {
+        accepted: difficulty,
+        too_easy: difficulty,
+        too_hard: difficulty + 1
+    }))
+}
+
+fn any_generation_task<FS>(constraint: &AnyConstraint, difficulty: i32,
+    full_sudoku_source: FS, locks: Locks)
+    -> Result<GenerationTask, TaskError>
+where
+    FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<AnyConstraint>
+        + Send + Sync + Clone + 'static
+{
+    let ladder = Ladder::for_constraint(constraint);
+
+    if constraint.has_subconstraint::<SandwichConstraint>() {
+        gen_sandwich(&ladder, difficulty, full_sudoku_source, prioritize_any,
+            locks)
+    }
+    else {
+        gen_simple(&ladder, difficulty, full_sudoku_source, locks)
+    }
+}
+
+/// Checks that all sandwich constraints in the given constraint, including
+/// those nested in composite constraints, have either no clues at all or
+/// exactly one per column and row of a Sudoku of the given size.
+fn sandwich_sizes_match(constraint: &AnyConstraint, size: usize) -> bool {
+    match constraint {
+        AnyConstraint::Sandwich(c) => {
+            let clues = SandwichClues::of(c);
+            let matches = |clues: &Vec<Option<usize>>|
+                clues.is_empty() || clues.len() == size;
+            matches(&clues.columns) && matches(&clues.rows)
+        },
+        AnyConstraint::Composite(cs) =>
+            cs.iter().all(|c| sandwich_sizes_match(c, size)),
+        _ => true
+    }
+}
+
+/// Creates a [GenerationTask] for random Sudoku with the given constraint
+/// template and difficulty. Full grids are generated with all sandwich
+/// constraints of the template removed, after which the sandwich clues are
+/// computed from the grid, so the template's own clues are ignored. The
+/// difficulty ladder is built for the template (see [Ladder::for_constraint]).
+///
+/// # Arguments
+///
+/// * `template`: The constraint of the generated Sudoku.
+/// * `difficulty`: The difficulty of the generated Sudoku.
+/// * `block_width`: The width of the blocks of the generated Sudoku.
+/// * `block_height`: The height of the blocks of the generated Sudoku.
+///
+/// # Errors
+///
+/// * [TaskError::InvalidDimensions] if a block dimension is zero or the
+/// Sudoku would be larger than [MAX_TEMPLATE_SIZE].
+/// * [TaskError::MismatchedSandwich] if a sandwich constraint of the template
+/// has clues, but not one per column and row.
+/// * [TaskError::InvalidDifficulty] if the ladder of the template has no
+/// level for the difficulty.
+pub(crate) fn template_generation_task(template: AnyConstraint,
+        difficulty: i32, block_width: usize, block_height: usize)
+        -> Result<GenerationTask, TaskError> {
+    let size = block_width.checked_mul(block_height)
+        .filter(|&size| size > 0 && size <= MAX_TEMPLATE_SIZE)
+        .ok_or(TaskError::InvalidDimensions(block_width, block_height))?;
+
+    if !sandwich_sizes_match(&template, size) {
+        return Err(TaskError::MismatchedSandwich);
     }
 
-    result
+    let generation_constraint = constraint::strip_sandwiches(&template);
+    let ladder_constraint = template.clone();
+    let source = random_source(block_width, block_height,
+        move || generation_constraint.clone(),
+        move |_: AnyConstraint, grid: &SudokuGrid|
+            constraint::complete_constraint(template.clone(), grid));
+    any_generation_task(&ladder_constraint, difficulty, source,
+        Locks::default())
+}
+
+/// Creates a [GenerationTask] for Sudoku that have the given full Sudoku as
+/// their solution. The locked cells, given as column and row, keep their
+/// digit during reduction. All sandwich clues of the constraint are
+/// recomputed from the solution. The difficulty ladder is chosen according to
+/// the constraint.
+///
+/// # Errors
+///
+/// [TaskError::InvalidDifficulty] if the ladder of the constraint has no
+/// level for the difficulty.
+pub(crate) fn solution_generation_task(solution: Sudoku<AnyConstraint>,
+        difficulty: i32, locked: Vec<(usize, usize)>)
+        -> Result<GenerationTask, TaskError> {
+    let (grid, constraint) = solution.into_raw_parts();
+    let constraint = constraint::complete_constraint(constraint, &grid);
+    let ladder_constraint = constraint.clone();
+    let solution = Sudoku::new_with_grid(grid, constraint);
+    any_generation_task(&ladder_constraint, difficulty, fixed_source(solution),
+        Locks::with_cells(locked))
 }
@@ -0,0 +1,248 @@
+//! Generation primitives whose results depend only on the provided random
+//! number generator. In particular, they avoid sampling `usize` values, which
+//! `rand` does differently on 32-bit and 64-bit targets. Given the same seed,
+//! they thus produce the same Sudoku on every platform.
+//!
+//! All primitives that may run for a long time poll a [CancelHandle] and
+//! return `Err(Cancelled)` as soon as it is cancelled.
+//!
+//! These primitives are used for all generation, not only for daily puzzles,
+//! since the `Generator` and `Reducer` of `sudoku-variants` can neither be
+//! cancelled nor complete a partial Sudoku, and reduce without [Locks]. They
+//! follow the same algorithms, i.e. a randomized backtracking fill and a
+//! single pass over all reductions in prioritized random order, so the cost
+//! of generation is still dominated by the same solver calls. The timings
+//! reported by `calibrate` allow comparing the generation time per level
+//! between engine versions.
+
+use crate::generate::Locks;
+use crate::sync::{Cancelled, CancelHandle};
+
+use rand::RngCore;
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+use sudoku_variants::constraint::Constraint;
+use sudoku_variants::generator::{Reduction, ReductionPrioritizer};
+use sudoku_variants::solver::{Solution, Solver};
+
+/// Samples an index in the range `0..bound` uniformly, using only 32-bit
+/// outputs of the given RNG.
+fn random_index<R: RngCore>(rng: &mut R, bound: usize) -> usize {
+    let bound = bound as u32;
+    let zone = u32::MAX - u32::MAX % bound;
+
+    loop {
+        let value = rng.next_u32();
+
+        if value < zone {
+            return (value % bound) as usize;
+        }
+    }
+}
+
+/// Shuffles the given slice with the Fisher-Yates algorithm, using
+/// [random_index].
+pub(crate) fn shuffle<T, R: RngCore>(slice: &mut [T], rng: &mut R) {
+    for i in (1..slice.len()).rev() {
+        let j = random_index(rng, i + 1);
+        slice.swap(i, j);
+    }
+}
+
+fn options<C>(grid: &SudokuGrid, constraint: &C, column: usize, row: usize)
+    -> Vec<usize>
+where
+    C: Constraint + Clone
+{
+    (1..=grid.size())
+        .filter(|&number| constraint.check_number(grid, column, row, number))
+        .collect()
+}
+
+/// Finds the empty cell with the fewest options, preferring earlier cells in
+/// row-major order on ties. Returns `None` if the grid is full.
+fn most_constrained_cell<C>(grid: &SudokuGrid, constraint: &C)
+    -> Option<(usize, usize, Vec<usize>)>
+where
+    C: Constraint + Clone
+{
+    let size = grid.size();
+    let mut best: Option<(usize, usize, Vec<usize>)> = None;
+
+    for row in 0..size {
+        for column in 0..size {
+            if grid.get_cell(column, row).unwrap().is_some() {
+                continue;
+            }
+
+            let options = options(grid, constraint, column, row);
+
+            if best.as_ref().map_or(true, |(_, _, o)| options.len() < o.len()) {
+                let found_dead_end = options.is_empty();
+                best = Some((column, row, options));
+
+                if found_dead_end {
+                    return best;
+                }
+            }
+        }
+    }
+
+    best
+}
+
+fn fill_rec<C, R>(grid: &mut SudokuGrid, constraint: &C, rng: &mut R,
+    cancel_handle: &CancelHandle) -> Result<bool, Cancelled>
+where
+    C: Constraint + Clone,
+    R: RngCore
+{
+    cancel_handle.check()?;
+
+    let (column, row, mut options) =
+        match most_constrained_cell(grid, constraint) {
+            Some(cell) => cell,
+            None => return Ok(true)
+        };
+
+    shuffle(&mut options, rng);
+
+    for number in options {
+        grid.set_cell(column, row, number).unwrap();
+
+        if fill_rec(grid, constraint, rng, cancel_handle)? {
+            return Ok(true);
+        }
+    }
+
+    grid.clear_cell(column, row).unwrap();
+    Ok(false)
+}
+
+/// Fills all empty cells of the given Sudoku randomly such that it satisfies
+/// its constraint, keeping all digits already present. Returns `Ok(None)` if
+/// this is not possible.
+pub(crate) fn fill<C, R>(sudoku: Sudoku<C>, rng: &mut R,
+    cancel_handle: &CancelHandle) -> Result<Option<Sudoku<C>>, Cancelled>
+where
+    C: Constraint + Clone,
+    R: RngCore
+{
+    if !sudoku.is_valid() {
+        return Ok(None);
+    }
+
+    let (mut grid, constraint) = sudoku.into_raw_parts();
+
+    if fill_rec(&mut grid, &constraint, rng, cancel_handle)? {
+        Ok(Some(Sudoku::new_with_grid(grid, constraint)))
+    }
+    else {
+        Ok(None)
+    }
+}
+
+/// Generates a random full Sudoku with the given dimensions that satisfies
+/// the given constraint. Returns `Ok(None)` if no such Sudoku exists.
+pub(crate) fn generate<C, R>(block_width: usize, block_height: usize,
+    constraint: C, rng: &mut R, cancel_handle: &CancelHandle)
+    -> Result<Option<Sudoku<C>>, Cancelled>
+where
+    C: Constraint + Clone,
+    R: RngCore
+{
+    let grid = SudokuGrid::new(block_width, block_height).unwrap();
+    fill(Sudoku::new_with_grid(grid, constraint), rng, cancel_handle)
+}
+
+/// Checks whether the given Sudoku is uniquely solvable by the given solver.
+/// Since solvers give up when they are cancelled, their result is discarded
+/// and `Err(Cancelled)` is returned if the handle was cancelled meanwhile.
+fn is_unique<C, S>(grid: &SudokuGrid, constraint: &C, solver: &S,
+    cancel_handle: &CancelHandle) -> Result<bool, Cancelled>
+where
+    C: Constraint + Clone + 'static,
+    S: Solver
+{
+    let sudoku = Sudoku::new_with_grid(grid.clone(), constraint.clone());
+    let solution = solver.solve(&sudoku);
+    cancel_handle.check()?;
+    Ok(matches!(solution, Solution::Unique(_)))
+}
+
+/// Reduces the given full Sudoku as far as possible while keeping it uniquely
+/// solvable by the given solver. All reductions, i.e. removing a digit or
+/// reducing the constraint, are tried once in the order of descending
+/// priority according to the given prioritizer. Reductions with the same
+/// priority are tried in random order. Digits in the locked cells are never
+/// removed, and the constraint is not reduced below the minimum number of
+/// clues given by the [Locks].
+///
+/// Cancellation is checked before and after every reduction, so a solver that
+/// gave up because it was cancelled never decides whether a reduction is kept.
+pub(crate) fn reduce<C, S, P, R>(sudoku: Sudoku<C>, solver: &S,
+    mut prioritizer: P, locks: &Locks, rng: &mut R,
+    cancel_handle: &CancelHandle) -> Result<Sudoku<C>, Cancelled>
+where
+    C: Constraint + Clone + 'static,
+    S: Solver,
+    P: ReductionPrioritizer<Reduction<C::Reduction>>,
+    R: RngCore
+{
+    let (mut grid, mut constraint) = sudoku.into_raw_parts();
+    let solution = grid.clone();
+    let size = grid.size();
+    let mut reductions = Vec::new();
+
+    for row in 0..size {
+        for column in 0..size {
+            if !locks.cells.contains(&(column, row)) {
+                reductions.push(Reduction::RemoveDigit { column, row });
+            }
+        }
+    }
+
+    for reduction in constraint.list_reductions(&solution) {
+        reductions.push(Reduction::ReduceConstraint { reduction });
+    }
+
+    shuffle(&mut reductions, rng);
+    let mut prioritized = reductions.into_iter()
+        .map(|r| (prioritizer.rough_priority(&r), r))
+        .collect::<Vec<_>>();
+    prioritized.sort_by(|(p1, _), (p2, _)| p2.total_cmp(p1));
+
+    for (_, reduction) in prioritized {
+        cancel_handle.check()?;
+
+        match reduction {
+            Reduction::RemoveDigit { column, row } => {
+                let number = grid.get_cell(column, row).unwrap().unwrap();
+                grid.clear_cell(column, row).unwrap();
+
+                if !is_unique(&grid, &constraint, solver, cancel_handle)? {
+                    grid.set_cell(column, row, number).unwrap();
+                }
+            },
+            Reduction::ReduceConstraint { reduction } => {
+                if locks.min_constraint_clues > 0 &&
+                        constraint.list_reductions(&solution).len() <=
+                            locks.min_constraint_clues {
+                    continue;
+                }
+
+                let revert_info =
+                    match constraint.reduce(&solution, &reduction) {
+                        Ok(revert_info) => revert_info,
+                        Err(_) => continue
+                    };
+
+                if !is_unique(&grid, &constraint, solver, cancel_handle)? {
+                    constraint.revert(&solution, &reduction, revert_info);
+                }
+            }
+        }
+    }
+
+    Ok(Sudoku::new_with_grid(grid, constraint))
+}
@@ -1,27 +1,31 @@
-use crate::generate::{self, CancellableStrategy};
+use crate::constraint::{AnyConstraint, AnyReduction, SandwichClues};
+use crate::generate::{
+    portable,
+    GenerationTask,
+    Ladder,
+    Locks,
+    SourceResult,
+    TaskError
+};
 use crate::sync::CancelHandle;
 
+use serde::Deserialize;
+
+use rand::RngCore;
+
+use rand_chacha::ChaCha8Rng;
+
 use sudoku_variants::{Sudoku, SudokuGrid};
 use sudoku_variants::constraint::{
     CompositeConstraint,
     CompositeData,
+    Constraint,
     DefaultConstraint,
-    SandwichConstraint,
+    SandwichConstraint
 };
 use sudoku_variants::constraint::sandwich::SandwichReduction;
-use sudoku_variants::generator::Reduction;
-use sudoku_variants::solver::Solver;
-use sudoku_variants::solver::strategy::{
-    BoundedCellsBacktrackingStrategy,
-    BoundedOptionsBacktrackingStrategy,
-    CompositeStrategy,
-    NakedSingleStrategy,
-    NoStrategy,
-    OnlyCellStrategy,
-    StrategicBacktrackingSolver,
-    StrategicSolver,
-    TupleStrategy
-};
+use sudoku_variants::generator::{Reduction, ReductionPrioritizer};
+use sudoku_variants::solver::strategy::CompositeStrategy;
 use sudoku_variants::solver::strategy::specific::{
     SandwichBunPlacementStrategy,
     SandwichPossibilitiesStrategy
@@ -37,161 +41,216 @@ pub(crate) fn sandwich_strategy() -> SandwichStrategy {
     )
 }
 
-fn sandwich_difficulty_1(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(
-        CancellableStrategy::new(
-            CompositeStrategy::new(
-                OnlyCellStrategy,
-                sandwich_strategy()
-            ), handle
-        )
-    )
+type DefaultSandwichConstraint =
+    CompositeConstraint<DefaultConstraint, SandwichConstraint>;
+
+pub(crate) fn make_sandwich_constraint(c1: DefaultConstraint,
+        grid: &SudokuGrid) -> DefaultSandwichConstraint {
+    let c2 = SandwichConstraint::new_full(grid);
+    CompositeConstraint::new(c1, c2)
 }
 
-fn sandwich_difficulty_2(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(
-        CancellableStrategy::new(
-            CompositeStrategy::new(
-                CompositeStrategy::new(
-                    OnlyCellStrategy,
-                    NakedSingleStrategy
-                ),
-                CompositeStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy),
-                    sandwich_strategy()
-                )
-            ), handle
-        )
-    )
+pub(crate) fn prioritize(
+        reduction: &Reduction<CompositeData<(), SandwichReduction>>) -> f64 {
+    match reduction {
+        Reduction::RemoveDigit { .. } => 0.0,
+        Reduction::ReduceConstraint { .. } => 100.0
+    }
 }
 
-fn sandwich_difficulty_3(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(CancellableStrategy::new(CompositeStrategy::new(
-        CompositeStrategy::new(
-            OnlyCellStrategy,
-            NakedSingleStrategy
-        ),
-        CompositeStrategy::new(
-            BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(1),
-                CancellableStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy),
-                    handle.clone())),
-            CompositeStrategy::new(
-                TupleStrategy::new(|_| 2),
-                sandwich_strategy()
-            )
-        )
-    ), handle))
+/// The opposite of [prioritize]: all digits are tried to be removed before
+/// any sandwich clue, which allows Sudoku without givens.
+pub(crate) fn prioritize_digits(
+        reduction: &Reduction<CompositeData<(), SandwichReduction>>) -> f64 {
+    match reduction {
+        Reduction::RemoveDigit { .. } => 100.0,
+        Reduction::ReduceConstraint { .. } => 0.0
+    }
 }
 
-fn sandwich_difficulty_4(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(CancellableStrategy::new(
-        CompositeStrategy::new(
-            CompositeStrategy::new(
-                OnlyCellStrategy,
-                NakedSingleStrategy
-            ),
-            CompositeStrategy::new(
-                CompositeStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(1),
-                        CancellableStrategy::new(
-                            BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy),
-                            handle.clone())
-                        ),
-                    TupleStrategy::new(|_| 3)
-                ),
-                CompositeStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(2),
-                        CompositeStrategy::new(
-                            OnlyCellStrategy,
-                            NakedSingleStrategy
-                        )
-                    ),
-                    CompositeStrategy::new(
-                        BoundedOptionsBacktrackingStrategy::new(|_| 2, |_| Some(2),
-                            CompositeStrategy::new(
-                                OnlyCellStrategy,
-                                NakedSingleStrategy
-                            )
-                        ),
-                        sandwich_strategy()
-                    )
-                )
-            )
-        ), handle)
-    )
+/// Equivalent to [prioritize] for Sudoku with an [AnyConstraint].
+pub(crate) fn prioritize_any(reduction: &Reduction<AnyReduction>) -> f64 {
+    match reduction {
+        Reduction::RemoveDigit { .. } => 0.0,
+        Reduction::ReduceConstraint { .. } => 100.0
+    }
 }
 
-fn sandwich_difficulty_5(handle: CancelHandle) -> impl Solver {
-    StrategicBacktrackingSolver::new(
-        CancellableStrategy::new(
-            CompositeStrategy::new(
-                CompositeStrategy::new(
-                    OnlyCellStrategy,
-                    NakedSingleStrategy
-                ),
-                sandwich_strategy()
-            ),
-            handle
-        )
-    )
+/// Creates a [GenerationTask] for sandwich Sudoku on the given [Ladder],
+/// trying reductions in the order given by the prioritizer.
+///
+/// # Errors
+///
+/// [TaskError::InvalidDifficulty] if the ladder has no level for the given
+/// difficulty.
+pub(crate) fn gen_sandwich<C, FS, P>(ladder: &Ladder, difficulty: i32,
+    full_sudoku_source: FS, reduction_prioritizer: P, locks: Locks)
+    -> Result<GenerationTask, TaskError>
+where
+    C: Constraint + Clone + Into<AnyConstraint> + Send + 'static,
+    FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C> + Send + Sync
+        + Clone + 'static,
+    P: ReductionPrioritizer<Reduction<C::Reduction>> + Clone + Copy + Send
+        + Sync + 'static
+{
+    ladder.generation_task(difficulty, full_sudoku_source,
+        reduction_prioritizer, locks)
 }
 
-type DefaultSandwichConstraint =
-    CompositeConstraint<DefaultConstraint, SandwichConstraint>;
+/// The lines which may have sandwich clues.
+#[derive(Clone, Copy, Deserialize, Eq, PartialEq)]
+pub(crate) enum SandwichLines {
 
-fn make_sandwich_constraint(c1: DefaultConstraint, grid: &SudokuGrid)
-        -> DefaultSandwichConstraint {
-    let c2 = SandwichConstraint::new_full(grid);
-    CompositeConstraint::new(c1, c2)
+    /// Both rows and columns may have sandwich clues.
+    #[serde(rename = "both")]
+    Both,
+
+    /// Only rows may have sandwich clues.
+    #[serde(rename = "rows")]
+    Rows,
+
+    /// Only columns may have sandwich clues.
+    #[serde(rename = "columns")]
+    Columns
 }
 
-fn prioritize(reduction: &Reduction<CompositeData<(), SandwichReduction>>)
-        -> f64 {
-    match reduction {
-        Reduction::RemoveDigit { .. } => 0.0,
-        Reduction::ReduceConstraint { .. } => 100.0
+impl SandwichLines {
+    fn allows_columns(self) -> bool {
+        self != SandwichLines::Rows
+    }
+
+    fn allows_rows(self) -> bool {
+        self != SandwichLines::Columns
+    }
+}
+
+/// Options for generating sandwich Sudoku with a specific distribution of
+/// clues. To be deserialized from the input, where all fields are optional.
+/// The default profile generates the same kind of Sudoku as constraint 5.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct SandwichProfile {
+
+    /// The minimum number of sandwich clues that remain in the generated
+    /// Sudoku. If it is at least the number of available clues, all of them
+    /// are kept and only the givens vary.
+    pub(crate) min_clues: usize,
+
+    /// The maximum number of sandwich clues that remain in the generated
+    /// Sudoku, or `None` if it is not restricted. Clues beyond this number are
+    /// removed at random before the Sudoku is reduced.
+    pub(crate) max_clues: Option<usize>,
+
+    /// Whether digits are removed before any sandwich clue, which allows
+    /// Sudoku without givens. Otherwise, sandwich clues are removed first.
+    pub(crate) zero_givens: bool,
+
+    /// The lines which may have sandwich clues.
+    pub(crate) lines: SandwichLines
+}
+
+impl Default for SandwichProfile {
+    fn default() -> SandwichProfile {
+        SandwichProfile {
+            min_clues: 0,
+            max_clues: None,
+            zero_givens: false,
+            lines: SandwichLines::Both
+        }
+    }
+}
+
+/// Removes all sandwich clues of lines which the profile does not allow and,
+/// if necessary, random other clues until at most the maximum number is left.
+fn restrict_clues<R>(constraint: &SandwichConstraint,
+    profile: &SandwichProfile, rng: &mut R) -> SandwichConstraint
+where
+    R: RngCore
+{
+    let mut clues = SandwichClues::of(constraint);
+
+    if !profile.lines.allows_columns() {
+        clues.columns.iter_mut().for_each(|clue| *clue = None);
+    }
+
+    if !profile.lines.allows_rows() {
+        clues.rows.iter_mut().for_each(|clue| *clue = None);
     }
+
+    if let Some(max_clues) = profile.max_clues {
+        let mut present = clues.columns.iter_mut()
+            .chain(clues.rows.iter_mut())
+            .filter(|clue| clue.is_some())
+            .collect::<Vec<_>>();
+        portable::shuffle(&mut present, rng);
+
+        for clue in present.into_iter().skip(max_clues) {
+            *clue = None;
+        }
+    }
+
+    clues.into_constraint()
 }
 
-pub(crate) fn gen_sandwich(difficulty: i32)
-        -> Sudoku<DefaultSandwichConstraint> {
-    match difficulty {
-        1 => generate::gen_with_difficulty(
-            generate::difficulty_0,
-            sandwich_difficulty_1,
-            sandwich_difficulty_1,
-            generate::default_constraint,
-            make_sandwich_constraint,
-            prioritize),
-        2 => generate::gen_with_difficulty(
-            sandwich_difficulty_1,
-            sandwich_difficulty_2,
-            sandwich_difficulty_2,
-            generate::default_constraint,
-            make_sandwich_constraint,
-            prioritize),
-        3 => generate::gen_with_difficulty(
-            sandwich_difficulty_2,
-            sandwich_difficulty_3,
-            sandwich_difficulty_5,
-            generate::default_constraint,
-            make_sandwich_constraint,
-            prioritize),
-        4 => generate::gen_with_difficulty(
-            sandwich_difficulty_3,
-            sandwich_difficulty_4,
-            sandwich_difficulty_5,
-            generate::default_constraint,
-            make_sandwich_constraint,
-            prioritize),
-        5 => generate::gen_with_difficulty(
-            sandwich_difficulty_4,
-            generate::difficulty_inf,
-            sandwich_difficulty_5,
-            generate::default_constraint,
-            make_sandwich_constraint,
-            prioritize),
-        _ => panic!("Invalid difficulty: {}", difficulty)
+fn profile_source(block_width: usize, block_height: usize,
+    profile: SandwichProfile)
+    -> impl Fn(&mut ChaCha8Rng, &CancelHandle)
+        -> SourceResult<DefaultSandwichConstraint> + Send + Sync + Clone
+        + 'static
+{
+    move |rng: &mut ChaCha8Rng, cancel_handle: &CancelHandle| {
+        let sudoku = match portable::generate(block_width, block_height,
+                DefaultConstraint, rng, cancel_handle)? {
+            Some(sudoku) => sudoku,
+            None => return Ok(None)
+        };
+        let (grid, constraint) = sudoku.into_raw_parts();
+        let sandwich = restrict_clues(&SandwichConstraint::new_full(&grid),
+            &profile, rng);
+        let constraint = CompositeConstraint::new(constraint, sandwich);
+        Ok(Some(Sudoku::new_with_grid(grid, constraint)))
+    }
+}
+
+/// Creates a [GenerationTask] for sandwich Sudoku of the given difficulty
+/// whose clues are distributed according to the given [SandwichProfile].
+///
+/// # Arguments
+///
+/// * `profile`: The profile which restricts the sandwich clues and givens.
+/// * `difficulty`: The difficulty of the generated Sudoku on the sandwich
+/// ladder.
+/// * `block_width`: The width of the blocks of the generated Sudoku.
+/// * `block_height`: The height of the blocks of the generated Sudoku.
+///
+/// # Errors
+///
+/// * [TaskError::InvalidClueRange] if the profile requires more clues than it
+/// allows.
+/// * [TaskError::InvalidDifficulty] if the sandwich ladder has no level for
+/// the difficulty.
+pub(crate) fn sandwich_profile_generation_task(profile: SandwichProfile,
+        difficulty: i32, block_width: usize, block_height: usize)
+        -> Result<GenerationTask, TaskError> {
+    if let Some(max_clues) = profile.max_clues {
+        if max_clues < profile.min_clues {
+            return Err(
+                TaskError::InvalidClueRange(profile.min_clues, max_clues));
+        }
+    }
+
+    let ladder = Ladder::sandwich_ladder();
+    let locks = Locks {
+        cells: Vec::new(),
+        min_constraint_clues: profile.min_clues
+    };
+    let zero_givens = profile.zero_givens;
+    let source = profile_source(block_width, block_height, profile);
+
+    if zero_givens {
+        gen_sandwich(&ladder, difficulty, source, prioritize_digits, locks)
+    }
+    else {
+        gen_sandwich(&ladder, difficulty, source, prioritize, locks)
     }
 }
@@ -1,10 +1,15 @@
 use crate::constraint::AnyConstraint;
-use crate::generate::{self, CancellableStrategy};
+use crate::generate::{
+    GenerationTask,
+    Ladder,
+    Locks,
+    SourceResult,
+    TaskError
+};
 use crate::sync::CancelHandle;
 
-use serde::Serialize;
+use rand_chacha::ChaCha8Rng;
 
-use sudoku_variants::Sudoku;
 use sudoku_variants::constraint::{
     CompositeConstraint,
     Constraint,
@@ -14,96 +19,6 @@ use sudoku_variants::constraint::{
     KnightsMoveConstraint
 };
 use sudoku_variants::generator::ReductionPrioritizer;
-use sudoku_variants::solver::Solver;
-use sudoku_variants::solver::strategy::{
-    BoundedCellsBacktrackingStrategy,
-    BoundedOptionsBacktrackingStrategy,
-    CompositeStrategy,
-    NakedSingleStrategy,
-    NoStrategy,
-    OnlyCellStrategy,
-    StrategicBacktrackingSolver,
-    StrategicSolver,
-    TupleStrategy
-};
-
-fn default_difficulty_1(_: CancelHandle) -> impl Solver {
-    StrategicSolver::new(OnlyCellStrategy)
-}
-
-fn default_difficulty_2(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(
-        CancellableStrategy::new(
-            CompositeStrategy::new(
-                CompositeStrategy::new(
-                    OnlyCellStrategy,
-                    NakedSingleStrategy
-                ),
-                BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy)
-            ), handle
-        )
-    )
-}
-
-fn default_difficulty_3(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(CancellableStrategy::new(CompositeStrategy::new(
-        CompositeStrategy::new(
-            OnlyCellStrategy,
-            NakedSingleStrategy
-        ),
-        CompositeStrategy::new(
-            BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(1),
-                CancellableStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy),
-                    handle.clone())),
-            TupleStrategy::new(|_| 2)
-        )
-    ), handle))
-}
-
-fn default_difficulty_4(handle: CancelHandle) -> impl Solver {
-    StrategicSolver::new(CancellableStrategy::new(
-        CompositeStrategy::new(
-            CompositeStrategy::new(
-                OnlyCellStrategy,
-                NakedSingleStrategy
-            ),
-            CompositeStrategy::new(
-                CompositeStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(1),
-                        CancellableStrategy::new(
-                            BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(0), NoStrategy),
-                            handle.clone())
-                        ),
-                    TupleStrategy::new(|_| 3)
-                ),
-                CompositeStrategy::new(
-                    BoundedCellsBacktrackingStrategy::new(|_| 2, |_| Some(2),
-                        CompositeStrategy::new(
-                            OnlyCellStrategy,
-                            NakedSingleStrategy
-                        )
-                    ),
-                    BoundedOptionsBacktrackingStrategy::new(|_| 2, |_| Some(2),
-                        CompositeStrategy::new(
-                            OnlyCellStrategy,
-                            NakedSingleStrategy
-                        )
-                    )
-                )
-            )
-        ), handle)
-    )
-}
-
-fn default_difficulty_5(_: CancelHandle) -> impl Solver {
-    StrategicBacktrackingSolver::new(
-        CompositeStrategy::new(
-            OnlyCellStrategy,
-            NakedSingleStrategy
-        )
-    )
-}
 
 type DefaultDiagonalsConstraint =
     CompositeConstraint<DefaultConstraint, DiagonalsConstraint>;
@@ -160,47 +75,21 @@ impl<R> ReductionPrioritizer<R> for EqualPrioritizer {
     }
 }
 
-pub(crate) fn gen_simple<C, FC>(difficulty: i32, constraint_cons: FC) -> Sudoku<C>
+/// Creates a [GenerationTask] for Sudoku without constraint-specific
+/// reductions on the given [Ladder], trying all reductions in random order.
+///
+/// # Errors
+///
+/// [TaskError::InvalidDifficulty] if the ladder has no level for the given
+/// difficulty.
+pub(crate) fn gen_simple<C, FS>(ladder: &Ladder, difficulty: i32,
+    full_sudoku_source: FS, locks: Locks)
+    -> Result<GenerationTask, TaskError>
 where
-    C: Constraint + Clone + Into<AnyConstraint> + Send + Serialize + 'static,
-    FC: Fn() -> C + Send + Copy + 'static
+    C: Constraint + Clone + Into<AnyConstraint> + Send + 'static,
+    FS: Fn(&mut ChaCha8Rng, &CancelHandle) -> SourceResult<C> + Send + Sync
+        + Clone + 'static
 {
-    match difficulty {
-        1 => generate::gen_with_difficulty(
-            generate::difficulty_0,
-            default_difficulty_1,
-            default_difficulty_1,
-            constraint_cons,
-            generate::constraint_identity,
-            EqualPrioritizer),
-        2 => generate::gen_with_difficulty(
-            default_difficulty_1,
-            default_difficulty_2,
-            default_difficulty_2,
-            constraint_cons,
-            generate::constraint_identity,
-            EqualPrioritizer),
-        3 => generate::gen_with_difficulty(
-            default_difficulty_2,
-            default_difficulty_3,
-            default_difficulty_5,
-            constraint_cons,
-            generate::constraint_identity,
-            EqualPrioritizer),
-        4 => generate::gen_with_difficulty(
-            default_difficulty_3,
-            default_difficulty_4,
-            default_difficulty_5,
-            constraint_cons,
-            generate::constraint_identity,
-            EqualPrioritizer),
-        5 => generate::gen_with_difficulty(
-            default_difficulty_4,
-            generate::difficulty_inf,
-            default_difficulty_5,
-            constraint_cons,
-            generate::constraint_identity,
-            EqualPrioritizer),
-        _ => panic!("Invalid difficulty: {}", difficulty)
-    }
+    ladder.generation_task(difficulty, full_sudoku_source, EqualPrioritizer,
+        locks)
 }
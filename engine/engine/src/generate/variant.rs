@@ -0,0 +1,206 @@
+//! Strategies for variants whose constraints relate cells that do not share a
+//! group, i.e. knight's move and king's move Sudoku. The strategies of
+//! `sudoku-variants` only consider groups, so on their own they miss
+//! deductions that a human solver of these variants would make.
+
+use serde::{Deserialize, Serialize};
+
+use sudoku_variants::constraint::Constraint;
+use sudoku_variants::solver::strategy::{Strategy, SudokuInfo};
+
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)
+];
+
+const KING_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)
+];
+
+/// A relation between cells that must not contain the same digit without
+/// sharing a group.
+#[derive(Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum Relation {
+
+    /// Cells which are a knight's move apart, as in knight's move Sudoku.
+    #[serde(rename = "knight")]
+    Knight,
+
+    /// Cells which are a king's move apart, as in king's move Sudoku.
+    #[serde(rename = "king")]
+    King
+}
+
+impl Relation {
+
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Relation::Knight => &KNIGHT_OFFSETS,
+            Relation::King => &KING_OFFSETS
+        }
+    }
+
+    /// Gets all cells, as column and row, in a grid of the given size which
+    /// are related to the given cell.
+    pub(crate) fn neighbours(self, column: usize, row: usize, size: usize)
+            -> impl Iterator<Item = (usize, usize)> {
+        self.offsets().iter()
+            .map(move |&(dc, dr)|
+                (column as isize + dc, row as isize + dr))
+            .filter(move |&(c, r)|
+                c >= 0 && r >= 0 && c < size as isize && r < size as isize)
+            .map(|(c, r)| (c as usize, r as usize))
+    }
+
+    /// Indicates whether the two given cells, as column and row, are related.
+    pub(crate) fn relates(self, (c1, r1): (usize, usize),
+            (c2, r2): (usize, usize)) -> bool {
+        let dc = c2 as isize - c1 as isize;
+        let dr = r2 as isize - r1 as isize;
+        self.offsets().contains(&(dc, dr))
+    }
+}
+
+/// A [Strategy] which removes the digit of every filled cell from the options
+/// of all cells related to it by the wrapped [Relation].
+pub(crate) struct NeighbourEliminationStrategy {
+    relation: Relation
+}
+
+impl NeighbourEliminationStrategy {
+    pub(crate) fn new(relation: Relation) -> NeighbourEliminationStrategy {
+        NeighbourEliminationStrategy {
+            relation
+        }
+    }
+}
+
+impl Strategy for NeighbourEliminationStrategy {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        let size = sudoku_info.size();
+        let mut changed = false;
+
+        for row in 0..size {
+            for column in 0..size {
+                let number = match sudoku_info.get_cell(column, row).unwrap() {
+                    Some(number) => number,
+                    None => continue
+                };
+
+                for (other_column, other_row) in
+                        self.relation.neighbours(column, row, size) {
+                    if sudoku_info.get_cell(other_column, other_row).unwrap()
+                            .is_none() {
+                        changed |= sudoku_info
+                            .get_options_mut(other_column, other_row)
+                            .unwrap()
+                            .remove(number)
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+/// A [Strategy] which, for every group and digit, finds all cells that see
+/// every cell of the group where the digit can still go, and removes the
+/// digit from their options. Cells see each other if they share a group or
+/// are related by one of the wrapped [Relation]s. Without relations, this is
+/// the classic pointing and claiming technique, including intersections with
+/// extra groups such as diagonals.
+pub(crate) struct PointingStrategy {
+    relations: Vec<Relation>
+}
+
+impl PointingStrategy {
+    pub(crate) fn new(relations: Vec<Relation>) -> PointingStrategy {
+        PointingStrategy {
+            relations
+        }
+    }
+
+    fn sees(&self, cell_groups: &[Vec<usize>], size: usize,
+            (c1, r1): (usize, usize), (c2, r2): (usize, usize)) -> bool {
+        if (c1, r1) == (c2, r2) {
+            return false;
+        }
+
+        let groups_1 = &cell_groups[r1 * size + c1];
+        let groups_2 = &cell_groups[r2 * size + c2];
+
+        groups_1.iter().any(|group| groups_2.contains(group)) ||
+            self.relations.iter()
+                .any(|relation| relation.relates((c1, r1), (c2, r2)))
+    }
+}
+
+impl Strategy for PointingStrategy {
+    fn apply<C>(&self, sudoku_info: &mut SudokuInfo<C>) -> bool
+    where
+        C: Constraint + Clone + 'static
+    {
+        let size = sudoku_info.size();
+        let groups = {
+            let sudoku = sudoku_info.sudoku();
+            sudoku.constraint().get_groups(sudoku.grid())
+        };
+        let mut cell_groups = vec![Vec::new(); size * size];
+        let mut changed = false;
+
+        for (index, group) in groups.iter().enumerate() {
+            for &(column, row) in group {
+                cell_groups[row * size + column].push(index);
+            }
+        }
+
+        for group in &groups {
+            for number in 1..=size {
+                let mut candidates = Vec::new();
+                let mut placed = false;
+
+                for &(column, row) in group {
+                    match sudoku_info.get_cell(column, row).unwrap() {
+                        Some(n) if n == number => placed = true,
+                        Some(_) => { },
+                        None => if sudoku_info.get_options(column, row)
+                                .unwrap().contains(number) {
+                            candidates.push((column, row));
+                        }
+                    }
+                }
+
+                if placed || candidates.is_empty() {
+                    continue;
+                }
+
+                for row in 0..size {
+                    for column in 0..size {
+                        if sudoku_info.get_cell(column, row).unwrap().is_some()
+                                || candidates.contains(&(column, row)) {
+                            continue;
+                        }
+
+                        let sees_all = candidates.iter().all(|&candidate|
+                            self.sees(&cell_groups, size, (column, row),
+                                candidate));
+
+                        if sees_all {
+                            changed |= sudoku_info
+                                .get_options_mut(column, row)
+                                .unwrap()
+                                .remove(number)
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+}
@@ -11,57 +11,163 @@
 //! * `3` for king's move Sudoku
 //! * `4` for Chess Sudoku (knight's move + king's move)
 //! * `5` for sandwich Sudoku
+//!
+//! Sudoku are passed to and returned from the engine as versioned JSON
+//! documents. Documents of older versions are upgraded when they are read,
+//! see [schema].
 
 use crate::check_response::CheckResponse;
 use crate::constraint::AnyConstraint;
+use crate::daily_response::DailyResponse;
 use crate::fill_response::FillResponse;
+use crate::fpuzzles_response::FPuzzlesResponse;
+use crate::gen_from_response::{
+    GenFromRequest,
+    GenFromResponse,
+    GenTemplateRequest
+};
+use crate::gen_metadata::GeneratedSudoku;
+use crate::gen_response::GenResponse;
+use crate::gen_with_response::GenWithResponse;
+use crate::generate::{
+    portable,
+    GenerationTask,
+    Ladder,
+    SandwichProfile,
+    StrategySpec,
+    TaskError
+};
+use crate::pack::{Pack, PackError, PackQuery};
+use crate::pack_response::PackResponse;
+use crate::parse_line_response::ParseLineResponse;
+use crate::pool::{PoolKey, PuzzlePool};
+use crate::render::Booklet;
+use crate::render_request::{RenderRequest, TextRequest};
+use crate::schema_response::SchemaResponse;
+use crate::share_code_response::ShareCodeResponse;
+use crate::sudokupad_response::SudokuPadResponse;
+use crate::sync::{Cancelled, CancelHandle};
+use crate::transform::Transform;
+use crate::transform_response::TransformResponse;
+use crate::workers::Priority;
 
 use serde::{Deserialize, Serialize};
 
 use std::ffi::{CStr, CString};
 use std::mem;
 use std::os::raw::c_char;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use sudoku_variants::Sudoku;
-use sudoku_variants::constraint::Constraint;
 use sudoku_variants::solver::Solution;
 
+pub mod calibrate;
+pub mod constraint;
+pub mod fpuzzles;
+pub mod line_format;
+pub mod pack;
+pub mod render;
+pub mod schema;
+pub mod share_code;
+pub mod sudokupad;
+
+mod canonical;
 mod check_response;
-mod constraint;
+mod daily;
+mod daily_response;
 mod fill_response;
+mod fpuzzles_response;
+mod gen_from_response;
+mod gen_metadata;
+mod gen_response;
+mod gen_with_response;
 mod generate;
+mod lz_string;
+mod pack_response;
+mod parse_line_response;
+mod pool;
+mod render_request;
+mod schema_response;
+mod share_code_response;
 mod solve;
+mod sudokupad_response;
 mod sync;
+#[cfg(test)]
+mod test_util;
+mod transform;
+mod transform_response;
+mod workers;
+
+/// The number of background threads refilling the [PuzzlePool].
+const POOL_REFILL_THREADS: usize = 1;
+
+/// The time for which each pool refill thread pauses after generating a
+/// puzzle.
+const POOL_THROTTLE: Duration = Duration::from_secs(2);
+
+/// The time after which generation from setter input or a constraint template
+/// falls back to the closest Sudoku found, since the locked cells, the given
+/// solution, or the constraint may make the requested difficulty unreachable.
+/// For a partial Sudoku, this includes the time to complete it.
+const GEN_FROM_DEADLINE: Duration = Duration::from_secs(10);
+
+/// The time after which generation with host-supplied parameters, such as a
+/// required technique or a sandwich profile, falls back to the closest Sudoku
+/// found, since the parameters may make the requested difficulty unreachable.
+const GEN_WITH_DEADLINE: Duration = Duration::from_secs(10);
+
+/// The time after which generation with host-supplied parameters gives up if
+/// no Sudoku at all has been found, e.g. because the generator of a custom
+/// ladder cannot reduce any full Sudoku to a unique one.
+const GEN_WITH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The puzzle pool opened via `pool_open`, if any.
+static POOL: Mutex<Option<Arc<PuzzlePool>>> = Mutex::new(None);
+
+/// The result of loading the most recent puzzle pack together with the JSON
+/// code of the pack, so paging through the same pack does not solve all its
+/// entries again.
+static LOADED_PACK: Mutex<Option<(String, Arc<Result<Pack, PackError>>)>> =
+    Mutex::new(None);
+
+fn to_ffi_string(s: String) -> *const c_char {
+    let s_c = CString::new(s).unwrap();
+    let s_ptr = s_c.as_ptr();
+    mem::forget(s_c);
+    s_ptr
+}
 
 fn to_ffi_json(s: &impl Serialize) -> *const c_char {
-    let json = serde_json::to_string(s).unwrap();
-    let json_c = CString::new(json).unwrap();
-    let json_ptr = json_c.as_ptr();
-    mem::forget(json_c);
-    json_ptr
+    to_ffi_string(serde_json::to_string(s).unwrap())
 }
 
-fn sudoku_to_ffi_json<C>(sudoku: Sudoku<C>) -> *const c_char
+fn from_ffi_json<T>(json: *const c_char) -> T
 where
-    C: Clone + Constraint + Into<AnyConstraint>
+    for<'de> T: Deserialize<'de>
 {
-    let (grid, constraint) = sudoku.into_raw_parts();
-    let constraint: AnyConstraint = constraint.into();
-    let sudoku = Sudoku::new_with_grid(grid, constraint);
-
-    to_ffi_json(&sudoku)
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    serde_json::from_str(json).unwrap()
 }
 
-fn from_ffi_json<T>(json: *const c_char) -> T
+fn try_from_ffi_json<T>(json: *const c_char) -> Result<T, TaskError>
 where
     for<'de> T: Deserialize<'de>
 {
     let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
-    serde_json::from_str(json).unwrap()
+    serde_json::from_str(json)
+        .map_err(|e| TaskError::InvalidJson(e.to_string()))
+}
+
+fn from_ffi_sudoku(json: *const c_char) -> Sudoku<AnyConstraint> {
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    schema::from_json(json).unwrap()
 }
 
 /// Generates a 9x9 Sudoku with the provided constraint and difficulty and
-/// returns its JSON serialization.
+/// returns its JSON serialization. In addition to the fields of the Sudoku,
+/// it contains a `metadata` field with information about the generation, see
+/// [GenMetadata](gen_metadata::GenMetadata).
 ///
 /// # Arguments
 ///
@@ -71,32 +177,407 @@ where
 /// 5 (both inclusive).
 #[no_mangle]
 pub extern fn gen(constraint: i32, difficulty: i32) -> *const c_char {
-    match constraint {
-        0 => sudoku_to_ffi_json(generate::gen_simple(
-            difficulty, 
-            generate::default_constraint
-        )),
-        1 => sudoku_to_ffi_json(generate::gen_simple(
-            difficulty, 
-            generate::diagonals_constraint
-        )),
-        2 => sudoku_to_ffi_json(generate::gen_simple(
-            difficulty, 
-            generate::knights_move_constraint
-        )),
-        3 => sudoku_to_ffi_json(generate::gen_simple(
-            difficulty, 
-            generate::kings_move_constraint
-        )),
-        4 => sudoku_to_ffi_json(generate::gen_simple(
-            difficulty, 
-            generate::chess_constraint
-        )),
-        5 => sudoku_to_ffi_json(generate::gen_sandwich(difficulty)),
-        _ => panic!("Invalid constraint identifier: {}", constraint)
+    let generated = generate::generation_task(constraint, difficulty, 3, 3)
+        .run(Priority::Interactive, None, &CancelHandle::new())
+        .unwrap();
+    to_ffi_json(&GeneratedSudoku::from_generated(generated))
+}
+
+/// Returns the built-in difficulty ladder of the provided constraint in JSON
+/// form. It can be used as a starting point for custom ladders passed to
+/// `gen_with_ladder`.
+///
+/// # Arguments
+///
+/// * `constraint`: A identifier for the constraint. For valid values, please
+/// refer to the crate-level documentation.
+#[no_mangle]
+pub extern fn ladder(constraint: i32) -> *const c_char {
+    let ladder = Ladder::builtin(constraint)
+        .unwrap_or_else(|e| panic!("Invalid parameters: {}", e));
+    to_ffi_json(&ladder)
+}
+
+/// Generates a 9x9 Sudoku with the provided constraint on a custom difficulty
+/// ladder. Returns a [GenWithResponse] in JSON form, which is an error if the
+/// ladder is malformed, cannot generate Sudoku for all of its levels (see
+/// [Ladder::validate](generate::Ladder::validate)), or has no level for the
+/// difficulty. If no Sudoku of the requested difficulty is found within
+/// [GEN_WITH_DEADLINE], the closest one is returned as a fallback. If none is
+/// found at all within [GEN_WITH_TIMEOUT], a timeout is returned.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the [Ladder](generate::Ladder) to use. See
+/// `ladder` for the built-in ones.
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku, i.e. the 1-based
+/// index of the level on the ladder.
+#[no_mangle]
+pub extern fn gen_with_ladder(json: *const c_char, constraint: i32,
+        difficulty: i32) -> *const c_char {
+    let task = try_from_ffi_json(json).and_then(|ladder: Ladder| {
+        ladder.validate()?;
+        generate::ladder_generation_task(&ladder, constraint, difficulty, 3, 3)
+    });
+    run_gen_with(task)
+}
+
+/// Runs the given task, if it could be created, with [GEN_WITH_DEADLINE] and
+/// returns the result as a [GenWithResponse] in JSON form. If no Sudoku at all
+/// is found within [GEN_WITH_TIMEOUT], a timeout is returned.
+fn run_gen_with(task: Result<GenerationTask, TaskError>) -> *const c_char {
+    let task = match task {
+        Ok(task) => task,
+        Err(e) => return to_ffi_json(&GenWithResponse::Error(e))
+    };
+    let start = Instant::now();
+    let cancel_handle = CancelHandle::with_deadline(start + GEN_WITH_TIMEOUT);
+    let generated = task.run(Priority::Interactive,
+        Some(start + GEN_WITH_DEADLINE), &cancel_handle);
+    to_ffi_json(&GenWithResponse::from_run(generated))
+}
+
+/// Generates a 9x9 Sudoku with the provided constraint which cannot be solved
+/// without the provided technique, but can be solved once it is allowed.
+/// Returns a [GenWithResponse] in JSON form. If no such Sudoku is found
+/// within [GEN_WITH_DEADLINE], the closest one is returned as a fallback.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the [StrategySpec](generate::StrategySpec) of
+/// the required technique, e.g. `{"type":"tuple","value":{"max_size":3}}`
+/// for naked triples. It must be part of the solver of the level of the given
+/// difficulty on the built-in ladder.
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty on the built-in ladder whose solver, without
+/// the technique, must fail to solve the Sudoku. Its level must have a
+/// strategic solver, i.e. it must be from 1 to 4 (both inclusive).
+#[no_mangle]
+pub extern fn gen_with_technique(json: *const c_char, constraint: i32,
+        difficulty: i32) -> *const c_char {
+    let task = try_from_ffi_json(json).and_then(|technique: StrategySpec|
+        generate::technique_generation_task(constraint, difficulty,
+            &technique, 3, 3));
+    run_gen_with(task)
+}
+
+/// Generates a 9x9 sandwich Sudoku with the provided difficulty whose clues are
+/// distributed according to the provided profile. Returns a [GenWithResponse]
+/// in JSON form. If no such Sudoku is found within [GEN_WITH_DEADLINE], the
+/// closest one is returned as a fallback.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of a [SandwichProfile](generate::SandwichProfile).
+/// All of its fields are optional, e.g. `{"min_clues":18}` keeps all sandwich
+/// clues and `{"zero_givens":true,"lines":"rows"}` allows puzzles with only
+/// row clues and no givens. The minimum number of clues must not exceed the
+/// maximum.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn gen_sandwich_with_profile(json: *const c_char,
+        difficulty: i32) -> *const c_char {
+    let task = try_from_ffi_json(json).and_then(|profile: SandwichProfile|
+        generate::sandwich_profile_generation_task(profile, difficulty, 3, 3));
+    run_gen_with(task)
+}
+
+/// Generates a 9x9 Sudoku with the provided constraint and difficulty, giving
+/// up after the provided deadline. Returns a [GenResponse] in JSON form. If
+/// no Sudoku of the requested difficulty was found in time, the closest one
+/// found is returned with its measured difficulty and the `fallback` flag set.
+/// If no Sudoku was found at all in time, this waits for the first one.
+///
+/// # Arguments
+///
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+/// * `deadline_millis`: The number of milliseconds after which generation
+/// falls back to the closest Sudoku found.
+#[no_mangle]
+pub extern fn gen_with_deadline(constraint: i32, difficulty: i32,
+        deadline_millis: u64) -> *const c_char {
+    let deadline = Instant::now() + Duration::from_millis(deadline_millis);
+    let generated = generate::generation_task(constraint, difficulty, 3, 3)
+        .run(Priority::Interactive, Some(deadline), &CancelHandle::new())
+        .unwrap();
+    to_ffi_json(&GenResponse::from_generated(generated))
+}
+
+/// Generates a batch of distinct 9x9 Sudoku with the provided constraint and
+/// difficulty. All worker threads keep generating until the batch is
+/// complete. Each Sudoku is passed to the given callback in JSON form as soon
+/// as it is found, including its metadata like with `gen`. The string is only
+/// valid for the duration of the callback.
+///
+/// # Arguments
+///
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+/// * `count`: The number of Sudoku to generate.
+/// * `callback`: A function that is called with the JSON code of each
+/// generated Sudoku.
+#[no_mangle]
+pub extern fn gen_batch(constraint: i32, difficulty: i32, count: i32,
+        callback: extern fn(*const c_char)) {
+    let task = generate::generation_task(constraint, difficulty, 3, 3);
+    task.run_batch(count.max(0) as usize, Priority::Normal,
+        &CancelHandle::new(), |generated| {
+        let sudoku = GeneratedSudoku::from_generated(generated);
+        let json = serde_json::to_string(&sudoku).unwrap();
+        let json_c = CString::new(json).unwrap();
+        callback(json_c.as_ptr());
+    });
+}
+
+/// Generates a batch of distinct 9x9 Sudoku with the provided constraint and
+/// difficulty, like `gen_batch`, but returns them all at once as JSON lines,
+/// i.e. the JSON code of each Sudoku on its own line.
+///
+/// # Arguments
+///
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+/// * `count`: The number of Sudoku to generate.
+#[no_mangle]
+pub extern fn gen_batch_lines(constraint: i32, difficulty: i32, count: i32)
+        -> *const c_char {
+    let task = generate::generation_task(constraint, difficulty, 3, 3);
+    let mut lines = String::new();
+    task.run_batch(count.max(0) as usize, Priority::Normal,
+        &CancelHandle::new(), |generated| {
+        let sudoku = GeneratedSudoku::from_generated(generated);
+        lines.push_str(&serde_json::to_string(&sudoku).unwrap());
+        lines.push('\n');
+    });
+    to_ffi_string(lines)
+}
+
+/// Generates the daily 9x9 puzzle for the given date, constraint, and
+/// difficulty. Every player gets the same puzzle for the same parameters,
+/// independently of platform, as long as the algorithm version matches.
+/// Returns a [DailyResponse] in JSON form, which contains the algorithm
+/// version alongside the Sudoku, or indicates that the date does not exist or
+/// no Sudoku could be generated.
+///
+/// # Arguments
+///
+/// * `year`: The year of the date, from 1 to 9999 (both inclusive).
+/// * `month`: The month of the date, from 1 to 12 (both inclusive).
+/// * `day`: The day of the month, from 1 to the length of the month (both
+/// inclusive), taking leap years into account.
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn daily(year: i32, month: i32, day: i32, constraint: i32,
+        difficulty: i32) -> *const c_char {
+    to_ffi_json(&DailyResponse::from_result(
+        daily::daily(year, month, day, constraint, difficulty)))
+}
+
+/// Runs the given task, if it could be created, with [GEN_FROM_DEADLINE] and
+/// returns the result as a [GenFromResponse] in JSON form.
+fn run_gen_from(task: Result<GenerationTask, TaskError>) -> *const c_char {
+    run_gen_from_until(task, Instant::now() + GEN_FROM_DEADLINE)
+}
+
+/// Runs the given task, if it could be created, with the given deadline and
+/// returns the result as a [GenFromResponse] in JSON form.
+fn run_gen_from_until(task: Result<GenerationTask, TaskError>,
+        deadline: Instant) -> *const c_char {
+    let task = match task {
+        Ok(task) => task,
+        Err(e) => return to_ffi_json(&GenFromResponse::Error(e))
+    };
+    let generated =
+        task.run(Priority::Interactive, Some(deadline), &CancelHandle::new());
+    to_ffi_json(&GenFromResponse::from_run(generated))
+}
+
+/// Generates a Sudoku with the given difficulty by randomly completing the
+/// given partial Sudoku once and reducing it afterwards. All sandwich clues
+/// are computed from the completed Sudoku. Returns a [GenFromResponse] in JSON
+/// form. If no Sudoku of the requested difficulty is found within
+/// [GEN_FROM_DEADLINE], the closest one is returned as a fallback. If the
+/// partial Sudoku cannot even be completed within that time, a timeout is
+/// reported.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of a [GenFromRequest], which contains the partial
+/// Sudoku and the cells whose digits must be kept.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn gen_from_partial(json: *const c_char, difficulty: i32)
+        -> *const c_char {
+    let request: GenFromRequest = match try_from_ffi_json(json) {
+        Ok(request) => request,
+        Err(e) => return to_ffi_json(&GenFromResponse::Error(e))
+    };
+    let deadline = Instant::now() + GEN_FROM_DEADLINE;
+    let cancel_handle = CancelHandle::with_deadline(deadline);
+    let sudoku = match portable::fill(request.sudoku,
+            &mut rand::thread_rng(), &cancel_handle) {
+        Ok(Some(sudoku)) => sudoku,
+        Ok(None) => return to_ffi_json(&GenFromResponse::Unsatisfiable),
+        Err(Cancelled) => return to_ffi_json(&GenFromResponse::Timeout)
+    };
+
+    run_gen_from_until(generate::solution_generation_task(sudoku, difficulty,
+        request.locked), deadline)
+}
+
+/// Generates a Sudoku with the given difficulty which has the given full
+/// Sudoku as its unique solution. All sandwich clues are computed from the
+/// solution. Returns a [GenFromResponse] in JSON form. If no Sudoku of the
+/// requested difficulty is found within [GEN_FROM_DEADLINE], the closest one
+/// is returned as a fallback.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of a [GenFromRequest], which contains the solution
+/// and the cells whose digits must be kept.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn gen_from_solution(json: *const c_char, difficulty: i32)
+        -> *const c_char {
+    let request: GenFromRequest = match try_from_ffi_json(json) {
+        Ok(request) => request,
+        Err(e) => return to_ffi_json(&GenFromResponse::Error(e))
+    };
+    let sudoku = &request.sudoku;
+    let size = sudoku.grid().size();
+    let is_full = (0..size).all(|row| (0..size)
+        .all(|column| sudoku.grid().get_cell(column, row).unwrap().is_some()));
+
+    if !is_full || !sudoku.is_valid() {
+        return to_ffi_json(&GenFromResponse::InvalidSolution);
+    }
+
+    run_gen_from(generate::solution_generation_task(request.sudoku,
+        difficulty, request.locked))
+}
+
+/// Generates a random Sudoku with the given difficulty and an arbitrary
+/// combination of constraints. All sandwich clues are computed from the
+/// generated grid, and the difficulty ladder is chosen according to the
+/// constraint. Returns a [GenFromResponse] in JSON form. If no Sudoku of the
+/// requested difficulty is found within [GEN_FROM_DEADLINE], the closest one
+/// is returned as a fallback.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of a [GenTemplateRequest], which contains the
+/// constraint template and optionally the block dimensions. Their product
+/// must not exceed 16.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn gen_from_template(json: *const c_char, difficulty: i32)
+        -> *const c_char {
+    run_gen_from(try_from_ffi_json(json)
+        .and_then(|request: GenTemplateRequest|
+            generate::template_generation_task(request.constraint, difficulty,
+                request.block_width, request.block_height)))
+}
+
+/// Opens a persistent puzzle pool in the given directory, closing the
+/// previously opened one, if any. Subsequent `pool_gen` calls are served from
+/// this pool. Returns 0 on success and 1 if the directory cannot be created.
+///
+/// # Arguments
+///
+/// * `dir`: The path of the directory in which puzzles are stored.
+/// * `capacity`: The number of puzzles to keep available for each combination
+/// of constraint and difficulty that has been requested.
+#[no_mangle]
+pub extern fn pool_open(dir: *const c_char, capacity: i32) -> u8 {
+    let dir = unsafe { CStr::from_ptr(dir) }.to_str().unwrap();
+
+    // Dropping a pool joins its refill workers, so the old pool must be
+    // dropped after the lock is released to not block concurrent pool_gen
+    // calls.
+
+    let previous = POOL.lock().unwrap().take();
+    drop(previous);
+
+    match PuzzlePool::open(dir, capacity.max(0) as usize, POOL_REFILL_THREADS,
+            POOL_THROTTLE) {
+        Ok(opened) => {
+            let previous = POOL.lock().unwrap().replace(Arc::new(opened));
+            drop(previous);
+            0
+        },
+        Err(_) => 1
+    }
+}
+
+/// Gets a 9x9 Sudoku with the provided constraint and difficulty from the
+/// puzzle pool opened via `pool_open` and returns its JSON serialization. If
+/// the pool holds no such Sudoku, or no pool is open, it is generated on
+/// demand like with `gen`. In any case, the metadata describes the original
+/// generation of the Sudoku.
+///
+/// # Arguments
+///
+/// * `constraint`: A identifier for the constraint that is used. For valid
+/// values, please refer to the crate-level documentation.
+/// * `difficulty`: The difficulty of the generated Sudoku on a scale from 1 to
+/// 5 (both inclusive).
+#[no_mangle]
+pub extern fn pool_gen(constraint: i32, difficulty: i32) -> *const c_char {
+    let pool = POOL.lock().unwrap().clone();
+
+    match pool {
+        Some(pool) => to_ffi_json(&pool.get(PoolKey {
+            constraint,
+            difficulty,
+            block_width: 3,
+            block_height: 3
+        })),
+        None => gen(constraint, difficulty)
+    }
+}
+
+/// Suspends refilling the puzzle pool in the background, e.g. because the app
+/// is suspended. Any generation in progress is cancelled.
+#[no_mangle]
+pub extern fn pool_suspend() {
+    if let Some(pool) = POOL.lock().unwrap().as_ref() {
+        pool.suspend();
+    }
+}
+
+/// Resumes refilling the puzzle pool in the background after `pool_suspend`.
+#[no_mangle]
+pub extern fn pool_resume() {
+    if let Some(pool) = POOL.lock().unwrap().as_ref() {
+        pool.resume();
     }
 }
 
+/// Closes the puzzle pool, stopping all background refills. Puzzles remain
+/// stored on disk and are available again after the next `pool_open`.
+#[no_mangle]
+pub extern fn pool_close() {
+    let pool = POOL.lock().unwrap().take();
+    drop(pool);
+}
+
 /// Checks whether all constraints in the given Sudoku are satisfied. Returns
 /// a [CheckResponse] in JSON form.
 ///
@@ -105,7 +586,7 @@ pub extern fn gen(constraint: i32, difficulty: i32) -> *const c_char {
 /// * `json`: The JSON code of the Sudoku to check.
 #[no_mangle]
 pub extern fn check(json: *const c_char) -> *const c_char {
-    let sudoku: Sudoku<AnyConstraint> = from_ffi_json(json);
+    let sudoku = from_ffi_sudoku(json);
     to_ffi_json(&CheckResponse::from_sudoku(&sudoku))
 }
 
@@ -118,7 +599,7 @@ pub extern fn check(json: *const c_char) -> *const c_char {
 /// * `json`: The JSON code of the Sudoku to fill.
 #[no_mangle]
 pub extern fn fill(json: *const c_char) -> *const c_char {
-    let sudoku: Sudoku<AnyConstraint> = from_ffi_json(json);
+    let sudoku = from_ffi_sudoku(json);
     to_ffi_json(&FillResponse::from_sudoku(sudoku))
 }
 
@@ -131,7 +612,7 @@ pub extern fn fill(json: *const c_char) -> *const c_char {
 /// * `json`: The JSON code of the Sudoku to check for solvability.
 #[no_mangle]
 pub extern fn is_solvable(json: *const c_char) -> u8 {
-    let sudoku: Sudoku<AnyConstraint> = from_ffi_json(json);
+    let sudoku = from_ffi_sudoku(json);
     let solution = solve::solve(sudoku);
 
     match solution {
@@ -141,6 +622,353 @@ pub extern fn is_solvable(json: *const c_char) -> u8 {
     }
 }
 
+/// Parses a Sudoku from the plain-text line format, e.g. `53..7....6..195...`
+/// for a classic Sudoku, where `.` or `0` denotes an empty cell and letters
+/// denote digits from 10 upwards. The size is detected from the number of
+/// cells, supporting 4x4, 6x6, 9x9, and 16x16. The parsed Sudoku has the
+/// default constraint. Returns a [ParseLineResponse] in JSON form.
+///
+/// # Arguments
+///
+/// * `line`: The line to parse.
+#[no_mangle]
+pub extern fn parse_line(line: *const c_char) -> *const c_char {
+    let line = unsafe { CStr::from_ptr(line) }.to_str().unwrap();
+    to_ffi_json(&ParseLineResponse::from_result(line_format::parse(line)))
+}
+
+/// Serializes the digits of the given Sudoku in the plain-text line format,
+/// using `.` for empty cells. The constraint is not part of the format and
+/// thus ignored. Returns the line as a plain string.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to serialize.
+#[no_mangle]
+pub extern fn to_line(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_string(line_format::serialize(&sudoku))
+}
+
+/// Imports a Sudoku from the JSON format of f-puzzles. Givens, diagonals,
+/// anti-knight, anti-king, and sandwich sums are supported. Puzzles with any
+/// other feature are rejected, listing the unsupported features. Returns an
+/// [FPuzzlesResponse] with the imported Sudoku in JSON form.
+///
+/// # Arguments
+///
+/// * `json`: The f-puzzles JSON code of the puzzle to import.
+#[no_mangle]
+pub extern fn import_fpuzzles(json: *const c_char) -> *const c_char {
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    let result = fpuzzles::import(json).map(|sudoku| schema::to_value(&sudoku));
+    to_ffi_json(&FPuzzlesResponse::from_result(result))
+}
+
+/// Exports the given Sudoku to the JSON format of f-puzzles. Returns an
+/// [FPuzzlesResponse] with the f-puzzles JSON object in JSON form.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to export.
+#[no_mangle]
+pub extern fn export_fpuzzles(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_json(&FPuzzlesResponse::from_result(
+        fpuzzles::export_value(&sudoku)))
+}
+
+/// Imports a Sudoku from a SudokuPad puzzle string or share link. Puzzles in
+/// the f-puzzles format and in the native `scl` format are supported, the
+/// `ctc` format is rejected explicitly. Returns a [SudokuPadResponse] with the
+/// imported Sudoku in JSON form.
+///
+/// # Arguments
+///
+/// * `input`: The puzzle string or share link to import.
+#[no_mangle]
+pub extern fn import_sudokupad(input: *const c_char) -> *const c_char {
+    let input = unsafe { CStr::from_ptr(input) }.to_str().unwrap();
+    let result = sudokupad::decode(input)
+        .map(|sudoku| schema::to_value(&sudoku));
+    to_ffi_json(&SudokuPadResponse::from_result(result))
+}
+
+/// Creates a SudokuPad share link that opens the given Sudoku. Returns a
+/// [SudokuPadResponse] with the link.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to export.
+#[no_mangle]
+pub extern fn export_sudokupad(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_json(&SudokuPadResponse::from_result(sudokupad::link(&sudoku)))
+}
+
+/// Creates a SudokuPad share link that opens the given Sudoku, using the
+/// native `scl` format, which unlike the f-puzzles format supports Sudoku of
+/// any size. Returns a [SudokuPadResponse] with the link.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to export.
+#[no_mangle]
+pub extern fn export_sudokupad_scl(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_json(&SudokuPadResponse::<String>::Ok(sudokupad::link_scl(&sudoku)))
+}
+
+/// Encodes the given Sudoku as a compact, URL-safe share code, which contains
+/// the grid and the constraint as well as a checksum. Returns a
+/// [ShareCodeResponse] with the share code, or an error if the Sudoku exceeds
+/// the limits of share codes, see [share_code].
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to encode.
+#[no_mangle]
+pub extern fn share_code(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_json(&ShareCodeResponse::from_result(share_code::encode(&sudoku)))
+}
+
+/// Decodes a Sudoku from a share code, validating its version and checksum.
+/// Returns a [ShareCodeResponse] with the decoded Sudoku in JSON form.
+///
+/// # Arguments
+///
+/// * `code`: The share code to decode.
+#[no_mangle]
+pub extern fn from_share_code(code: *const c_char) -> *const c_char {
+    let code = unsafe { CStr::from_ptr(code) }.to_str().unwrap();
+    let result = share_code::decode(code)
+        .map(|sudoku| schema::to_value(&sudoku));
+    to_ffi_json(&ShareCodeResponse::from_result(result))
+}
+
+/// Draws a Sudoku, optionally with the player's digits and pencilmarks or its
+/// solution, and returns the drawing as an SVG document in a plain string.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the [RenderRequest], which contains the Sudoku
+/// to draw.
+#[no_mangle]
+pub extern fn render_svg(json: *const c_char) -> *const c_char {
+    let request: RenderRequest = from_ffi_json(json);
+    let annotations = if request.solution {
+        render::solution_annotations(&request.sudoku)
+            .unwrap_or(request.annotations)
+    }
+    else {
+        request.annotations
+    };
+
+    to_ffi_string(render::render_svg(&request.sudoku, &annotations,
+        &request.options))
+}
+
+/// Renders a Sudoku as Unicode text with box-drawing characters, optionally
+/// with the candidates of every cell, and returns it in a plain string. This
+/// is intended for logs and bug reports.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the [TextRequest], which contains the Sudoku to
+/// render.
+#[no_mangle]
+pub extern fn render_text(json: *const c_char) -> *const c_char {
+    let request: TextRequest = from_ffi_json(json);
+    to_ffi_string(render::render_text(&request.sudoku, &request.annotations,
+        &request.options))
+}
+
+/// Lays out a batch of puzzles as a printable booklet, optionally followed by
+/// their solutions, and returns it as a PDF document in a plain string. The
+/// document consists of ASCII characters only.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the [Booklet] to print, which contains the
+/// puzzles and their metadata.
+#[no_mangle]
+pub extern fn booklet_pdf(json: *const c_char) -> *const c_char {
+    let booklet: Booklet = from_ffi_json(json);
+    let pdf = render::booklet_pdf(&booklet);
+    to_ffi_string(String::from_utf8(pdf).unwrap())
+}
+
+/// Loads and validates the puzzle pack with the given JSON code, unless it is
+/// the same pack that was loaded last, in which case the cached result is
+/// returned. The pack is loaded without holding the cache lock.
+fn load_pack_cached(json: &str) -> Arc<Result<Pack, PackError>> {
+    if let Some((loaded_json, pack)) = LOADED_PACK.lock().unwrap().as_ref() {
+        if loaded_json == json {
+            return Arc::clone(pack);
+        }
+    }
+
+    let pack = Arc::new(Pack::load(json));
+    *LOADED_PACK.lock().unwrap() = Some((json.to_owned(), Arc::clone(&pack)));
+    pack
+}
+
+/// Loads and validates a puzzle pack, solving every entry. Returns a
+/// [PackResponse] with a [PackSummary](pack::PackSummary) of the pack or, if
+/// it is invalid, the reason, naming all invalid entries. The result is
+/// cached, so subsequent calls for the same pack, including `pack_page`, do
+/// not solve the entries again.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the pack, see [pack].
+#[no_mangle]
+pub extern fn load_pack(json: *const c_char) -> *const c_char {
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    let pack = load_pack_cached(json);
+    to_ffi_json(&PackResponse::from_result((*pack).as_ref()
+        .map(Pack::summary)
+        .map_err(Clone::clone)))
+}
+
+/// Loads and validates a puzzle pack and returns one page of its entries
+/// that satisfy a filter. Returns a [PackResponse] with the
+/// [PackPage](pack::PackPage) or, if the pack or the query is invalid, the
+/// reason. Like `load_pack`, the pack is only validated once as long as the
+/// same pack is queried.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the pack, see [pack].
+/// * `query_json`: The JSON code of the [PackQuery], which contains the
+/// filter and the page to return. Its page size must be positive.
+#[no_mangle]
+pub extern fn pack_page(json: *const c_char, query_json: *const c_char)
+        -> *const c_char {
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    let query_json = unsafe { CStr::from_ptr(query_json) }.to_str().unwrap();
+    let query: PackQuery = match serde_json::from_str(query_json) {
+        Ok(query) => query,
+        Err(e) => return to_ffi_json(&PackResponse::<()>::Error(
+            PackError::InvalidQuery(e.to_string())))
+    };
+    let pack = load_pack_cached(json);
+    let page = (*pack).as_ref()
+        .map_err(Clone::clone)
+        .and_then(|pack| pack.page(&query));
+    to_ffi_json(&PackResponse::from_result(page))
+}
+
+/// Upgrades a Sudoku document written by any earlier version of the engine
+/// or app to the current schema version, without otherwise interpreting it.
+/// This is intended for saved puzzles, which should be migrated before they
+/// are read. Returns a [SchemaResponse] with the upgraded document in JSON
+/// form.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku document to upgrade.
+#[no_mangle]
+pub extern fn migrate(json: *const c_char) -> *const c_char {
+    let json = unsafe { CStr::from_ptr(json) }.to_str().unwrap();
+    let result = serde_json::from_str(json)
+        .map_err(|e| schema::SchemaError::InvalidJson(e.to_string()))
+        .and_then(schema::migrate);
+    to_ffi_json(&SchemaResponse::from_result(result))
+}
+
+/// Returns a JSON Schema describing Sudoku documents of the current schema
+/// version as a plain string, see [schema::json_schema].
+#[no_mangle]
+pub extern fn json_schema() -> *const c_char {
+    to_ffi_string(serde_json::to_string_pretty(&schema::json_schema()).unwrap())
+}
+
+/// Computes the canonical form of the given Sudoku, which is the same for all
+/// Sudoku that are equivalent under the transformations their constraint
+/// allows. Returns the canonical Sudoku in JSON form.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to canonicalize.
+#[no_mangle]
+pub extern fn canonicalize(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_string(schema::to_json(&canonical::canonicalize(&sudoku)))
+}
+
+/// Computes a fingerprint of the given Sudoku for duplicate detection, which
+/// is the same for all equivalent Sudoku and stable across platforms. Returns
+/// it as 16 hexadecimal digits in a plain string.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to fingerprint.
+#[no_mangle]
+pub extern fn fingerprint(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_string(format!("{:016x}", canonical::fingerprint(&sudoku)))
+}
+
+/// Returns the elementary transformations which preserve the constraint of the
+/// given Sudoku as a list of [Transform]s in JSON form. All compositions of
+/// them preserve the constraint as well.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to transform.
+#[no_mangle]
+pub extern fn transforms(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    to_ffi_json(&transform::available_transforms(&sudoku))
+}
+
+/// Applies a transformation to the given Sudoku, which yields an equivalent
+/// Sudoku if it preserves the constraint. Sandwich clues are moved
+/// accordingly. Returns a [TransformResponse] in JSON form.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to transform.
+/// * `transform_json`: The JSON code of the [Transform] to apply.
+#[no_mangle]
+pub extern fn transform(json: *const c_char, transform_json: *const c_char)
+        -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    let transformation: Transform = from_ffi_json(transform_json);
+    to_ffi_json(&TransformResponse::from_result(
+        transform::transform(&sudoku, &transformation)))
+}
+
+/// Applies a random composition of transformations which preserve the
+/// constraint to the given Sudoku, e.g. to present a puzzle from a curated
+/// pool in a fresh form. Returns the transformed Sudoku in JSON form.
+///
+/// # Arguments
+///
+/// * `json`: The JSON code of the Sudoku to transform.
+#[no_mangle]
+pub extern fn random_transform(json: *const c_char) -> *const c_char {
+    let sudoku = from_ffi_sudoku(json);
+    let transformed =
+        transform::random_transform(&sudoku, &mut rand::thread_rng());
+    to_ffi_string(schema::to_json(&transformed))
+}
+
+/// Sets the number of threads the engine uses for generation, e.g. fewer when
+/// the device runs on battery. By default, one thread per CPU is used.
+/// Threads that are currently busy exit once their work is finished.
+///
+/// # Arguments
+///
+/// * `threads`: The number of threads to use. Values less than 1 are treated
+/// as 1.
+#[no_mangle]
+pub extern fn set_worker_threads(threads: i32) {
+    workers::workers().set_threads(threads.max(1) as usize);
+}
+
 /// Returns 42. For tests that the library was loaded correctly.
 #[no_mangle]
 pub extern fn test() -> i32 {
@@ -0,0 +1,340 @@
+//! Parsing and serialization of the plain-text line format used by many books
+//! and websites, e.g. `53..7....6..195...` for a classic Sudoku. Each cell
+//! is one character in row-major order, where `.` or `0` denotes an empty
+//! cell, `1` to `9` the digits 1 to 9, and letters the digits from 10 upwards,
+//! i.e. `A` (or `a`) is 10 and `G` is 16. Whitespace is ignored, so grids
+//! with one row per line are accepted as well.
+//!
+//! The format only describes the digits, so parsed Sudoku have the
+//! [AnyConstraint::Default] constraint and serialization ignores the
+//! constraint.
+
+use crate::constraint::AnyConstraint;
+
+use serde::Serialize;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+
+/// The block width and height of the sizes that are detected from the length
+/// of a line, i.e. 4x4, 6x6, 9x9, and 16x16.
+const DETECTED_DIMENSIONS: [(usize, usize); 4] =
+    [(2, 2), (3, 2), (3, 3), (4, 4)];
+
+/// The largest digit that has a character, i.e. `Z`.
+const MAX_DIGIT: usize = 35;
+
+/// The reasons why a line cannot be parsed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum LineFormatError {
+
+    /// The number of cells does not match any supported size, or the given
+    /// dimensions.
+    #[serde(rename = "wrong-length")]
+    WrongLength {
+
+        /// The number of cells in the line, not counting whitespace.
+        length: usize,
+
+        /// The numbers of cells that would have been accepted.
+        expected: Vec<usize>
+    },
+
+    /// A character does not denote an empty cell or a digit.
+    #[serde(rename = "invalid-character")]
+    InvalidCharacter {
+
+        /// The invalid character.
+        character: char,
+
+        /// The 0-based index of the character in the line.
+        position: usize
+    },
+
+    /// A character denotes a digit that is larger than the size of the
+    /// Sudoku, e.g. `A` in a 9x9 Sudoku.
+    #[serde(rename = "digit-too-large")]
+    DigitTooLarge {
+
+        /// The digit denoted by the character.
+        digit: usize,
+
+        /// The 0-based index of the character in the line.
+        position: usize,
+
+        /// The size of the Sudoku, i.e. the largest allowed digit.
+        size: usize
+    },
+
+    /// The given block dimensions do not describe a valid Sudoku.
+    #[serde(rename = "invalid-dimensions")]
+    InvalidDimensions
+}
+
+impl Display for LineFormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LineFormatError::WrongLength { length, expected } => {
+                let expected = expected.iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "line has {} cells, expected one of: {}", length,
+                    expected)
+            },
+            LineFormatError::InvalidCharacter { character, position } =>
+                write!(f, "invalid character '{}' at position {}", character,
+                    position),
+            LineFormatError::DigitTooLarge { digit, position, size } =>
+                write!(f, "digit {} at position {} exceeds the size {}",
+                    digit, position, size),
+            LineFormatError::InvalidDimensions =>
+                write!(f, "invalid block dimensions")
+        }
+    }
+}
+
+impl Error for LineFormatError { }
+
+/// Gets the digit denoted by the given character, `Some(None)` for an empty
+/// cell, or `None` if the character is invalid.
+fn parse_char(character: char) -> Option<Option<usize>> {
+    match character {
+        '.' | '0' => Some(None),
+        '1'..='9' | 'a'..='z' | 'A'..='Z' =>
+            Some(character.to_digit(36).map(|d| d as usize)),
+        _ => None
+    }
+}
+
+/// Gets the character denoting the given cell, i.e. `.` for an empty cell and
+/// `1` to `9` followed by upper-case letters for digits.
+pub(crate) fn to_char(cell: Option<usize>) -> char {
+    match cell {
+        Some(digit) => std::char::from_digit(digit as u32, 36).unwrap()
+            .to_ascii_uppercase(),
+        None => '.'
+    }
+}
+
+fn parse_cells(line: &str, size: usize)
+        -> Result<Vec<Option<usize>>, LineFormatError> {
+    line.chars()
+        .enumerate()
+        .filter(|(_, character)| !character.is_whitespace())
+        .map(|(position, character)| {
+            match parse_char(character) {
+                Some(Some(digit)) if digit > size =>
+                    Err(LineFormatError::DigitTooLarge {
+                        digit,
+                        position,
+                        size
+                    }),
+                Some(cell) => Ok(cell),
+                None => Err(LineFormatError::InvalidCharacter {
+                    character,
+                    position
+                })
+            }
+        })
+        .collect()
+}
+
+fn cell_count(line: &str) -> usize {
+    line.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Parses a Sudoku with the given block dimensions from the line format.
+///
+/// # Arguments
+///
+/// * `line`: The line to parse.
+/// * `block_width`: The width of the blocks of the Sudoku.
+/// * `block_height`: The height of the blocks of the Sudoku.
+///
+/// # Errors
+///
+/// If the dimensions are invalid, the number of cells does not match them,
+/// or a character does not denote an empty cell or a digit of this size.
+pub fn parse_with_dimensions(line: &str, block_width: usize,
+        block_height: usize)
+        -> Result<Sudoku<AnyConstraint>, LineFormatError> {
+    let mut grid = SudokuGrid::new(block_width, block_height)
+        .map_err(|_| LineFormatError::InvalidDimensions)?;
+    let size = grid.size();
+
+    if size > MAX_DIGIT {
+        return Err(LineFormatError::InvalidDimensions);
+    }
+
+    let length = cell_count(line);
+
+    if length != size * size {
+        return Err(LineFormatError::WrongLength {
+            length,
+            expected: vec![size * size]
+        });
+    }
+
+    let cells = parse_cells(line, size)?;
+
+    for (index, cell) in cells.into_iter().enumerate() {
+        if let Some(digit) = cell {
+            grid.set_cell(index % size, index / size, digit).unwrap();
+        }
+    }
+
+    Ok(Sudoku::new_with_grid(grid, AnyConstraint::Default))
+}
+
+/// Parses a Sudoku from the line format, detecting its size from the number
+/// of cells. Supported are 4x4 with 2x2 blocks, 6x6 with 3x2 blocks (3 wide,
+/// 2 high), 9x9 with 3x3 blocks, and 16x16 with 4x4 blocks.
+///
+/// # Errors
+///
+/// If the number of cells does not match a supported size, or a character
+/// does not denote an empty cell or a digit of the detected size.
+pub fn parse(line: &str) -> Result<Sudoku<AnyConstraint>, LineFormatError> {
+    let length = cell_count(line);
+    let dimensions = DETECTED_DIMENSIONS.iter()
+        .find(|(width, height)| (width * height).pow(2) == length);
+
+    match dimensions {
+        Some(&(block_width, block_height)) =>
+            parse_with_dimensions(line, block_width, block_height),
+        None => Err(LineFormatError::WrongLength {
+            length,
+            expected: DETECTED_DIMENSIONS.iter()
+                .map(|(width, height)| (width * height).pow(2))
+                .collect()
+        })
+    }
+}
+
+/// Serializes the digits of the given Sudoku in the line format, using `.`
+/// for empty cells and upper-case letters for digits from 10 upwards. The
+/// constraint is not part of the format and thus ignored.
+///
+/// # Panics
+///
+/// If the Sudoku is larger than 35x35, since larger digits have no character.
+pub fn serialize(sudoku: &Sudoku<AnyConstraint>) -> String {
+    let grid = sudoku.grid();
+    let size = grid.size();
+
+    if size > MAX_DIGIT {
+        panic!("Sudoku too large for the line format: {}", size);
+    }
+
+    (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .map(|(column, row)| to_char(grid.get_cell(column, row).unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Gets a line for the given size in which every character occurs, with
+    /// digits from 10 upwards as upper-case letters.
+    fn line(size: usize) -> String {
+        (0..size * size)
+            .map(|index| to_char(Some(index % (size + 1)).filter(|&d| d > 0)))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_detected_dimensions() {
+        for &(block_width, block_height) in &DETECTED_DIMENSIONS {
+            let size = block_width * block_height;
+            let line = line(size);
+            let sudoku = parse(&line).unwrap();
+
+            assert_eq!(block_width, sudoku.grid().block_width());
+            assert_eq!(block_height, sudoku.grid().block_height());
+            assert_eq!(line, serialize(&sudoku));
+        }
+    }
+
+    #[test]
+    fn round_trip_with_dimensions() {
+        let line = line(12);
+        let sudoku = parse_with_dimensions(&line, 4, 3).unwrap();
+
+        assert_eq!(4, sudoku.grid().block_width());
+        assert_eq!(3, sudoku.grid().block_height());
+        assert_eq!(line, serialize(&sudoku));
+    }
+
+    #[test]
+    fn alternative_characters() {
+        let sudoku = parse("1 2 0 .\n3 4 . 0\n.... ....").unwrap();
+        assert_eq!("12..34..........", serialize(&sudoku));
+
+        let upper = line(16);
+        let lower = upper.to_ascii_lowercase();
+        assert_eq!(upper, serialize(&parse(&lower).unwrap()));
+    }
+
+    #[test]
+    fn wrong_length() {
+        assert_eq!(Err(LineFormatError::WrongLength {
+            length: 80,
+            expected: vec![16, 36, 81, 256]
+        }), parse(&".".repeat(80)).map(|_| ()));
+        assert_eq!(Err(LineFormatError::WrongLength {
+            length: 0,
+            expected: vec![16, 36, 81, 256]
+        }), parse(" \n").map(|_| ()));
+        assert_eq!(Err(LineFormatError::WrongLength {
+            length: 16,
+            expected: vec![81]
+        }), parse_with_dimensions(&".".repeat(16), 3, 3).map(|_| ()));
+    }
+
+    #[test]
+    fn invalid_character() {
+        assert_eq!(Err(LineFormatError::InvalidCharacter {
+            character: '*',
+            position: 6
+        }), parse("1 2 . *............").map(|_| ()));
+    }
+
+    #[test]
+    fn digit_too_large() {
+        let mut line = ".".repeat(81);
+        line.replace_range(40..41, "a");
+
+        assert_eq!(Err(LineFormatError::DigitTooLarge {
+            digit: 10,
+            position: 40,
+            size: 9
+        }), parse(&line).map(|_| ()));
+        assert_eq!(Err(LineFormatError::DigitTooLarge {
+            digit: 5,
+            position: 0,
+            size: 4
+        }), parse("5...............").map(|_| ()));
+    }
+
+    #[test]
+    fn invalid_dimensions() {
+        assert_eq!(Err(LineFormatError::InvalidDimensions),
+            parse_with_dimensions("", 0, 3).map(|_| ()));
+        assert_eq!(Err(LineFormatError::InvalidDimensions),
+            parse_with_dimensions(&".".repeat(36 * 36), 6, 6).map(|_| ()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn serialize_too_large() {
+        let grid = SudokuGrid::new(6, 6).unwrap();
+        serialize(&Sudoku::new_with_grid(grid, AnyConstraint::Default));
+    }
+}
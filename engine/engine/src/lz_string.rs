@@ -0,0 +1,345 @@
+//! A port of the base64 variant of the
+//! [LZ-string](https://github.com/pieroxy/lz-string) compression algorithm,
+//! i.e. `compressToBase64` and `decompressFromBase64`, which SudokuPad uses
+//! for its puzzle strings. Like the JavaScript original, it operates on UTF-16
+//! code units, so the output is compatible with it.
+
+use std::collections::{HashMap, HashSet};
+
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+const BITS_PER_CHAR: usize = 6;
+
+/// Gets the 6-bit value of a base64 character. The URI-safe variant of
+/// LZ-string uses `-` instead of `/`, so both are accepted.
+fn base64_value(character: u8) -> Option<u32> {
+    match character {
+        b'-' => Some(63),
+        _ => BASE64.iter()
+            .position(|&c| c == character)
+            .map(|v| v as u32)
+    }
+}
+
+struct BitWriter {
+    output: String,
+    value: usize,
+    position: usize
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            output: String::new(),
+            value: 0,
+            position: 0
+        }
+    }
+
+    /// Writes the given number of bits of the given value, least significant
+    /// bit first.
+    fn write(&mut self, mut value: usize, bits: usize) {
+        for _ in 0..bits {
+            self.value = (self.value << 1) | (value & 1);
+
+            if self.position == BITS_PER_CHAR - 1 {
+                self.output.push(BASE64[self.value] as char);
+                self.position = 0;
+                self.value = 0;
+            }
+            else {
+                self.position += 1;
+            }
+
+            value >>= 1;
+        }
+    }
+
+    fn finish(mut self) -> String {
+        loop {
+            self.value <<= 1;
+
+            if self.position == BITS_PER_CHAR - 1 {
+                self.output.push(BASE64[self.value] as char);
+                break;
+            }
+
+            self.position += 1;
+        }
+
+        while self.output.len() % 4 != 0 {
+            self.output.push('=');
+        }
+
+        self.output
+    }
+}
+
+struct Compressor {
+    writer: BitWriter,
+    dictionary: HashMap<Vec<u16>, usize>,
+    to_create: HashSet<Vec<u16>>,
+    enlarge_in: usize,
+    num_bits: usize
+}
+
+impl Compressor {
+    fn decrease_enlarge_in(&mut self) {
+        self.enlarge_in -= 1;
+
+        if self.enlarge_in == 0 {
+            self.enlarge_in = 1 << self.num_bits;
+            self.num_bits += 1;
+        }
+    }
+
+    fn emit(&mut self, w: &[u16]) {
+        if self.to_create.remove(w) {
+            let code = w[0] as usize;
+
+            if code < 256 {
+                self.writer.write(0, self.num_bits);
+                self.writer.write(code, 8);
+            }
+            else {
+                self.writer.write(1, self.num_bits);
+                self.writer.write(code, 16);
+            }
+
+            self.decrease_enlarge_in();
+        }
+        else {
+            let code = self.dictionary[w];
+            self.writer.write(code, self.num_bits);
+        }
+
+        self.decrease_enlarge_in();
+    }
+}
+
+/// Compresses the given string into base64, equivalent to
+/// `LZString.compressToBase64`.
+pub(crate) fn compress_to_base64(input: &str) -> String {
+    let mut compressor = Compressor {
+        writer: BitWriter::new(),
+        dictionary: HashMap::new(),
+        to_create: HashSet::new(),
+        enlarge_in: 2,
+        num_bits: 2
+    };
+    let mut dict_size = 3;
+    let mut w: Vec<u16> = Vec::new();
+
+    for c in input.encode_utf16() {
+        let c = vec![c];
+
+        if !compressor.dictionary.contains_key(&c) {
+            compressor.dictionary.insert(c.clone(), dict_size);
+            compressor.to_create.insert(c.clone());
+            dict_size += 1;
+        }
+
+        let mut wc = w.clone();
+        wc.extend_from_slice(&c);
+
+        if compressor.dictionary.contains_key(&wc) {
+            w = wc;
+        }
+        else {
+            compressor.emit(&w);
+            compressor.dictionary.insert(wc, dict_size);
+            dict_size += 1;
+            w = c;
+        }
+    }
+
+    if !w.is_empty() {
+        compressor.emit(&w);
+    }
+
+    let num_bits = compressor.num_bits;
+    compressor.writer.write(2, num_bits);
+    compressor.writer.finish()
+}
+
+struct BitReader {
+    values: Vec<u32>,
+    value: u32,
+    position: u32,
+    index: usize
+}
+
+impl BitReader {
+    const RESET: u32 = 1 << (BITS_PER_CHAR - 1);
+
+    fn next_value(&mut self) -> u32 {
+        let value = self.values.get(self.index).copied().unwrap_or(0);
+        self.index += 1;
+        value
+    }
+
+    /// Reads the given number of bits, least significant bit first.
+    fn read(&mut self, bits: usize) -> usize {
+        let mut result = 0;
+
+        for bit in 0..bits {
+            let set = self.value & self.position != 0;
+            self.position >>= 1;
+
+            if self.position == 0 {
+                self.position = BitReader::RESET;
+                self.value = self.next_value();
+            }
+
+            if set {
+                result |= 1 << bit;
+            }
+        }
+
+        result
+    }
+}
+
+/// Decompresses the given base64 string, equivalent to
+/// `LZString.decompressFromBase64`. Padding and whitespace are ignored.
+/// Returns `None` if the input is not valid compressed data.
+pub(crate) fn decompress_from_base64(input: &str) -> Option<String> {
+    let values = input.bytes()
+        .filter(|c| *c != b'=' && !c.is_ascii_whitespace())
+        .map(base64_value)
+        .collect::<Option<Vec<_>>>()?;
+    let length = values.len();
+    let mut reader = BitReader {
+        values,
+        value: 0,
+        position: BitReader::RESET,
+        index: 0
+    };
+    reader.value = reader.next_value();
+
+    let mut dictionary: Vec<Vec<u16>> = vec![Vec::new(); 3];
+    let mut enlarge_in = 4usize;
+    let mut num_bits = 3;
+    let mut result: Vec<u16> = Vec::new();
+
+    let mut w = match reader.read(2) {
+        0 => vec![reader.read(8) as u16],
+        1 => vec![reader.read(16) as u16],
+        _ => return Some(String::new())
+    };
+    dictionary.push(w.clone());
+    result.extend_from_slice(&w);
+
+    loop {
+        if reader.index > length {
+            return None;
+        }
+
+        let mut c = reader.read(num_bits);
+
+        match c {
+            0 | 1 => {
+                let bits = if c == 0 { 8 } else { 16 };
+                dictionary.push(vec![reader.read(bits) as u16]);
+                c = dictionary.len() - 1;
+                enlarge_in -= 1;
+            },
+            2 => return String::from_utf16(&result).ok(),
+            _ => { }
+        }
+
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+
+        let entry = if c < dictionary.len() {
+            dictionary[c].clone()
+        }
+        else if c == dictionary.len() {
+            let mut entry = w.clone();
+            entry.push(w[0]);
+            entry
+        }
+        else {
+            return None;
+        };
+
+        result.extend_from_slice(&entry);
+
+        let mut new_entry = w;
+        new_entry.push(entry[0]);
+        dictionary.push(new_entry);
+        enlarge_in -= 1;
+        w = entry;
+
+        if enlarge_in == 0 {
+            enlarge_in = 1 << num_bits;
+            num_bits += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_round_trip(input: &str) {
+        let compressed = compress_to_base64(input);
+
+        assert_eq!(Some(input), decompress_from_base64(&compressed).as_deref());
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!("Q===", compress_to_base64(""));
+        assert_round_trip("");
+    }
+
+    #[test]
+    fn ascii() {
+        assert_round_trip("a");
+        assert_round_trip("Hello, world");
+        assert_round_trip("{\"size\":9,\"grid\":[[{\"value\":1}]]}");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_round_trip("Grüße, 数独");
+        assert_round_trip("\u{1F9E9} and \u{1D11E} need surrogate pairs");
+    }
+
+    #[test]
+    fn repetitive() {
+        let input = "[{},{},{\"value\":5}]".repeat(500);
+
+        assert!(compress_to_base64(&input).len() < input.len() / 10);
+        assert_round_trip(&input);
+    }
+
+    #[test]
+    fn all_code_points_below_512() {
+        let input = (0..512u32)
+            .filter_map(char::from_u32)
+            .collect::<String>();
+
+        assert_round_trip(&input);
+    }
+
+    #[test]
+    fn uri_safe_characters() {
+        let input = "\u{3F0} sudoku".to_owned();
+        let compressed = compress_to_base64(&input);
+        let uri_safe = compressed.replace('/', "-").replace('=', "");
+
+        assert!(compressed.contains('/'));
+        assert_eq!(Some(input), decompress_from_base64(&uri_safe));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(None, decompress_from_base64("!!!!"));
+    }
+}
@@ -0,0 +1,846 @@
+//! Curated puzzle packs. A pack is a JSON document consisting of a format
+//! version, a manifest describing the pack, and a list of entries.
+//!
+//! ```text
+//! {
+//!     "format_version": 1,
+//!     "manifest": {
+//!         "title": "First Steps",
+//!         "author": "Jane Doe",
+//!         "description": "Gentle puzzles for beginners.",
+//!         "license": "CC-BY-4.0"
+//!     },
+//!     "entries": [
+//!         {
+//!             "id": "first-steps-1",
+//!             "puzzle": "53..7....6..195....98....6.8...6...34..8\
+//!                 .3..17...2...6.6....28....419..5....8..79",
+//!             "constraint": { "type": "default" },
+//!             "difficulty": 1,
+//!             "rating": 1.5,
+//!             "solution": "534678912672195348198342567859761423426\
+//!                 853791713924856961537284287419635345286179",
+//!             "rules": "Normal Sudoku rules apply."
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Puzzles and solutions are given in the line format, see [line_format],
+//! and their size is detected from the number of cells. The strings above
+//! are wrapped for readability only. The constraint defaults to classic
+//! Sudoku, and the solution and rules are optional.
+//!
+//! Packs are loaded with [Pack::load], which validates every entry and
+//! reports all invalid entries at once, naming each by its index and ID.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+use crate::line_format::{self, LineFormatError};
+use crate::schema;
+use crate::solve;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use serde_json::Value;
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+use sudoku_variants::solver::Solution;
+
+/// The version of the pack format understood by this engine.
+pub const PACK_FORMAT_VERSION: u64 = 1;
+
+const MIN_DIFFICULTY: i32 = 1;
+const MAX_DIFFICULTY: i32 = 5;
+
+fn default_page_size() -> usize {
+    20
+}
+
+/// The description of a pack as a whole.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PackManifest {
+
+    /// The title of the pack.
+    pub title: String,
+
+    /// The name of the author or curator of the pack.
+    pub author: String,
+
+    /// A description of the pack to show to players. Defaults to an empty
+    /// string.
+    #[serde(default)]
+    pub description: String,
+
+    /// The license under which the puzzles are distributed, preferably as an
+    /// SPDX identifier. Defaults to an empty string.
+    #[serde(default)]
+    pub license: String
+}
+
+/// An entry of a pack as it is written in the pack document.
+#[derive(Deserialize)]
+struct RawEntry {
+    id: String,
+    puzzle: String,
+    #[serde(default = "default_constraint")]
+    constraint: AnyConstraint,
+    difficulty: i32,
+    rating: f64,
+    #[serde(default)]
+    solution: Option<String>,
+    #[serde(default)]
+    rules: Option<String>
+}
+
+fn default_constraint() -> AnyConstraint {
+    AnyConstraint::Default
+}
+
+fn serialize_solution<S>(solution: &SudokuGrid, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer
+{
+    let sudoku = Sudoku::new_with_grid(solution.clone(),
+        AnyConstraint::Default);
+    serializer.serialize_str(&line_format::serialize(&sudoku))
+}
+
+/// A validated entry of a pack. When serialized, the puzzle is written as a
+/// Sudoku document (see [schema]) including the constraint, and the solution
+/// in the line format.
+#[derive(Clone, Serialize)]
+pub struct PackEntry {
+
+    /// The ID of the entry, which is unique within the pack.
+    pub id: String,
+
+    /// The puzzle together with its constraint.
+    #[serde(serialize_with = "schema::serialize")]
+    pub sudoku: Sudoku<AnyConstraint>,
+
+    /// The unique solution of the puzzle. If the pack does not provide it, it
+    /// is computed while loading.
+    #[serde(serialize_with = "serialize_solution")]
+    pub solution: SudokuGrid,
+
+    /// The difficulty on a scale from 1 to 5 (both inclusive).
+    pub difficulty: i32,
+
+    /// A finer-grained, non-negative rating of the difficulty, which allows
+    /// ordering puzzles within and across difficulty levels. Higher ratings
+    /// denote harder puzzles.
+    pub rating: f64,
+
+    /// A description of the rules to show instead of the standard one for
+    /// the constraint, or `None` to use the standard description.
+    pub rules: Option<String>
+}
+
+/// The reasons why an entry of a pack is invalid.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum EntryProblem {
+
+    /// The entry does not have the structure of a pack entry, e.g. because a
+    /// field is missing. The message of the parser is provided.
+    #[serde(rename = "malformed")]
+    Malformed(String),
+
+    /// The ID is empty.
+    #[serde(rename = "empty-id")]
+    EmptyId,
+
+    /// An earlier entry has the same ID.
+    #[serde(rename = "duplicate-id")]
+    DuplicateId,
+
+    /// The puzzle cannot be parsed. The reason is provided.
+    #[serde(rename = "invalid-puzzle")]
+    InvalidPuzzle(LineFormatError),
+
+    /// The solution cannot be parsed. The reason is provided.
+    #[serde(rename = "invalid-solution")]
+    InvalidSolution(LineFormatError),
+
+    /// The constraint does not fit the size of the puzzle, e.g. because the
+    /// number of sandwich clues differs from the number of rows.
+    #[serde(rename = "constraint-size-mismatch")]
+    ConstraintSizeMismatch,
+
+    /// The difficulty is outside the range from 1 to 5. It is provided.
+    #[serde(rename = "invalid-difficulty")]
+    InvalidDifficulty(i32),
+
+    /// The rating is negative or not finite.
+    #[serde(rename = "invalid-rating")]
+    InvalidRating,
+
+    /// The givens of the puzzle violate its constraint.
+    #[serde(rename = "invalid-givens")]
+    InvalidGivens,
+
+    /// The puzzle has no solution.
+    #[serde(rename = "unsolvable")]
+    Unsolvable,
+
+    /// The puzzle has more than one solution.
+    #[serde(rename = "ambiguous")]
+    Ambiguous,
+
+    /// The provided solution differs from the unique solution of the
+    /// puzzle.
+    #[serde(rename = "wrong-solution")]
+    WrongSolution
+}
+
+impl Display for EntryProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryProblem::Malformed(message) =>
+                write!(f, "malformed entry: {}", message),
+            EntryProblem::EmptyId => write!(f, "empty ID"),
+            EntryProblem::DuplicateId => write!(f, "duplicate ID"),
+            EntryProblem::InvalidPuzzle(e) =>
+                write!(f, "invalid puzzle: {}", e),
+            EntryProblem::InvalidSolution(e) =>
+                write!(f, "invalid solution: {}", e),
+            EntryProblem::ConstraintSizeMismatch =>
+                write!(f, "constraint does not fit the puzzle size"),
+            EntryProblem::InvalidDifficulty(difficulty) =>
+                write!(f, "invalid difficulty {}", difficulty),
+            EntryProblem::InvalidRating => write!(f, "invalid rating"),
+            EntryProblem::InvalidGivens =>
+                write!(f, "givens violate the constraint"),
+            EntryProblem::Unsolvable => write!(f, "puzzle has no solution"),
+            EntryProblem::Ambiguous =>
+                write!(f, "puzzle has multiple solutions"),
+            EntryProblem::WrongSolution =>
+                write!(f, "solution does not match the puzzle")
+        }
+    }
+}
+
+/// An invalid entry of a pack, identified by its position and, if it could
+/// be read, its ID.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct EntryError {
+
+    /// The 0-based index of the entry in the pack.
+    pub index: usize,
+
+    /// The ID of the entry, or `None` if it could not be read.
+    pub id: Option<String>,
+
+    /// The reason why the entry is invalid.
+    pub problem: EntryProblem
+}
+
+impl Display for EntryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "entry {} ({:?}): {}", self.index, id,
+                self.problem),
+            None => write!(f, "entry {}: {}", self.index, self.problem)
+        }
+    }
+}
+
+/// The reasons why a pack cannot be loaded or queried.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum PackError {
+
+    /// The input is not valid JSON. The message of the parser is provided.
+    #[serde(rename = "invalid-json")]
+    InvalidJson(String),
+
+    /// The `format_version` field is missing or not a non-negative integer.
+    #[serde(rename = "invalid-version")]
+    InvalidVersion,
+
+    /// The pack was written for a newer version of the format. The version
+    /// is provided.
+    #[serde(rename = "unsupported-version")]
+    UnsupportedVersion(u64),
+
+    /// The manifest is missing or malformed. The message of the parser is
+    /// provided.
+    #[serde(rename = "invalid-manifest")]
+    InvalidManifest(String),
+
+    /// The `entries` field is missing or not a list.
+    #[serde(rename = "missing-entries")]
+    MissingEntries,
+
+    /// Some entries are invalid. All of them are provided in order.
+    #[serde(rename = "invalid-entries")]
+    InvalidEntries(Vec<EntryError>),
+
+    /// The [PackQuery] is malformed or its page size is 0. A description of
+    /// the problem is provided.
+    #[serde(rename = "invalid-query")]
+    InvalidQuery(String)
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::InvalidJson(message) =>
+                write!(f, "invalid JSON: {}", message),
+            PackError::InvalidVersion =>
+                write!(f, "missing or invalid format version"),
+            PackError::UnsupportedVersion(version) =>
+                write!(f, "unsupported pack format version {}", version),
+            PackError::InvalidManifest(message) =>
+                write!(f, "invalid manifest: {}", message),
+            PackError::MissingEntries => write!(f, "missing entry list"),
+            PackError::InvalidEntries(errors) => {
+                write!(f, "{} invalid entries", errors.len())?;
+
+                for error in errors {
+                    write!(f, "; {}", error)?;
+                }
+
+                Ok(())
+            },
+            PackError::InvalidQuery(message) =>
+                write!(f, "invalid query: {}", message)
+        }
+    }
+}
+
+impl Error for PackError { }
+
+/// Criteria for selecting entries of a pack. All criteria are optional, and
+/// an entry must satisfy all given ones. To be deserialized from the input,
+/// where all fields are optional.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PackFilter {
+
+    /// The constraint identifier (see the crate-level documentation) the
+    /// entries must have. Entries with other combinations of constraints
+    /// never match an identifier.
+    pub constraint: Option<i32>,
+
+    /// The minimum difficulty (inclusive).
+    pub min_difficulty: Option<i32>,
+
+    /// The maximum difficulty (inclusive).
+    pub max_difficulty: Option<i32>,
+
+    /// The minimum rating (inclusive).
+    pub min_rating: Option<f64>,
+
+    /// The maximum rating (inclusive).
+    pub max_rating: Option<f64>
+}
+
+impl PackFilter {
+
+    /// Indicates whether the given entry satisfies all criteria.
+    pub fn matches(&self, entry: &PackEntry) -> bool {
+        let constraint = self.constraint.is_none_or(|identifier|
+            constraint_identifier(entry.sudoku.constraint())
+                == Some(identifier));
+        let min_difficulty = self.min_difficulty
+            .is_none_or(|min| entry.difficulty >= min);
+        let max_difficulty = self.max_difficulty
+            .is_none_or(|max| entry.difficulty <= max);
+        let min_rating = self.min_rating.is_none_or(|min| entry.rating >= min);
+        let max_rating = self.max_rating.is_none_or(|max| entry.rating <= max);
+
+        constraint && min_difficulty && max_difficulty && min_rating &&
+            max_rating
+    }
+}
+
+/// A request for one page of the entries of a pack that satisfy a filter.
+/// To be deserialized from the input, where all fields are optional.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct PackQuery {
+
+    /// The criteria the entries must satisfy. Defaults to selecting all
+    /// entries.
+    #[serde(flatten)]
+    pub filter: PackFilter,
+
+    /// The 0-based index of the page. Defaults to the first page.
+    pub page: usize,
+
+    /// The maximum number of entries per page. Defaults to 20.
+    pub page_size: usize
+}
+
+impl Default for PackQuery {
+    fn default() -> PackQuery {
+        PackQuery {
+            filter: PackFilter::default(),
+            page: 0,
+            page_size: default_page_size()
+        }
+    }
+}
+
+/// An entry on a [PackPage] together with its index in the pack.
+#[derive(Serialize)]
+pub struct PageEntry<'a> {
+
+    /// The 0-based index of the entry in the pack.
+    pub index: usize,
+
+    /// The entry itself.
+    #[serde(flatten)]
+    pub entry: &'a PackEntry
+}
+
+/// One page of the entries of a pack that satisfy a filter, see
+/// [Pack::page].
+#[derive(Serialize)]
+pub struct PackPage<'a> {
+
+    /// The 0-based index of this page.
+    pub page: usize,
+
+    /// The number of pages, which is 0 if no entry satisfies the filter.
+    pub page_count: usize,
+
+    /// The number of entries that satisfy the filter across all pages.
+    pub total: usize,
+
+    /// The entries on this page in the order of the pack. This is empty if
+    /// the page is out of range.
+    pub entries: Vec<PageEntry<'a>>
+}
+
+/// An overview of a loaded pack, consisting of its manifest and the number
+/// of entries of every difficulty.
+#[derive(Serialize)]
+pub struct PackSummary<'a> {
+
+    /// The manifest of the pack.
+    pub manifest: &'a PackManifest,
+
+    /// The total number of entries.
+    pub entry_count: usize,
+
+    /// The number of entries of every difficulty, where index 0 corresponds
+    /// to difficulty 1.
+    pub difficulty_counts: Vec<usize>
+}
+
+/// A loaded and validated puzzle pack.
+#[derive(Clone)]
+pub struct Pack {
+    manifest: PackManifest,
+    entries: Vec<PackEntry>
+}
+
+/// Gets the constraint identifier (see the crate-level documentation) of the
+/// given constraint, or `None` if it is a combination without identifier.
+/// The order of the components of composite constraints does not matter.
+fn constraint_identifier(constraint: &AnyConstraint) -> Option<i32> {
+    let mut components = Vec::new();
+    collect_components(constraint, &mut components);
+    components.retain(|c| !matches!(c, AnyConstraint::Default));
+
+    match components.as_slice() {
+        [] => Some(0),
+        [AnyConstraint::Diagonals] => Some(1),
+        [AnyConstraint::KnightsMove] => Some(2),
+        [AnyConstraint::KingsMove] => Some(3),
+        [AnyConstraint::KnightsMove, AnyConstraint::KingsMove]
+            | [AnyConstraint::KingsMove, AnyConstraint::KnightsMove] =>
+            Some(4),
+        [AnyConstraint::Sandwich(_)] => Some(5),
+        _ => None
+    }
+}
+
+fn collect_components<'a>(constraint: &'a AnyConstraint,
+        components: &mut Vec<&'a AnyConstraint>) {
+    match constraint {
+        AnyConstraint::Composite(cs) =>
+            cs.iter().for_each(|c| collect_components(c, components)),
+        c => components.push(c)
+    }
+}
+
+/// Indicates whether the given constraint can be applied to a grid of the
+/// given size, i.e. all sandwich constraints have one clue per row and
+/// column.
+fn fits(constraint: &AnyConstraint, size: usize) -> bool {
+    match constraint {
+        AnyConstraint::Sandwich(c) => {
+            let clues = SandwichClues::of(c);
+            clues.columns.len() == size && clues.rows.len() == size
+        },
+        AnyConstraint::Composite(cs) => cs.iter().all(|c| fits(c, size)),
+        _ => true
+    }
+}
+
+fn same_cells(a: &SudokuGrid, b: &SudokuGrid) -> bool {
+    let size = a.size();
+
+    a.block_width() == b.block_width() &&
+        a.block_height() == b.block_height() &&
+        (0..size).all(|row| (0..size).all(|column|
+            a.get_cell(column, row).unwrap() ==
+                b.get_cell(column, row).unwrap()))
+}
+
+/// Validates a single entry and converts it into a [PackEntry].
+fn validate_entry(raw: RawEntry) -> Result<PackEntry, EntryProblem> {
+    if raw.id.is_empty() {
+        return Err(EntryProblem::EmptyId);
+    }
+
+    if !(MIN_DIFFICULTY..=MAX_DIFFICULTY).contains(&raw.difficulty) {
+        return Err(EntryProblem::InvalidDifficulty(raw.difficulty));
+    }
+
+    if !raw.rating.is_finite() || raw.rating < 0.0 {
+        return Err(EntryProblem::InvalidRating);
+    }
+
+    let puzzle = line_format::parse(&raw.puzzle)
+        .map_err(EntryProblem::InvalidPuzzle)?;
+    let grid = puzzle.grid();
+
+    if !fits(&raw.constraint, grid.size()) {
+        return Err(EntryProblem::ConstraintSizeMismatch);
+    }
+
+    let provided_solution = match &raw.solution {
+        Some(solution) => Some(line_format::parse_with_dimensions(solution,
+                grid.block_width(), grid.block_height())
+            .map_err(EntryProblem::InvalidSolution)?),
+        None => None
+    };
+    let sudoku = Sudoku::new_with_grid(grid.clone(), raw.constraint);
+
+    if !sudoku.is_valid() {
+        return Err(EntryProblem::InvalidGivens);
+    }
+
+    let solution = match solve::solve(sudoku.clone()) {
+        Solution::Unique(solution) => solution,
+        Solution::Impossible => return Err(EntryProblem::Unsolvable),
+        Solution::Ambiguous => return Err(EntryProblem::Ambiguous)
+    };
+
+    if let Some(provided_solution) = provided_solution {
+        if !same_cells(provided_solution.grid(), &solution) {
+            return Err(EntryProblem::WrongSolution);
+        }
+    }
+
+    Ok(PackEntry {
+        id: raw.id,
+        sudoku,
+        solution,
+        difficulty: raw.difficulty,
+        rating: raw.rating,
+        rules: raw.rules
+    })
+}
+
+/// Reads the ID of an entry that could not be deserialized, if possible.
+fn raw_id(entry: &Value) -> Option<String> {
+    entry.get("id").and_then(Value::as_str).map(str::to_owned)
+}
+
+impl Pack {
+
+    /// Loads a pack from its JSON code and validates all entries. Every
+    /// entry must be well-formed, have a unique, non-empty ID and a
+    /// difficulty from 1 to 5, and its puzzle must have exactly one
+    /// solution according to the solver of the engine, which must match the
+    /// provided solution, if any.
+    ///
+    /// # Errors
+    ///
+    /// If the pack itself is malformed, an appropriate [PackError] is
+    /// returned. If any entries are invalid, [PackError::InvalidEntries] is
+    /// returned with one [EntryError] per invalid entry.
+    pub fn load(json: &str) -> Result<Pack, PackError> {
+        let document: Value = serde_json::from_str(json)
+            .map_err(|e| PackError::InvalidJson(e.to_string()))?;
+        let version = document.get("format_version")
+            .and_then(Value::as_u64)
+            .ok_or(PackError::InvalidVersion)?;
+
+        if version > PACK_FORMAT_VERSION {
+            return Err(PackError::UnsupportedVersion(version));
+        }
+
+        let manifest = document.get("manifest")
+            .ok_or_else(|| PackError::InvalidManifest(
+                "missing field `manifest`".to_owned()))
+            .and_then(|manifest| PackManifest::deserialize(manifest)
+                .map_err(|e| PackError::InvalidManifest(e.to_string())))?;
+        let raw_entries = document.get("entries")
+            .and_then(Value::as_array)
+            .ok_or(PackError::MissingEntries)?;
+        let mut ids = HashSet::new();
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, raw_entry) in raw_entries.iter().enumerate() {
+            let id = raw_id(raw_entry);
+            let duplicate = id.as_ref()
+                .is_some_and(|id| !id.is_empty() && !ids.insert(id.clone()));
+            let result = if duplicate {
+                Err(EntryProblem::DuplicateId)
+            }
+            else {
+                RawEntry::deserialize(raw_entry)
+                    .map_err(|e| EntryProblem::Malformed(e.to_string()))
+                    .and_then(validate_entry)
+            };
+
+            match result {
+                Ok(entry) => entries.push(entry),
+                Err(problem) => errors.push(EntryError {
+                    index,
+                    id,
+                    problem
+                })
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(PackError::InvalidEntries(errors));
+        }
+
+        Ok(Pack {
+            manifest,
+            entries
+        })
+    }
+
+    /// Gets the manifest of this pack.
+    pub fn manifest(&self) -> &PackManifest {
+        &self.manifest
+    }
+
+    /// Gets all entries of this pack in order.
+    pub fn entries(&self) -> &[PackEntry] {
+        &self.entries
+    }
+
+    /// Gets the entry with the given ID, if there is one.
+    pub fn entry(&self, id: &str) -> Option<&PackEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Gets an overview of this pack.
+    pub fn summary(&self) -> PackSummary<'_> {
+        let mut difficulty_counts =
+            vec![0; (MAX_DIFFICULTY - MIN_DIFFICULTY + 1) as usize];
+
+        for entry in &self.entries {
+            let index = (entry.difficulty - MIN_DIFFICULTY) as usize;
+            difficulty_counts[index] += 1;
+        }
+
+        PackSummary {
+            manifest: &self.manifest,
+            entry_count: self.entries.len(),
+            difficulty_counts
+        }
+    }
+
+    /// Gets all entries that satisfy the given filter in order, together
+    /// with their indices in the pack.
+    pub fn filter<'a>(&'a self, filter: &'a PackFilter)
+            -> impl Iterator<Item = PageEntry<'a>> + 'a {
+        self.entries.iter()
+            .enumerate()
+            .filter(move |(_, entry)| filter.matches(entry))
+            .map(|(index, entry)| PageEntry {
+                index,
+                entry
+            })
+    }
+
+    /// Gets one page of the entries that satisfy the filter of the given
+    /// query.
+    ///
+    /// # Errors
+    ///
+    /// [PackError::InvalidQuery] if the page size of the query is 0.
+    pub fn page(&self, query: &PackQuery) -> Result<PackPage<'_>, PackError> {
+        if query.page_size == 0 {
+            return Err(PackError::InvalidQuery(
+                "page size must be positive".to_owned()));
+        }
+
+        let total = self.filter(&query.filter).count();
+        let entries = self.filter(&query.filter)
+            .skip(query.page.saturating_mul(query.page_size))
+            .take(query.page_size)
+            .collect();
+
+        Ok(PackPage {
+            page: query.page,
+            page_count: total.div_ceil(query.page_size),
+            total,
+            entries
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::sandwich;
+
+    use serde_json::json;
+
+    const PUZZLE: &str = ".2..3..1...4.1..";
+    const SOLUTION: &str = "1243342123144132";
+
+    fn entry(id: &str, difficulty: i32) -> Value {
+        json!({
+            "id": id,
+            "puzzle": PUZZLE,
+            "difficulty": difficulty,
+            "rating": difficulty as f64 + 0.5,
+            "solution": SOLUTION
+        })
+    }
+
+    fn pack_json(entries: Vec<Value>) -> String {
+        json!({
+            "format_version": PACK_FORMAT_VERSION,
+            "manifest": {
+                "title": "Test",
+                "author": "Tester"
+            },
+            "entries": entries
+        }).to_string()
+    }
+
+    fn entry_error(index: usize, id: Option<&str>, problem: EntryProblem)
+            -> EntryError {
+        EntryError {
+            index,
+            id: id.map(str::to_owned),
+            problem
+        }
+    }
+
+    fn query(page: usize, page_size: usize) -> PackQuery {
+        PackQuery {
+            page,
+            page_size,
+            ..PackQuery::default()
+        }
+    }
+
+    #[test]
+    fn load_valid_pack() {
+        let pack = Pack::load(&pack_json(vec![entry("a", 1), entry("b", 3)]))
+            .unwrap();
+        let summary = pack.summary();
+
+        assert_eq!("Test", pack.manifest().title);
+        assert_eq!(2, summary.entry_count);
+        assert_eq!(vec![1, 0, 1, 0, 0], summary.difficulty_counts);
+        assert_eq!(3, pack.entry("b").unwrap().difficulty);
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_with_index_and_id() {
+        let mut malformed = entry("b", 1);
+        malformed.as_object_mut().unwrap().remove("difficulty");
+        let mut ambiguous = entry("c", 1);
+        ambiguous["puzzle"] = json!("................");
+        ambiguous["solution"] = Value::Null;
+        let mut unsolvable = entry("d", 1);
+        unsolvable["puzzle"] = json!("12....3...4.....");
+        unsolvable["solution"] = Value::Null;
+        let mut wrong_solution = entry("e", 1);
+        wrong_solution["solution"] = json!("1234341221434321");
+        let mut mismatch = entry("f", 1);
+        mismatch["constraint"] = serde_json::to_value(sandwich(9)).unwrap();
+        let entries = vec![
+            entry("a", 1),
+            entry("a", 2),
+            malformed,
+            json!({ "puzzle": PUZZLE }),
+            ambiguous,
+            unsolvable,
+            wrong_solution,
+            mismatch
+        ];
+        let errors = match Pack::load(&pack_json(entries)) {
+            Err(PackError::InvalidEntries(errors)) => errors,
+            _ => panic!("pack with invalid entries was loaded")
+        };
+
+        assert!(matches!(&errors[1].problem, EntryProblem::Malformed(_)));
+        assert!(matches!(&errors[2].problem, EntryProblem::Malformed(_)));
+
+        let expected = vec![
+            entry_error(1, Some("a"), EntryProblem::DuplicateId),
+            entry_error(2, Some("b"), errors[1].problem.clone()),
+            entry_error(3, None, errors[2].problem.clone()),
+            entry_error(4, Some("c"), EntryProblem::Ambiguous),
+            entry_error(5, Some("d"), EntryProblem::Unsolvable),
+            entry_error(6, Some("e"), EntryProblem::WrongSolution),
+            entry_error(7, Some("f"), EntryProblem::ConstraintSizeMismatch)
+        ];
+
+        assert_eq!(expected, errors);
+    }
+
+    #[test]
+    fn page_size_zero_is_rejected() {
+        let pack = Pack::load(&pack_json(vec![entry("a", 1)])).unwrap();
+
+        assert!(matches!(pack.page(&query(0, 0)),
+            Err(PackError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn pages_split_filtered_entries() {
+        let entries = (1..=5)
+            .map(|difficulty| entry(&difficulty.to_string(), difficulty))
+            .collect();
+        let pack = Pack::load(&pack_json(entries)).unwrap();
+        let mut query = query(1, 2);
+        query.filter.min_difficulty = Some(2);
+        let page = pack.page(&query).unwrap();
+        let indices = page.entries.iter()
+            .map(|entry| entry.index)
+            .collect::<Vec<_>>();
+
+        assert_eq!(1, page.page);
+        assert_eq!(2, page.page_count);
+        assert_eq!(4, page.total);
+        assert_eq!(vec![3, 4], indices);
+    }
+
+    #[test]
+    fn out_of_range_page_is_empty() {
+        let pack = Pack::load(&pack_json(vec![entry("a", 1), entry("b", 2)]))
+            .unwrap();
+        let page = pack.page(&query(usize::MAX, 2)).unwrap();
+
+        assert_eq!(usize::MAX, page.page);
+        assert_eq!(1, page.page_count);
+        assert_eq!(2, page.total);
+        assert!(page.entries.is_empty());
+    }
+}
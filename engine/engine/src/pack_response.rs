@@ -0,0 +1,32 @@
+use crate::pack::PackError;
+
+use serde::Serialize;
+
+/// The response to a `load_pack` or `pack_page` call to the engine. To be
+/// serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum PackResponse<T> {
+
+    /// Indicates that the pack was loaded successfully. The summary of the
+    /// pack or the requested page of entries is provided.
+    #[serde(rename = "ok")]
+    Ok(T),
+
+    /// Indicates that the pack could not be loaded. The reason, including
+    /// all invalid entries, is provided.
+    #[serde(rename = "error")]
+    Error(PackError)
+}
+
+impl<T> PackResponse<T> {
+
+    /// Wraps the result of an operation on a pack in a pack response.
+    pub(crate) fn from_result(result: Result<T, PackError>)
+            -> PackResponse<T> {
+        match result {
+            Ok(value) => PackResponse::Ok(value),
+            Err(e) => PackResponse::Error(e)
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::constraint::AnyConstraint;
+use crate::line_format::LineFormatError;
+use crate::schema;
+
+use serde::Serialize;
+
+use sudoku_variants::Sudoku;
+
+/// The response to a `parse_line` call to the engine. To be serialized for
+/// return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum ParseLineResponse {
+
+    /// Indicates that the line was parsed successfully. The parsed Sudoku is
+    /// provided.
+    #[serde(rename = "ok")]
+    Ok(#[serde(serialize_with = "schema::serialize")] Sudoku<AnyConstraint>),
+
+    /// Indicates that the line could not be parsed. The reason is provided.
+    #[serde(rename = "error")]
+    Error(LineFormatError)
+}
+
+impl ParseLineResponse {
+
+    /// Wraps the result of parsing a line in a parse line response.
+    pub(crate) fn from_result(
+            result: Result<Sudoku<AnyConstraint>, LineFormatError>)
+            -> ParseLineResponse {
+        match result {
+            Ok(sudoku) => ParseLineResponse::Ok(sudoku),
+            Err(e) => ParseLineResponse::Error(e)
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use crate::constraint::AnyConstraint;
+use crate::gen_metadata::GeneratedSudoku;
+use crate::generate::{self, Generated, GenerationTask, RunError};
+use crate::schema;
+use crate::sync::CancelHandle;
+use crate::workers::Priority;
+
+use serde::{Deserialize, Serialize};
+
+use serde_json::Value;
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const PUZZLE_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// The name of the file in the pool directory which lists the requested keys.
+const KEYS_FILE: &str = "keys.json";
+
+/// The field of a stored puzzle which holds its generation metadata.
+const METADATA_FIELD: &str = "metadata";
+
+/// Identifies a class of interchangeable puzzles in a [PuzzlePool].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) struct PoolKey {
+
+    /// The constraint identifier, see the crate-level documentation.
+    pub(crate) constraint: i32,
+
+    /// The difficulty on a scale from 1 to 5 (both inclusive).
+    pub(crate) difficulty: i32,
+
+    /// The width of the blocks of the Sudoku.
+    pub(crate) block_width: usize,
+
+    /// The height of the blocks of the Sudoku.
+    pub(crate) block_height: usize
+}
+
+impl PoolKey {
+    fn generation_task(&self) -> GenerationTask {
+        generate::generation_task(self.constraint, self.difficulty,
+            self.block_width, self.block_height)
+    }
+
+    fn dir_name(&self) -> String {
+        format!("{}-{}-{}x{}", self.constraint, self.difficulty,
+            self.block_width, self.block_height)
+    }
+}
+
+struct PoolState {
+
+    /// All keys which have been requested and shall thus be kept filled.
+    keys: HashSet<PoolKey>,
+
+    /// Keys for which a refill worker is currently generating a puzzle.
+    in_flight: HashSet<PoolKey>,
+
+    /// The handle that is cancelled when the pool is suspended or closed.
+    cancel_handle: CancelHandle,
+    suspended: bool,
+    closed: bool
+}
+
+struct Shared {
+    dir: PathBuf,
+    capacity: usize,
+    throttle: Duration,
+    state: Mutex<PoolState>,
+    wakeup: Condvar,
+    file_counter: AtomicU64
+}
+
+impl Shared {
+    fn key_dir(&self, key: &PoolKey) -> PathBuf {
+        self.dir.join(key.dir_name())
+    }
+
+    fn puzzle_files(&self, key: &PoolKey) -> Vec<PathBuf> {
+        let entries = match fs::read_dir(self.key_dir(key)) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new()
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| has_extension(path, PUZZLE_EXTENSION))
+            .collect()
+    }
+
+    /// Removes and returns some stored puzzle for the given key, if there is
+    /// one, see [read_puzzle]. Files that cannot be parsed, e.g. due to a
+    /// crash while they were being taken, are removed and skipped.
+    fn take(&self, key: &PoolKey) -> Option<GeneratedSudoku> {
+        for path in self.puzzle_files(key) {
+            let json = fs::read_to_string(&path);
+
+            if fs::remove_file(&path).is_err() {
+                // Another thread took it concurrently.
+                continue;
+            }
+
+            let sudoku = json.ok().and_then(|json| read_puzzle(&json));
+
+            if sudoku.is_some() {
+                return sudoku;
+            }
+        }
+
+        None
+    }
+
+    /// Stores the given puzzle for the given key. The puzzle is written with
+    /// [write_atomically], so a crash never leaves a partially written puzzle
+    /// behind.
+    fn store(&self, key: &PoolKey, sudoku: &GeneratedSudoku)
+            -> io::Result<()> {
+        let dir = self.key_dir(key);
+        fs::create_dir_all(&dir)?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let counter = self.file_counter.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{}-{}", timestamp, counter);
+        let temp_path = dir.join(&name).with_extension(TEMP_EXTENSION);
+        let path = dir.join(&name).with_extension(PUZZLE_EXTENSION);
+        let json = serde_json::to_string(sudoku)?;
+        write_atomically(&temp_path, &path, &json)
+    }
+
+    /// Persists the given requested keys, so they are refilled again after
+    /// the pool is reopened.
+    fn store_keys(&self, keys: &HashSet<PoolKey>) -> io::Result<()> {
+        let path = self.dir.join(KEYS_FILE);
+        let json = serde_json::to_string(keys)?;
+        write_atomically(&path.with_extension(TEMP_EXTENSION), &path, &json)
+    }
+
+    /// Chooses a key that is below capacity and not currently being refilled.
+    fn next_refill_key(&self, state: &PoolState) -> Option<PoolKey> {
+        state.keys.iter()
+            .filter(|key| !state.in_flight.contains(*key))
+            .find(|key| self.puzzle_files(key).len() < self.capacity)
+            .copied()
+    }
+
+    /// Completes the refill of the given key with the result of its
+    /// generation and returns the locked state. A refill which yields no
+    /// Sudoku, because it was cancelled or the source was exhausted, stores
+    /// nothing but counts as stored, so the key is picked up again later. If
+    /// the Sudoku cannot be written, e.g. because the disk is full, the key is
+    /// no longer refilled until it is requested again.
+    fn finish_refill(&self, key: &PoolKey,
+            generated: Result<Generated<AnyConstraint>, RunError>)
+            -> MutexGuard<'_, PoolState> {
+        let stored = match generated {
+            Ok(generated) => self
+                .store(key, &GeneratedSudoku::from_generated(generated))
+                .is_ok(),
+            Err(RunError::Cancelled) | Err(RunError::Exhausted) => true
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.in_flight.remove(key);
+
+        if !stored {
+            state.keys.remove(key);
+            let _ = self.store_keys(&state.keys);
+        }
+
+        state
+    }
+}
+
+/// Parses a stored puzzle. Puzzles stored with an older schema version are
+/// migrated, and those stored by an older version without metadata are given
+/// [unknown](crate::gen_metadata::GenMetadata::unknown) metadata. Returns
+/// `None` if the puzzle cannot be parsed.
+fn read_puzzle(json: &str) -> Option<GeneratedSudoku> {
+    let document: Value = serde_json::from_str(json).ok()?;
+    let document = schema::migrate(document).ok()?;
+
+    if document.get(METADATA_FIELD).is_some() {
+        serde_json::from_value(document).ok()
+    }
+    else {
+        schema::from_value(document).ok().map(GeneratedSudoku::without_metadata)
+    }
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().map_or(false, |e| e == extension)
+}
+
+/// Writes the given contents to a temporary file, synchronizes it to disk, and
+/// atomically renames it to the given path.
+fn write_atomically(temp_path: &Path, path: &Path, contents: &str)
+        -> io::Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(temp_path, path)
+}
+
+/// Removes the temporary files of interrupted writes in the given directory
+/// and its key directories.
+fn remove_temp_files(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if path.is_dir() {
+            remove_temp_files(&path);
+        }
+        else if has_extension(&path, TEMP_EXTENSION) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Reads the keys that were requested before the pool in the given directory
+/// was last closed. If there are none or they cannot be read, no key is
+/// returned.
+fn load_keys(dir: &Path) -> HashSet<PoolKey> {
+    fs::read_to_string(dir.join(KEYS_FILE)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn refill_worker(shared: Arc<Shared>) {
+    loop {
+        let (key, cancel_handle) = {
+            let mut state = shared.state.lock().unwrap();
+
+            loop {
+                if state.closed {
+                    return;
+                }
+
+                if !state.suspended {
+                    if let Some(key) = shared.next_refill_key(&state) {
+                        state.in_flight.insert(key);
+                        break (key, state.cancel_handle.clone());
+                    }
+                }
+
+                state = shared.wakeup.wait(state).unwrap();
+            }
+        };
+
+        // Refills are cancelled when the pool is suspended or closed, and
+        // preempted by interactive generation. Either way, nothing is stored
+        // and the key is picked up again later.
+
+        let generated = key.generation_task()
+            .run(Priority::Background, None, &cancel_handle);
+        let mut state = shared.finish_refill(&key, generated);
+
+        // Pause for the throttle duration, but wake up immediately once the
+        // pool is closed, so closing it does not block.
+
+        let resume = Instant::now() + shared.throttle;
+
+        while !state.closed {
+            let timeout = resume.saturating_duration_since(Instant::now());
+
+            if timeout.is_zero() {
+                break;
+            }
+
+            state = shared.wakeup.wait_timeout(state, timeout).unwrap().0;
+        }
+    }
+}
+
+/// A pool of pre-generated puzzles which is persisted in a directory on disk.
+/// Every puzzle is stored in its own file, grouped into one subdirectory per
+/// [PoolKey]. Background worker threads refill every key that has been
+/// requested at least once up to a fixed capacity, pausing for a throttle
+/// duration after every generated puzzle.
+pub(crate) struct PuzzlePool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>
+}
+
+impl PuzzlePool {
+
+    /// Opens a puzzle pool in the given directory, which is created if it does
+    /// not exist yet. Leftovers of interrupted writes are removed. Keys that
+    /// were requested before the pool was last closed are refilled again.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir`: The directory in which to store the puzzles.
+    /// * `capacity`: The number of puzzles to keep available for each key.
+    /// * `refill_threads`: The number of background threads that refill the
+    /// pool. Each of them occupies one thread of the shared worker pool while
+    /// generating.
+    /// * `throttle`: The duration for which each refill thread pauses after
+    /// generating a puzzle.
+    pub(crate) fn open(dir: impl Into<PathBuf>, capacity: usize,
+            refill_threads: usize, throttle: Duration)
+            -> io::Result<PuzzlePool> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        remove_temp_files(&dir);
+        let keys = load_keys(&dir);
+
+        let shared = Arc::new(Shared {
+            dir,
+            capacity,
+            throttle,
+            state: Mutex::new(PoolState {
+                keys,
+                in_flight: HashSet::new(),
+                cancel_handle: CancelHandle::new(),
+                suspended: false,
+                closed: false
+            }),
+            wakeup: Condvar::new(),
+            file_counter: AtomicU64::new(0)
+        });
+        let workers = (0..refill_threads)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || refill_worker(shared))
+            })
+            .collect();
+
+        Ok(PuzzlePool {
+            shared,
+            workers
+        })
+    }
+
+    /// Gets a puzzle for the given key. If the pool contains one, it is
+    /// removed and returned immediately, otherwise one is generated on
+    /// demand. In any case, the key is registered to be refilled in the
+    /// background, and persisted to be refilled after a restart as well.
+    pub(crate) fn get(&self, key: PoolKey) -> GeneratedSudoku {
+        let taken = self.shared.take(&key);
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.keys.insert(key) {
+            // If the keys cannot be persisted, they are merely not refilled
+            // before being requested again after a restart.
+            let _ = self.shared.store_keys(&state.keys);
+        }
+
+        drop(state);
+        self.shared.wakeup.notify_all();
+
+        taken.unwrap_or_else(|| {
+            let generated = key.generation_task()
+                .run(Priority::Interactive, None, &CancelHandle::new())
+                .unwrap();
+            GeneratedSudoku::from_generated(generated)
+        })
+    }
+
+    /// Suspends refilling the pool. Generation that is currently in progress
+    /// is cancelled.
+    pub(crate) fn suspend(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.suspended = true;
+        state.cancel_handle.cancel();
+    }
+
+    /// Resumes refilling the pool after it was suspended.
+    pub(crate) fn resume(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.suspended {
+            state.suspended = false;
+            state.cancel_handle = CancelHandle::new();
+            self.shared.wakeup.notify_all();
+        }
+    }
+}
+
+impl Drop for PuzzlePool {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.closed = true;
+            state.cancel_handle.cancel();
+        }
+
+        self.shared.wakeup.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    use sudoku_variants::{Sudoku, SudokuGrid};
+
+    const KEY: PoolKey = PoolKey {
+        constraint: 0,
+        difficulty: 1,
+        block_width: 3,
+        block_height: 3
+    };
+
+    /// Opens a pool without refill threads in a fresh temporary directory,
+    /// so refills only happen when a test simulates them.
+    fn open_pool(name: &str) -> PuzzlePool {
+        let dir = env::temp_dir()
+            .join(format!("sudoverse-pool-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        PuzzlePool::open(dir, 2, 0, Duration::ZERO).unwrap()
+    }
+
+    fn close_pool(pool: PuzzlePool) {
+        let dir = pool.shared.dir.clone();
+        drop(pool);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Registers the key as requested and in flight, as a refill worker does
+    /// before generating, and returns the cancel handle of the refill.
+    fn begin_refill(pool: &PuzzlePool) -> CancelHandle {
+        let mut state = pool.shared.state.lock().unwrap();
+        state.keys.insert(KEY);
+        state.in_flight.insert(KEY);
+        state.cancel_handle.clone()
+    }
+
+    fn assert_kept_without_puzzles(pool: &PuzzlePool) {
+        let state = pool.shared.state.lock().unwrap();
+
+        assert!(state.keys.contains(&KEY));
+        assert!(!state.in_flight.contains(&KEY));
+        assert!(pool.shared.puzzle_files(&KEY).is_empty());
+    }
+
+    fn sudoku() -> Sudoku<AnyConstraint> {
+        let mut grid = SudokuGrid::new(3, 3).unwrap();
+        grid.set_cell(4, 2, 7).unwrap();
+        Sudoku::new_with_grid(grid, AnyConstraint::Diagonals)
+    }
+
+    fn assert_same_sudoku(expected: &Sudoku<AnyConstraint>,
+            actual: &GeneratedSudoku) {
+        assert_eq!(serde_json::to_value(expected).unwrap(),
+            serde_json::to_value(&actual.sudoku).unwrap());
+    }
+
+    #[test]
+    fn read_current_puzzle() {
+        let stored = GeneratedSudoku::without_metadata(sudoku());
+        let json = serde_json::to_string(&stored).unwrap();
+
+        assert_same_sudoku(&sudoku(), &read_puzzle(&json).unwrap());
+    }
+
+    #[test]
+    fn read_puzzle_without_metadata() {
+        let legacy = serde_json::to_string(&sudoku()).unwrap();
+        let versioned = schema::to_json(&sudoku());
+
+        assert_same_sudoku(&sudoku(), &read_puzzle(&legacy).unwrap());
+        assert_same_sudoku(&sudoku(), &read_puzzle(&versioned).unwrap());
+    }
+
+    #[test]
+    fn read_invalid_puzzle() {
+        assert!(read_puzzle("").is_none());
+        assert!(read_puzzle("{\"grid\":").is_none());
+        assert!(read_puzzle("{\"metadata\":{}}").is_none());
+        assert!(read_puzzle("[]").is_none());
+    }
+
+    #[test]
+    fn store_and_take() {
+        let pool = open_pool("store-and-take");
+        let stored = GeneratedSudoku::without_metadata(sudoku());
+        pool.shared.store(&KEY, &stored).unwrap();
+        pool.shared.store(&KEY, &stored).unwrap();
+
+        assert_eq!(2, pool.shared.puzzle_files(&KEY).len());
+        assert_same_sudoku(&sudoku(), &pool.shared.take(&KEY).unwrap());
+        assert_same_sudoku(&sudoku(), &pool.shared.take(&KEY).unwrap());
+        assert!(pool.shared.take(&KEY).is_none());
+        assert!(pool.shared.puzzle_files(&KEY).is_empty());
+
+        close_pool(pool);
+    }
+
+    #[test]
+    fn take_skips_unreadable_puzzles() {
+        let pool = open_pool("take-skips-unreadable");
+        let dir = pool.shared.key_dir(&KEY);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("broken.json"), "{\"grid\":").unwrap();
+
+        assert!(pool.shared.take(&KEY).is_none());
+        assert!(pool.shared.puzzle_files(&KEY).is_empty());
+
+        close_pool(pool);
+    }
+
+    #[test]
+    fn suspend_cancels_refill_without_storing() {
+        let pool = open_pool("suspend");
+        let cancel_handle = begin_refill(&pool);
+        pool.suspend();
+
+        assert!(cancel_handle.is_cancelled());
+
+        let generated = KEY.generation_task()
+            .run(Priority::Background, None, &cancel_handle);
+
+        assert_eq!(Some(&RunError::Cancelled), generated.as_ref().err());
+
+        drop(pool.shared.finish_refill(&KEY, generated));
+        assert_kept_without_puzzles(&pool);
+
+        pool.resume();
+        let state = pool.shared.state.lock().unwrap();
+
+        assert!(!state.suspended);
+        assert!(!state.cancel_handle.is_cancelled());
+
+        drop(state);
+
+        close_pool(pool);
+    }
+
+    #[test]
+    fn exhausted_refill_counts_as_stored() {
+        let pool = open_pool("exhausted");
+        begin_refill(&pool);
+        drop(pool.shared.finish_refill(&KEY, Err(RunError::Exhausted)));
+
+        assert_kept_without_puzzles(&pool);
+
+        close_pool(pool);
+    }
+}
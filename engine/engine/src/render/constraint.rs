@@ -0,0 +1,88 @@
+//! Drawing of the markings of each variant of [AnyConstraint].
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+use crate::render::{Color, Layout, Margins, Shape};
+
+use sudoku_variants::constraint::SandwichConstraint;
+
+const DIAGONAL_WIDTH: f64 = 0.04;
+const CLUE_SIZE: f64 = 0.45;
+
+/// Gets the space the given constraint requires outside the grid.
+pub(crate) fn margins(constraint: &AnyConstraint) -> Margins {
+    match constraint {
+        AnyConstraint::Sandwich(_) => sandwich_margins(),
+        AnyConstraint::Composite(cs) => cs.iter()
+            .map(margins)
+            .fold(Margins::default(), Margins::max),
+        _ => Margins::default()
+    }
+}
+
+/// Draws the markings of the given constraint, including those of all
+/// constraints nested in composite constraints.
+pub(crate) fn draw(constraint: &AnyConstraint, layout: &Layout,
+        shapes: &mut Vec<Shape>) {
+    match constraint {
+        AnyConstraint::Default => { },
+        AnyConstraint::Diagonals => draw_diagonals(layout, shapes),
+        AnyConstraint::KnightsMove => { },
+        AnyConstraint::KingsMove => { },
+        AnyConstraint::Sandwich(c) => draw_sandwich(c, layout, shapes),
+        AnyConstraint::Composite(cs) => {
+            for c in cs {
+                draw(c, layout, shapes);
+            }
+        }
+    }
+}
+
+fn draw_diagonals(layout: &Layout, shapes: &mut Vec<Shape>) {
+    let size = layout.size as isize;
+    let line = |x1, y1, x2, y2| Shape::Line {
+        x1,
+        y1,
+        x2,
+        y2,
+        width: DIAGONAL_WIDTH * layout.cell_size,
+        color: Color::LIGHT_GRAY
+    };
+
+    shapes.push(line(layout.x(0), layout.y(0), layout.x(size),
+        layout.y(size)));
+    shapes.push(line(layout.x(0), layout.y(size), layout.x(size),
+        layout.y(0)));
+}
+
+fn sandwich_margins() -> Margins {
+    Margins {
+        top: 1.0,
+        left: 1.0,
+        ..Margins::default()
+    }
+}
+
+/// Draws the sandwich sums of the columns above the grid and those of the
+/// rows to its left.
+fn draw_sandwich(constraint: &SandwichConstraint, layout: &Layout,
+        shapes: &mut Vec<Shape>) {
+    let clues = SandwichClues::of(constraint);
+    let columns = clues.columns.iter().enumerate()
+        .map(|(column, &clue)| (column as isize, -1, clue));
+    let rows = clues.rows.iter().enumerate()
+        .map(|(row, &clue)| (-1, row as isize, clue));
+
+    for (column, row, clue) in columns.chain(rows) {
+        if let Some(clue) = clue {
+            let (x, y) = layout.center(column, row);
+            shapes.push(Shape::Text {
+                x,
+                y,
+                content: clue.to_string(),
+                size: CLUE_SIZE * layout.cell_size,
+                bold: false,
+                color: Color::BLACK
+            });
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Rendering of Sudoku into a resolution-independent [Drawing], which is then
+//! written in a concrete format such as SVG (see [to_svg]) or, as part of a
+//! printable booklet, PDF (see [booklet_pdf]). A drawing consists of the grid
+//! with its cell and block lines, the givens, optionally digits and
+//! pencilmarks entered by a player, and the markings of the constraint.
+//!
+//! Each variant of [AnyConstraint] is drawn by its own function, so new
+//! variants with cages, lines, or dots only need to add theirs. Variants
+//! whose rules have no visual representation in the grid, such as knight's
+//! move and king's move, are not drawn.
+//!
+//! Independently of drawings, Sudoku can be rendered as Unicode text for logs
+//! and terminals, see [render_text].
+
+use crate::constraint::AnyConstraint;
+use crate::solve;
+
+use serde::Deserialize;
+
+use sudoku_variants::Sudoku;
+use sudoku_variants::solver::Solution;
+
+mod constraint;
+mod pdf;
+mod svg;
+mod text;
+
+pub use pdf::{Booklet, BookletPuzzle, booklet_pdf};
+pub use svg::to_svg;
+pub use text::{TextOptions, candidate_annotations, render_text};
+
+/// The space around the grid and its margins, as a fraction of the cell size.
+const PADDING: f64 = 0.25;
+
+const THIN_LINE: f64 = 0.025;
+const THICK_LINE: f64 = 0.075;
+const DIGIT_SIZE: f64 = 0.6;
+const PENCILMARK_SIZE: f64 = 0.22;
+
+/// An RGB color.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const BLACK: Color = Color(0, 0, 0);
+    pub const WHITE: Color = Color(255, 255, 255);
+    pub const GRAY: Color = Color(128, 128, 128);
+    pub const LIGHT_GRAY: Color = Color(192, 192, 192);
+    pub const BLUE: Color = Color(30, 80, 200);
+}
+
+/// A primitive shape of a [Drawing]. All coordinates and lengths are in the
+/// units of the drawing, with the origin in the top left corner and the y
+/// axis pointing down.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+
+    /// A straight line between two points.
+    Line {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        width: f64,
+        color: Color
+    },
+
+    /// An axis-aligned rectangle, which is filled and/or outlined.
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        fill: Option<Color>,
+        stroke: Option<(Color, f64)>
+    },
+
+    /// A single line of text, centered horizontally and vertically on the
+    /// given point.
+    Text {
+        x: f64,
+        y: f64,
+        content: String,
+        size: f64,
+        bold: bool,
+        color: Color
+    }
+}
+
+/// A resolution-independent drawing of a Sudoku, consisting of shapes that
+/// are painted in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drawing {
+
+    /// The width of the drawing.
+    pub width: f64,
+
+    /// The height of the drawing.
+    pub height: f64,
+
+    /// The shapes of the drawing, where later shapes are painted over earlier
+    /// ones.
+    pub shapes: Vec<Shape>
+}
+
+/// The state of a puzzle as entered by a player, which is drawn in addition
+/// to the givens. To be deserialized from the input, where all fields are
+/// optional.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Annotations {
+
+    /// The digits entered by the player in row-major order, or an empty list
+    /// if there are none. Digits in cells with a given are ignored.
+    pub digits: Vec<Option<usize>>,
+
+    /// The pencilmarks of every cell in row-major order, or an empty list if
+    /// there are none. Pencilmarks of filled cells are ignored.
+    pub pencilmarks: Vec<Vec<usize>>
+}
+
+impl Annotations {
+    fn digit(&self, index: usize) -> Option<usize> {
+        self.digits.get(index).copied().flatten()
+    }
+
+    fn pencilmarks(&self, index: usize) -> &[usize] {
+        self.pencilmarks.get(index).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+fn default_cell_size() -> f64 {
+    40.0
+}
+
+/// Options that control the appearance of a [Drawing]. To be deserialized
+/// from the input, where all fields are optional.
+#[derive(Clone, Deserialize)]
+pub struct RenderOptions {
+
+    /// The width and height of a cell in units of the drawing. Defaults to
+    /// 40.
+    #[serde(default = "default_cell_size")]
+    pub cell_size: f64
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            cell_size: default_cell_size()
+        }
+    }
+}
+
+/// The space a constraint requires outside the grid, e.g. for clues, in cells.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Margins {
+    pub(crate) top: f64,
+    pub(crate) left: f64,
+    pub(crate) bottom: f64,
+    pub(crate) right: f64
+}
+
+impl Margins {
+    pub(crate) fn max(self, other: Margins) -> Margins {
+        Margins {
+            top: self.top.max(other.top),
+            left: self.left.max(other.left),
+            bottom: self.bottom.max(other.bottom),
+            right: self.right.max(other.right)
+        }
+    }
+}
+
+/// The position of the grid within a [Drawing].
+pub(crate) struct Layout {
+
+    /// The width and height of a cell.
+    pub(crate) cell_size: f64,
+
+    /// The x coordinate of the left edge of the grid.
+    pub(crate) left: f64,
+
+    /// The y coordinate of the top edge of the grid.
+    pub(crate) top: f64,
+
+    /// The number of cells in each row and column.
+    pub(crate) size: usize
+}
+
+impl Layout {
+
+    /// Gets the x coordinate of the left edge of the given column, which may
+    /// be negative or `size` for positions outside the grid.
+    pub(crate) fn x(&self, column: isize) -> f64 {
+        self.left + column as f64 * self.cell_size
+    }
+
+    /// Gets the y coordinate of the top edge of the given row, which may be
+    /// negative or `size` for positions outside the grid.
+    pub(crate) fn y(&self, row: isize) -> f64 {
+        self.top + row as f64 * self.cell_size
+    }
+
+    /// Gets the center of the given cell, which may be outside the grid.
+    pub(crate) fn center(&self, column: isize, row: isize) -> (f64, f64) {
+        let half = self.cell_size / 2.0;
+        (self.x(column) + half, self.y(row) + half)
+    }
+
+    /// Gets the x or y coordinate of the right or bottom edge of the grid.
+    pub(crate) fn extent(&self) -> f64 {
+        self.size as f64 * self.cell_size
+    }
+}
+
+/// Formats a coordinate or length with at most two decimal places and
+/// without trailing zeros, as used by all output formats.
+pub(crate) fn format_number(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    match trimmed {
+        "-0" => "0".to_owned(),
+        _ => trimmed.to_owned()
+    }
+}
+
+fn draw_grid_lines(sudoku: &Sudoku<AnyConstraint>, layout: &Layout,
+        shapes: &mut Vec<Shape>) {
+    let grid = sudoku.grid();
+    let size = layout.size as isize;
+    let cell_size = layout.cell_size;
+    let line = |x1, y1, x2, y2| move |width: f64| Shape::Line {
+        x1,
+        y1,
+        x2,
+        y2,
+        width: width * cell_size,
+        color: Color::BLACK
+    };
+    let mut thick = Vec::new();
+
+    for i in 0..=size {
+        let vertical_thick = i as usize % grid.block_width() == 0;
+        let horizontal_thick = i as usize % grid.block_height() == 0;
+        let vertical = (line(layout.x(i), layout.y(0), layout.x(i),
+            layout.y(size)), vertical_thick);
+        let horizontal = (line(layout.x(0), layout.y(i), layout.x(size),
+            layout.y(i)), horizontal_thick);
+
+        for (shape, is_thick) in [vertical, horizontal] {
+            if is_thick {
+                thick.push(shape(THICK_LINE));
+            }
+            else {
+                shapes.push(shape(THIN_LINE));
+            }
+        }
+    }
+
+    // Thick lines are drawn last so thin lines do not cut through them.
+
+    shapes.append(&mut thick);
+}
+
+fn draw_pencilmarks(marks: &[usize], column: isize, row: isize,
+        layout: &Layout, shapes: &mut Vec<Shape>) {
+    let size = layout.size;
+    let columns = (1..=size).find(|c| c * c >= size).unwrap_or(1);
+    let rows = size.div_ceil(columns);
+    let width = layout.cell_size / columns as f64;
+    let height = layout.cell_size / rows as f64;
+
+    for &mark in marks.iter().filter(|&m| (1..=size).contains(m)) {
+        let index = mark - 1;
+        shapes.push(Shape::Text {
+            x: layout.x(column) + ((index % columns) as f64 + 0.5) * width,
+            y: layout.y(row) + ((index / columns) as f64 + 0.5) * height,
+            content: mark.to_string(),
+            size: PENCILMARK_SIZE * layout.cell_size,
+            bold: false,
+            color: Color::GRAY
+        });
+    }
+}
+
+fn draw_cells(sudoku: &Sudoku<AnyConstraint>, annotations: &Annotations,
+        layout: &Layout, shapes: &mut Vec<Shape>) {
+    let grid = sudoku.grid();
+    let size = layout.size;
+
+    for row in 0..size {
+        for column in 0..size {
+            let index = row * size + column;
+            let (x, y) = layout.center(column as isize, row as isize);
+            let digit = match grid.get_cell(column, row).unwrap() {
+                Some(given) => Some((given, true)),
+                None => annotations.digit(index).map(|d| (d, false))
+            };
+
+            match digit {
+                Some((digit, given)) => shapes.push(Shape::Text {
+                    x,
+                    y,
+                    content: digit.to_string(),
+                    size: DIGIT_SIZE * layout.cell_size,
+                    bold: given,
+                    color: if given { Color::BLACK } else { Color::BLUE }
+                }),
+                None => draw_pencilmarks(annotations.pencilmarks(index),
+                    column as isize, row as isize, layout, shapes)
+            }
+        }
+    }
+}
+
+/// Draws the given Sudoku together with the digits and pencilmarks entered by
+/// a player.
+///
+/// # Arguments
+///
+/// * `sudoku`: The Sudoku to draw.
+/// * `annotations`: The player's digits and pencilmarks. Use
+/// `Annotations::default()` to draw the puzzle only.
+/// * `options`: Options that control the appearance of the drawing.
+pub fn draw(sudoku: &Sudoku<AnyConstraint>, annotations: &Annotations,
+        options: &RenderOptions) -> Drawing {
+    let cell_size = options.cell_size;
+    let size = sudoku.grid().size();
+    let margins = constraint::margins(sudoku.constraint());
+    let layout = Layout {
+        cell_size,
+        left: (PADDING + margins.left) * cell_size,
+        top: (PADDING + margins.top) * cell_size,
+        size
+    };
+    let width = layout.left + layout.extent() +
+        (PADDING + margins.right) * cell_size;
+    let height = layout.top + layout.extent() +
+        (PADDING + margins.bottom) * cell_size;
+    let mut shapes = vec![Shape::Rect {
+        x: 0.0,
+        y: 0.0,
+        width,
+        height,
+        fill: Some(Color::WHITE),
+        stroke: None
+    }];
+
+    constraint::draw(sudoku.constraint(), &layout, &mut shapes);
+    draw_grid_lines(sudoku, &layout, &mut shapes);
+    draw_cells(sudoku, annotations, &layout, &mut shapes);
+
+    Drawing {
+        width,
+        height,
+        shapes
+    }
+}
+
+/// Gets annotations that fill all empty cells of the given Sudoku with the
+/// digits of its solution, which can be passed to [draw] to draw the
+/// solution. Returns `None` if the Sudoku is not uniquely solvable.
+pub fn solution_annotations(sudoku: &Sudoku<AnyConstraint>)
+        -> Option<Annotations> {
+    let solution = match solve::solve(sudoku.clone()) {
+        Solution::Unique(solution) => solution,
+        _ => return None
+    };
+    let size = solution.size();
+    let digits = (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .map(|(column, row)| solution.get_cell(column, row).unwrap())
+        .collect();
+
+    Some(Annotations {
+        digits,
+        pencilmarks: Vec::new()
+    })
+}
+
+/// Draws the given Sudoku and writes the drawing as an SVG document. See
+/// [draw] for the arguments.
+pub fn render_svg(sudoku: &Sudoku<AnyConstraint>, annotations: &Annotations,
+        options: &RenderOptions) -> String {
+    to_svg(&draw(sudoku, annotations, options))
+}
@@ -0,0 +1,571 @@
+//! Writing of printable puzzle booklets as PDF documents. Every puzzle is
+//! drawn with the same [Drawing] model as the SVG output and placed on A4
+//! pages together with its number, difficulty stars, and rules text.
+//! Solutions follow on separate pages after all puzzles, if requested.
+//!
+//! The PDF is written by hand. It uses the standard Helvetica fonts, which
+//! every PDF viewer provides, so no fonts are embedded and the output consists
+//! of ASCII characters only. Texts are encoded in WinAnsiEncoding, the
+//! encoding declared for the fonts, with characters outside of printable
+//! ASCII written as octal escapes. Characters that WinAnsiEncoding lacks are
+//! replaced by `?`.
+
+use crate::constraint::AnyConstraint;
+use crate::render::{self, Annotations, Color, Drawing, RenderOptions, Shape};
+use crate::schema;
+use crate::render::format_number;
+
+use serde::Deserialize;
+
+use std::fmt::Write;
+
+use sudoku_variants::Sudoku;
+
+/// The width of an A4 page in points.
+const PAGE_WIDTH: f64 = 595.0;
+
+/// The height of an A4 page in points.
+const PAGE_HEIGHT: f64 = 842.0;
+
+const MARGIN: f64 = 48.0;
+const GAP: f64 = 24.0;
+const HEADER_SIZE: f64 = 14.0;
+const HEADER_SPACE: f64 = 28.0;
+const LABEL_SIZE: f64 = 11.0;
+const RULES_SIZE: f64 = 8.5;
+const LINE_SPACING: f64 = 1.25;
+const STAR_RADIUS: f64 = 5.0;
+
+/// The height of digits in Helvetica as a fraction of the font size, used to
+/// center text vertically.
+const DIGIT_HEIGHT: f64 = 0.7;
+
+/// The widths of the characters of WinAnsiEncoding in Helvetica in
+/// thousandths of the font size, starting at the space with code 32. Codes
+/// which WinAnsiEncoding leaves undefined have width 0, as they are never
+/// written. Helvetica-Bold is slightly wider, which is not significant for
+/// the short texts of a booklet.
+const HELVETICA_WIDTHS: [u16; 224] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333,
+    278, 278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278,
+    584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278,
+    500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944,
+    667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556,
+    278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500,
+    278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, 0,
+    556, 0, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 0,
+    611, 0, 0, 222, 222, 333, 333, 350, 556, 1000, 333, 1000, 500, 333,
+    944, 0, 500, 667, 278, 333, 556, 556, 556, 556, 260, 556, 333, 737,
+    370, 556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 556, 537, 278,
+    333, 333, 365, 556, 834, 834, 834, 611, 667, 667, 667, 667, 667, 667,
+    1000, 722, 667, 667, 667, 667, 278, 278, 278, 278, 722, 722, 778, 778,
+    778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, 556, 556,
+    556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500,
+    556, 500
+];
+
+/// The characters which WinAnsiEncoding places at codes 128 to 159, where
+/// Latin-1 has control characters, together with their codes. All other
+/// codes from 32 to 255 except 127 match Latin-1.
+const WIN_ANSI_SPECIALS: [(char, u8); 27] = [
+    ('€', 0x80), ('‚', 0x82), ('ƒ', 0x83), ('„', 0x84), ('…', 0x85),
+    ('†', 0x86), ('‡', 0x87), ('ˆ', 0x88), ('‰', 0x89), ('Š', 0x8a),
+    ('‹', 0x8b), ('Œ', 0x8c), ('Ž', 0x8e), ('‘', 0x91), ('’', 0x92),
+    ('“', 0x93), ('”', 0x94), ('•', 0x95), ('–', 0x96), ('—', 0x97),
+    ('˜', 0x98), ('™', 0x99), ('š', 0x9a), ('›', 0x9b), ('œ', 0x9c),
+    ('ž', 0x9e), ('Ÿ', 0x9f)
+];
+
+/// The object numbers of the fixed objects of every document. Pages and their
+/// content streams follow in pairs.
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+const FONT_REGULAR: usize = 3;
+const FONT_BOLD: usize = 4;
+const FIRST_PAGE: usize = 5;
+
+fn default_per_page() -> usize {
+    2
+}
+
+/// A puzzle of a [Booklet] together with its metadata. To be deserialized
+/// from the input.
+#[derive(Clone, Deserialize)]
+pub struct BookletPuzzle {
+
+    /// The puzzle to print.
+    #[serde(deserialize_with = "schema::deserialize")]
+    pub sudoku: Sudoku<AnyConstraint>,
+
+    /// The number of stars shown next to the puzzle to indicate its
+    /// difficulty. Defaults to none.
+    #[serde(default)]
+    pub stars: usize,
+
+    /// The rules of the puzzle, printed above it. Defaults to none.
+    #[serde(default)]
+    pub rules: Option<String>
+}
+
+/// A batch of puzzles to be printed as a booklet. To be deserialized from the
+/// input.
+#[derive(Clone, Deserialize)]
+pub struct Booklet {
+
+    /// The title printed at the top of every page.
+    pub title: String,
+
+    /// The puzzles in the order in which they are printed.
+    pub puzzles: Vec<BookletPuzzle>,
+
+    /// The number of puzzles on each page. Defaults to 2.
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+
+    /// Whether pages with the solutions of all puzzles are added at the end.
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub solutions: bool
+}
+
+/// Gets the code of the given character in WinAnsiEncoding. Characters
+/// which it lacks, including control characters, are replaced by `?`.
+fn win_ansi(c: char) -> u8 {
+    if (' '..='~').contains(&c) || ('\u{a0}'..='\u{ff}').contains(&c) {
+        return c as u8;
+    }
+
+    WIN_ANSI_SPECIALS.iter()
+        .find(|&&(special, _)| special == c)
+        .map(|&(_, code)| code)
+        .unwrap_or(b'?')
+}
+
+/// Encodes the given text as the content of a PDF string literal. Characters
+/// outside of printable ASCII are written as octal escapes of their code in
+/// WinAnsiEncoding.
+fn pdf_text(text: &str) -> String {
+    let mut result = String::new();
+
+    for code in text.chars().map(win_ansi) {
+        match code {
+            b'(' | b')' | b'\\' => {
+                result.push('\\');
+                result.push(code as char);
+            },
+            b' '..=b'~' => result.push(code as char),
+            code => write!(result, "\\{:03o}", code).unwrap()
+        }
+    }
+
+    result
+}
+
+/// Gets the approximate width of the given text in points.
+fn text_width(text: &str, size: f64) -> f64 {
+    let width = text.chars()
+        .map(win_ansi)
+        .map(|code| HELVETICA_WIDTHS[(code - b' ') as usize] as f64)
+        .sum::<f64>();
+    width * size / 1000.0
+}
+
+/// Breaks the given text into lines no wider than the given width, breaking
+/// only between words.
+fn wrap(text: &str, size: f64, max_width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_owned()
+            }
+            else {
+                format!("{} {}", line, word)
+            };
+
+            if !line.is_empty() && text_width(&candidate, size) > max_width {
+                lines.push(line);
+                line = word.to_owned();
+            }
+            else {
+                line = candidate;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// The content stream of a page under construction. All methods take
+/// coordinates with the origin in the top left corner of the page and the y
+/// axis pointing down, like a [Drawing].
+struct Page {
+    content: String
+}
+
+impl Page {
+    fn new() -> Page {
+        Page {
+            content: String::from("2 J\n")
+        }
+    }
+
+    fn color(&mut self, Color(r, g, b): Color, operator: &str) {
+        writeln!(self.content, "{} {} {} {}",
+            format_number(r as f64 / 255.0), format_number(g as f64 / 255.0),
+            format_number(b as f64 / 255.0), operator).unwrap();
+    }
+
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64),
+            width: f64, color: Color) {
+        self.color(color, "RG");
+        writeln!(self.content, "{} w {} {} m {} {} l S", format_number(width),
+            format_number(x1), format_number(PAGE_HEIGHT - y1),
+            format_number(x2), format_number(PAGE_HEIGHT - y2)).unwrap();
+    }
+
+    fn rect(&mut self, (x, y): (f64, f64), (width, height): (f64, f64),
+            fill: Option<Color>, stroke: Option<(Color, f64)>) {
+        let operator = match (fill, stroke) {
+            (Some(_), Some(_)) => "B",
+            (Some(_), None) => "f",
+            (None, Some(_)) => "S",
+            (None, None) => return
+        };
+
+        if let Some(fill) = fill {
+            self.color(fill, "rg");
+        }
+
+        if let Some((stroke, width)) = stroke {
+            self.color(stroke, "RG");
+            writeln!(self.content, "{} w", format_number(width)).unwrap();
+        }
+
+        writeln!(self.content, "{} {} {} {} re {}", format_number(x),
+            format_number(PAGE_HEIGHT - y - height), format_number(width),
+            format_number(height), operator).unwrap();
+    }
+
+    /// Writes text starting at the given point on its baseline.
+    fn text(&mut self, (x, y): (f64, f64), text: &str, size: f64, bold: bool,
+            color: Color) {
+        let font = if bold { "F2" } else { "F1" };
+        self.color(color, "rg");
+        writeln!(self.content, "BT /{} {} Tf {} {} Td ({}) Tj ET", font,
+            format_number(size), format_number(x),
+            format_number(PAGE_HEIGHT - y), pdf_text(text)).unwrap();
+    }
+
+    /// Draws a filled five-pointed star around the given center.
+    fn star(&mut self, (x, y): (f64, f64), radius: f64, color: Color) {
+        self.color(color, "rg");
+
+        for i in 0..10 {
+            let r = if i % 2 == 0 { radius } else { radius * 0.4 };
+            let angle = std::f64::consts::PI * i as f64 / 5.0;
+            let px = x + r * angle.sin();
+            let py = PAGE_HEIGHT - y + r * angle.cos();
+            let operator = if i == 0 { "m" } else { "l" };
+            writeln!(self.content, "{} {} {}", format_number(px),
+                format_number(py), operator).unwrap();
+        }
+
+        self.content.push_str("h f\n");
+    }
+
+    /// Draws the given drawing scaled by the given factor with its top left
+    /// corner at the given point.
+    fn drawing(&mut self, drawing: &Drawing, (left, top): (f64, f64),
+            scale: f64) {
+        let map = |x: f64, y: f64| (left + x * scale, top + y * scale);
+
+        for shape in &drawing.shapes {
+            match shape {
+                Shape::Line { x1, y1, x2, y2, width, color } =>
+                    self.line(map(*x1, *y1), map(*x2, *y2), width * scale,
+                        *color),
+                Shape::Rect { x, y, width, height, fill, stroke } =>
+                    self.rect(map(*x, *y), (width * scale, height * scale),
+                        *fill, stroke.map(|(c, w)| (c, w * scale))),
+                Shape::Text { x, y, content, size, bold, color } => {
+                    let size = size * scale;
+                    let (x, y) = map(*x, *y);
+                    let x = x - text_width(content, size) / 2.0;
+                    let y = y + DIGIT_HEIGHT * size / 2.0;
+                    self.text((x, y), content, size, *bold, *color);
+                }
+            }
+        }
+    }
+}
+
+/// The area of a page in which one puzzle is placed.
+struct Slot {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64
+}
+
+fn slots(per_page: usize) -> Vec<Slot> {
+    let columns = (1..=per_page).take_while(|c| c * c <= per_page)
+        .last()
+        .unwrap_or(1);
+    let rows = per_page.div_ceil(columns);
+    let top = MARGIN + HEADER_SPACE;
+    let width = (PAGE_WIDTH - 2.0 * MARGIN - GAP * (columns - 1) as f64) /
+        columns as f64;
+    let height = (PAGE_HEIGHT - top - MARGIN - GAP * (rows - 1) as f64) /
+        rows as f64;
+
+    (0..per_page)
+        .map(|i| Slot {
+            left: MARGIN + (i % columns) as f64 * (width + GAP),
+            top: top + (i / columns) as f64 * (height + GAP),
+            width,
+            height
+        })
+        .collect()
+}
+
+fn draw_header(page: &mut Page, title: &str, number: usize) {
+    let baseline = MARGIN + HEADER_SIZE;
+    let page_label = number.to_string();
+    let label_x = PAGE_WIDTH - MARGIN - text_width(&page_label, HEADER_SIZE);
+
+    page.text((MARGIN, baseline), title, HEADER_SIZE, true, Color::BLACK);
+    page.text((label_x, baseline), &page_label, HEADER_SIZE, false,
+        Color::BLACK);
+}
+
+fn draw_puzzle(page: &mut Page, slot: &Slot, label: &str,
+        puzzle: &BookletPuzzle, rules: bool, annotations: &Annotations) {
+    let mut y = slot.top + LABEL_SIZE;
+    page.text((slot.left, y), label, LABEL_SIZE, true, Color::BLACK);
+
+    let stars_left =
+        slot.left + text_width(label, LABEL_SIZE) + STAR_RADIUS * 2.0;
+
+    for i in 0..puzzle.stars {
+        let x = stars_left + i as f64 * STAR_RADIUS * 2.5;
+        page.star((x, y - STAR_RADIUS * 0.8), STAR_RADIUS, Color::BLACK);
+    }
+
+    y += LABEL_SIZE * (LINE_SPACING - 1.0);
+
+    if let Some(text) = puzzle.rules.as_ref().filter(|_| rules) {
+        for line in wrap(text, RULES_SIZE, slot.width) {
+            y += RULES_SIZE * LINE_SPACING;
+            page.text((slot.left, y), &line, RULES_SIZE, false, Color::BLACK);
+        }
+    }
+
+    y += RULES_SIZE;
+
+    let drawing = render::draw(&puzzle.sudoku, annotations,
+        &RenderOptions::default());
+    let available_height = slot.top + slot.height - y;
+    let scale = (slot.width / drawing.width)
+        .min(available_height / drawing.height)
+        .max(0.0);
+    let left = slot.left + (slot.width - drawing.width * scale) / 2.0;
+    page.drawing(&drawing, (left, y), scale);
+}
+
+fn write_pdf(pages: Vec<Page>) -> Vec<u8> {
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", FIRST_PAGE + 2 * i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut objects = vec![
+        (CATALOG, format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES)),
+        (PAGES, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids,
+            pages.len())),
+        (FONT_REGULAR, String::from("<< /Type /Font /Subtype /Type1 \
+            /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>")),
+        (FONT_BOLD, String::from("<< /Type /Font /Subtype /Type1 \
+            /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"))
+    ];
+
+    for (i, page) in pages.into_iter().enumerate() {
+        let page_object = FIRST_PAGE + 2 * i;
+        let content_object = page_object + 1;
+        objects.push((page_object, format!("<< /Type /Page /Parent {} 0 R \
+            /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R \
+            /F2 {} 0 R >> >> /Contents {} 0 R >>", PAGES, PAGE_WIDTH,
+            PAGE_HEIGHT, FONT_REGULAR, FONT_BOLD, content_object)));
+        objects.push((content_object, format!(
+            "<< /Length {} >>\nstream\n{}endstream", page.content.len(),
+            page.content)));
+    }
+
+    for (number, body) in &objects {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj\n{}\nendobj", number, body).unwrap();
+    }
+
+    let xref = pdf.len();
+    writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1)
+        .unwrap();
+
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+
+    writeln!(pdf, "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF",
+        objects.len() + 1, CATALOG, xref).unwrap();
+    pdf.into_bytes()
+}
+
+/// Lays out the given booklet and writes it as a PDF document. Each page
+/// holds the configured number of puzzles. If solutions are requested, they
+/// follow on separate pages in the same layout. Puzzles without a unique
+/// solution are printed without digits on the solution pages.
+pub fn booklet_pdf(booklet: &Booklet) -> Vec<u8> {
+    let per_page = booklet.per_page.max(1);
+    let slots = slots(per_page);
+    let mut pages = Vec::new();
+    let mut sections = vec![(false, "Puzzle")];
+
+    if booklet.solutions {
+        sections.push((true, "Solution"));
+    }
+
+    for (solution, name) in sections {
+        for (chunk_index, chunk) in
+                booklet.puzzles.chunks(per_page).enumerate() {
+            let mut page = Page::new();
+            draw_header(&mut page, &booklet.title, pages.len() + 1);
+
+            for (i, (puzzle, slot)) in chunk.iter().zip(&slots).enumerate() {
+                let label = format!("{} {}", name,
+                    chunk_index * per_page + i + 1);
+                let annotations = if solution {
+                    render::solution_annotations(&puzzle.sudoku)
+                        .unwrap_or_default()
+                }
+                else {
+                    Annotations::default()
+                };
+
+                draw_puzzle(&mut page, slot, &label, puzzle, !solution,
+                    &annotations);
+            }
+
+            pages.push(page);
+        }
+    }
+
+    if pages.is_empty() {
+        let mut page = Page::new();
+        draw_header(&mut page, &booklet.title, 1);
+        pages.push(page);
+    }
+
+    write_pdf(pages)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::sudoku;
+
+    fn booklet() -> Booklet {
+        let puzzle = BookletPuzzle {
+            sudoku: sudoku(2, 2, AnyConstraint::Default),
+            stars: 2,
+            rules: Some(String::from("Größe (normal) – “classic”"))
+        };
+
+        Booklet {
+            title: String::from("Rätsel"),
+            puzzles: vec![puzzle; 3],
+            per_page: 2,
+            solutions: true
+        }
+    }
+
+    fn booklet_text() -> String {
+        let pdf = booklet_pdf(&booklet());
+
+        assert!(pdf.is_ascii());
+
+        String::from_utf8(pdf).unwrap()
+    }
+
+    #[test]
+    fn xref_offsets_point_at_objects() {
+        let pdf = booklet_text();
+        let startxref = pdf.rfind("startxref\n").unwrap();
+        let xref = pdf[startxref..].lines().nth(1).unwrap()
+            .parse::<usize>().unwrap();
+        let mut lines = pdf[xref..].lines();
+
+        assert_eq!(Some("xref"), lines.next());
+
+        let size = lines.next().unwrap().strip_prefix("0 ").unwrap()
+            .parse::<usize>().unwrap();
+
+        // 2 puzzle pages and 2 solution pages, each with a content stream.
+        assert_eq!(FIRST_PAGE + 2 * 4, size);
+        assert_eq!(Some("0000000000 65535 f "), lines.next());
+
+        for number in 1..size {
+            let offset = lines.next().unwrap().strip_suffix(" 00000 n ")
+                .unwrap().parse::<usize>().unwrap();
+
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", number)));
+        }
+
+        assert_eq!(Some("trailer"), lines.next());
+        assert!(pdf.contains(&format!("<< /Size {} /Root {} 0 R >>", size,
+            CATALOG)));
+    }
+
+    #[test]
+    fn stream_lengths_match_content() {
+        let pdf = booklet_text();
+        let mut streams = 0;
+
+        for (start, prefix) in pdf.match_indices("<< /Length ") {
+            let (length, content) = pdf[start + prefix.len()..]
+                .split_once(" >>\nstream\n").unwrap();
+            let length = length.parse::<usize>().unwrap();
+
+            assert!(!content[..length].contains("endstream"));
+            assert!(content[length..].starts_with("endstream\nendobj"));
+
+            streams += 1;
+        }
+
+        assert_eq!(4, streams);
+    }
+
+    #[test]
+    fn text_is_encoded_in_win_ansi() {
+        assert_eq!("Gr\\366\\337e \\(\\200\\) \\226 ?",
+            pdf_text("Größe (€) – →"));
+        assert!(booklet_text().contains("(R\\344tsel) Tj"));
+    }
+
+    #[test]
+    fn text_width_uses_win_ansi_widths() {
+        assert_eq!(0.667, text_width("A", 1.0));
+        assert_eq!(1.0, text_width("Æ", 1.0));
+        assert_eq!(1.0, text_width("—", 1.0));
+        assert_eq!(0.35, text_width("•", 1.0));
+        assert_eq!(text_width("?", 1.0), text_width("→", 1.0));
+        assert_eq!(11.12, text_width("éé", 10.0));
+    }
+}
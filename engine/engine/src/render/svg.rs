@@ -0,0 +1,177 @@
+//! Writing of [Drawing]s as SVG documents. The output only depends on the
+//! drawing, so it is stable across platforms and suitable for snapshots.
+
+use crate::render::{Color, Drawing, Shape, format_number};
+
+use std::fmt::Write;
+
+const FONT_FAMILY: &str = "sans-serif";
+
+fn color(Color(r, g, b): Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn write_shape(svg: &mut String, shape: &Shape) {
+    let result = match shape {
+        Shape::Line { x1, y1, x2, y2, width, color: c } =>
+            writeln!(svg, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" \
+                stroke=\"{}\" stroke-width=\"{}\" \
+                stroke-linecap=\"square\"/>", format_number(*x1),
+                format_number(*y1), format_number(*x2), format_number(*y2),
+                color(*c), format_number(*width)),
+        Shape::Rect { x, y, width, height, fill, stroke } => {
+            let fill = fill.map(color).unwrap_or_else(|| "none".to_owned());
+            let stroke = match stroke {
+                Some((c, w)) => format!(" stroke=\"{}\" stroke-width=\"{}\"",
+                    color(*c), format_number(*w)),
+                None => String::new()
+            };
+            writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" \
+                height=\"{}\" fill=\"{}\"{}/>", format_number(*x),
+                format_number(*y), format_number(*width),
+                format_number(*height), fill, stroke)
+        },
+        Shape::Text { x, y, content, size, bold, color: c } => {
+            let weight = if *bold { "bold" } else { "normal" };
+            writeln!(svg, "<text x=\"{}\" y=\"{}\" font-family=\"{}\" \
+                font-size=\"{}\" font-weight=\"{}\" fill=\"{}\" \
+                text-anchor=\"middle\" dominant-baseline=\"central\">{}\
+                </text>", format_number(*x), format_number(*y), FONT_FAMILY,
+                format_number(*size), weight, color(*c), escape(content))
+        }
+    };
+
+    result.unwrap();
+}
+
+/// Writes the given drawing as a standalone SVG document.
+pub fn to_svg(drawing: &Drawing) -> String {
+    let width = format_number(drawing.width);
+    let height = format_number(drawing.height);
+    let mut svg = String::new();
+
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" \
+        height=\"{}\" viewBox=\"0 0 {} {}\">", width, height, width, height)
+        .unwrap();
+
+    for shape in &drawing.shapes {
+        write_shape(&mut svg, shape);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::constraint::{AnyConstraint, SandwichClues};
+    use crate::line_format;
+    use crate::render::{Annotations, RenderOptions, render_svg};
+
+    use sudoku_variants::Sudoku;
+
+    fn sudoku(constraint: AnyConstraint) -> Sudoku<AnyConstraint> {
+        let (grid, _) = line_format::parse("1......2.......4").unwrap()
+            .into_raw_parts();
+        Sudoku::new_with_grid(grid, constraint)
+    }
+
+    fn sandwich() -> AnyConstraint {
+        let clues = SandwichClues {
+            columns: vec![Some(5), None, Some(0), None],
+            rows: vec![None, Some(2), None, Some(3)]
+        };
+        AnyConstraint::Sandwich(clues.into_constraint())
+    }
+
+    fn assert_snapshot(expected: &str, sudoku: &Sudoku<AnyConstraint>,
+            annotations: &Annotations) {
+        let svg = render_svg(sudoku, annotations, &RenderOptions::default());
+        assert_eq!(expected, svg);
+    }
+
+    #[test]
+    fn default_snapshot() {
+        assert_snapshot(include_str!("../../fixtures/svg/default.svg"),
+            &sudoku(AnyConstraint::Default), &Annotations::default());
+    }
+
+    #[test]
+    fn diagonals_snapshot() {
+        assert_snapshot(include_str!("../../fixtures/svg/diagonals.svg"),
+            &sudoku(AnyConstraint::Diagonals), &Annotations::default());
+    }
+
+    #[test]
+    fn knights_move_snapshot() {
+        assert_snapshot(include_str!("../../fixtures/svg/knights-move.svg"),
+            &sudoku(AnyConstraint::KnightsMove), &Annotations::default());
+    }
+
+    #[test]
+    fn kings_move_snapshot() {
+        assert_snapshot(include_str!("../../fixtures/svg/kings-move.svg"),
+            &sudoku(AnyConstraint::KingsMove), &Annotations::default());
+    }
+
+    #[test]
+    fn sandwich_snapshot() {
+        assert_snapshot(include_str!("../../fixtures/svg/sandwich.svg"),
+            &sudoku(sandwich()), &Annotations::default());
+    }
+
+    #[test]
+    fn composite_snapshot() {
+        let constraint = AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            sandwich()
+        ]);
+
+        assert_snapshot(include_str!("../../fixtures/svg/composite.svg"),
+            &sudoku(constraint), &Annotations::default());
+    }
+
+    #[test]
+    fn annotations_snapshot() {
+        let mut digits = vec![None; 16];
+        digits[5] = Some(3);
+        let mut pencilmarks = vec![Vec::new(); 16];
+        pencilmarks[1] = vec![3, 4];
+        pencilmarks[6] = vec![1, 2, 3, 4];
+        let annotations = Annotations {
+            digits,
+            pencilmarks
+        };
+
+        assert_snapshot(include_str!("../../fixtures/svg/annotations.svg"),
+            &sudoku(AnyConstraint::Default), &annotations);
+    }
+
+    #[test]
+    fn escaped_text() {
+        let drawing = Drawing {
+            width: 10.0,
+            height: 10.0,
+            shapes: vec![Shape::Text {
+                x: 5.0,
+                y: 5.0,
+                content: "<a & b>".to_owned(),
+                size: 1.0,
+                bold: false,
+                color: Color::BLACK
+            }]
+        };
+
+        assert!(to_svg(&drawing).contains(">&lt;a &amp; b&gt;</text>"));
+    }
+}
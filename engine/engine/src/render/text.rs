@@ -0,0 +1,518 @@
+//! Rendering of Sudoku as Unicode text with box-drawing characters, e.g. for
+//! the command line, test failure messages, and bug reports. Digits from 10
+//! upwards are written as letters, as in the line format. Digits entered by
+//! a player are followed by `'` to distinguish them from the givens.
+//!
+//! Blocks are enclosed by double lines. Sandwich sums are written above and
+//! left of the grid, and cells on the diagonals of a diagonals Sudoku are
+//! marked with `╲`, `╱`, or `╳` (for both) in their top left corner.
+//!
+//! ```text
+//!      3         12              0
+//!    ╔═════════╦═════════╦═════════╗
+//!  7 ║╲5  3  4'║ .  7  . ║ .  . ╱. ║
+//!    ║ 6 ╲.  . ║ 1  9  5 ║ . ╱.  . ║
+//! 35 ║ .  9 ╲8 ║ .  .  . ║╱.  6  . ║
+//!    ╠═════════╬═════════╬═════════╣
+//! ```
+//!
+//! The example above is a diagonals Sudoku with sandwich clues, in which the
+//! player entered a 4 in the third cell.
+//!
+//! In the candidates view, every empty cell shows its pencilmarks in a
+//! mini-grid, which is 3x3 for 9x9 Sudoku, and cells are separated by single
+//! lines.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+use crate::line_format;
+use crate::render::Annotations;
+
+use serde::Deserialize;
+
+use std::iter;
+
+use sudoku_variants::Sudoku;
+use sudoku_variants::constraint::Constraint;
+
+const MAIN_DIAGONAL: char = '╲';
+const ANTI_DIAGONAL: char = '╱';
+const BOTH_DIAGONALS: char = '╳';
+
+/// Written after digits entered by a player.
+const ENTERED_MARKER: char = '\'';
+
+/// The width of a cell outside the candidates view, which consists of the
+/// diagonal marker, the digit, and a space or [ENTERED_MARKER].
+const CELL_WIDTH: usize = 3;
+
+/// Options that control the text rendering. To be deserialized from the
+/// input, where all fields are optional.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TextOptions {
+
+    /// Whether to show the pencilmarks of every empty cell in a mini-grid.
+    /// If the annotations contain no pencilmarks at all, all digits that do
+    /// not violate the constraint are shown instead, see
+    /// [candidate_annotations]. Defaults to `false`.
+    pub candidates: bool
+}
+
+#[derive(Clone, Copy)]
+enum Line {
+    Single,
+    Double
+}
+
+impl Line {
+    fn horizontal(self) -> char {
+        match self {
+            Line::Single => '─',
+            Line::Double => '═'
+        }
+    }
+
+    fn vertical(self) -> char {
+        match self {
+            Line::Single => '│',
+            Line::Double => '║'
+        }
+    }
+}
+
+/// The position of a line relative to the grid, which determines the shape
+/// of the junctions on it.
+#[derive(Clone, Copy)]
+enum Edge {
+    Start = 0,
+    Middle = 1,
+    End = 2
+}
+
+impl Edge {
+    fn of(index: usize, size: usize) -> Edge {
+        if index == 0 {
+            Edge::Start
+        }
+        else if index == size {
+            Edge::End
+        }
+        else {
+            Edge::Middle
+        }
+    }
+}
+
+/// Gets the character where a vertical and a horizontal line meet.
+fn junction(vertical: Line, horizontal: Line, row: Edge, column: Edge)
+        -> char {
+    let rows = match (vertical, horizontal) {
+        (Line::Single, Line::Single) => ["┌┬┐", "├┼┤", "└┴┘"],
+        (Line::Single, Line::Double) => ["╒╤╕", "╞╪╡", "╘╧╛"],
+        (Line::Double, Line::Single) => ["╓╥╖", "╟╫╢", "╙╨╜"],
+        (Line::Double, Line::Double) => ["╔╦╗", "╠╬╣", "╚╩╝"]
+    };
+
+    rows[row as usize].chars().nth(column as usize).unwrap()
+}
+
+/// The arrangement of the cells and lines of the text.
+struct Layout {
+    block_width: usize,
+    block_height: usize,
+    size: usize,
+
+    /// Whether cells within a block are separated by single lines.
+    cell_lines: bool,
+    cell_width: usize,
+    cell_height: usize
+}
+
+impl Layout {
+
+    /// Gets the line left of the given column, which may be `size` for the
+    /// right edge of the grid.
+    fn vertical(&self, column: usize) -> Option<Line> {
+        if column % self.block_width == 0 {
+            Some(Line::Double)
+        }
+        else if self.cell_lines {
+            Some(Line::Single)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Gets the line above the given row, which may be `size` for the bottom
+    /// edge of the grid.
+    fn horizontal(&self, row: usize) -> Option<Line> {
+        if row % self.block_height == 0 {
+            Some(Line::Double)
+        }
+        else if self.cell_lines {
+            Some(Line::Single)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Gets the text of the line above the given row, if there is one.
+    fn border(&self, row: usize) -> Option<String> {
+        let horizontal = self.horizontal(row)?;
+        let mut text = String::new();
+
+        for column in 0..=self.size {
+            if let Some(vertical) = self.vertical(column) {
+                text.push(junction(vertical, horizontal,
+                    Edge::of(row, self.size), Edge::of(column, self.size)));
+            }
+
+            if column < self.size {
+                text.extend(iter::repeat_n(horizontal.horizontal(),
+                    self.cell_width));
+            }
+        }
+
+        Some(text)
+    }
+
+    /// Gets the text of one line through all columns, where `content`
+    /// provides the text of each cell and vertical lines are drawn only if
+    /// `lines` is set, and replaced by spaces otherwise.
+    fn cells<F>(&self, lines: bool, content: F) -> String
+    where
+        F: Fn(usize) -> String
+    {
+        let mut text = String::new();
+
+        for column in 0..=self.size {
+            if let Some(vertical) = self.vertical(column) {
+                text.push(if lines { vertical.vertical() } else { ' ' });
+            }
+
+            if column < self.size {
+                text.push_str(&content(column));
+            }
+        }
+
+        text
+    }
+}
+
+/// Gets the number of columns and rows of the mini-grid of pencilmarks in
+/// the candidates view.
+fn mini_grid(size: usize) -> (usize, usize) {
+    let columns = (1..=size).find(|c| c * c >= size).unwrap_or(1);
+    (columns, size.div_ceil(columns))
+}
+
+fn has_diagonals(constraint: &AnyConstraint) -> bool {
+    match constraint {
+        AnyConstraint::Diagonals => true,
+        AnyConstraint::Composite(cs) => cs.iter().any(has_diagonals),
+        _ => false
+    }
+}
+
+/// Gets the clues of the first sandwich constraint in the given constraint,
+/// if there is one.
+fn sandwich_clues(constraint: &AnyConstraint) -> Option<SandwichClues> {
+    match constraint {
+        AnyConstraint::Sandwich(c) => Some(SandwichClues::of(c)),
+        AnyConstraint::Composite(cs) => cs.iter().find_map(sandwich_clues),
+        _ => None
+    }
+}
+
+fn clue_text(clue: Option<&Option<usize>>) -> String {
+    match clue {
+        Some(Some(clue)) => clue.to_string(),
+        _ => String::new()
+    }
+}
+
+/// Centers the given text in the given width, rounding to the right.
+fn center(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    let left = width.saturating_sub(length).div_ceil(2);
+    let right = width.saturating_sub(length + left);
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+fn diagonal_marker(column: usize, row: usize, size: usize) -> char {
+    match (column == row, column + row + 1 == size) {
+        (true, true) => BOTH_DIAGONALS,
+        (true, false) => MAIN_DIAGONAL,
+        (false, true) => ANTI_DIAGONAL,
+        (false, false) => ' '
+    }
+}
+
+/// Gets annotations that contain, for every empty cell of the given Sudoku,
+/// all digits that can be entered without violating the constraint given the
+/// current digits. These can be passed to [render_text] to show the
+/// candidates of a puzzle.
+pub fn candidate_annotations(sudoku: &Sudoku<AnyConstraint>) -> Annotations {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let pencilmarks = (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .map(|(column, row)| match grid.get_cell(column, row).unwrap() {
+            Some(_) => Vec::new(),
+            None => (1..=size)
+                .filter(|&digit| sudoku.constraint()
+                    .check_number(grid, column, row, digit))
+                .collect()
+        })
+        .collect();
+
+    Annotations {
+        digits: Vec::new(),
+        pencilmarks
+    }
+}
+
+/// Renders the given Sudoku as Unicode text, together with the digits and
+/// pencilmarks entered by a player. Every line, including the last one, is
+/// terminated by a line break.
+///
+/// # Arguments
+///
+/// * `sudoku`: The Sudoku to render.
+/// * `annotations`: The player's digits and pencilmarks. Use
+/// `Annotations::default()` to render the puzzle only. Digits are marked
+/// with a trailing `'`, and pencilmarks are only shown in the candidates
+/// view.
+/// * `options`: Options that control the rendering.
+///
+/// # Panics
+///
+/// If the Sudoku is larger than 35x35, since larger digits have no character.
+pub fn render_text(sudoku: &Sudoku<AnyConstraint>, annotations: &Annotations,
+        options: &TextOptions) -> String {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let (mark_columns, mark_rows) = mini_grid(size);
+    let layout = if options.candidates {
+        Layout {
+            block_width: grid.block_width(),
+            block_height: grid.block_height(),
+            size,
+            cell_lines: true,
+            cell_width: 2 * mark_columns + 1,
+            cell_height: mark_rows
+        }
+    }
+    else {
+        Layout {
+            block_width: grid.block_width(),
+            block_height: grid.block_height(),
+            size,
+            cell_lines: false,
+            cell_width: CELL_WIDTH,
+            cell_height: 1
+        }
+    };
+    let computed;
+    let annotations = if options.candidates &&
+            annotations.pencilmarks.is_empty() {
+        computed = Annotations {
+            digits: annotations.digits.clone(),
+            ..candidate_annotations(sudoku)
+        };
+        &computed
+    }
+    else {
+        annotations
+    };
+    let diagonals = has_diagonals(sudoku.constraint());
+    let sandwich = sandwich_clues(sudoku.constraint());
+    let margin = sandwich.as_ref()
+        .map(|clues| (0..size)
+            .map(|row| clue_text(clues.rows.get(row)).len() + 1)
+            .max()
+            .unwrap_or(1))
+        .unwrap_or(0);
+
+    // Writes the given line of a cell, i.e. the marker, the digit, or a row
+    // of pencilmarks.
+
+    let cell = |column: usize, row: usize, line: usize| {
+        let index = row * size + column;
+        let mut text = vec![' '; layout.cell_width];
+        let given = grid.get_cell(column, row).unwrap();
+        let entered = match given {
+            Some(_) => None,
+            None => annotations.digit(index)
+        };
+
+        match given.or(entered) {
+            Some(digit) if line == layout.cell_height / 2 => {
+                let middle = layout.cell_width / 2;
+                text[middle] = line_format::to_char(Some(digit));
+
+                if entered.is_some() {
+                    text[middle + 1] = ENTERED_MARKER;
+                }
+            },
+            Some(_) => { },
+            None if options.candidates => {
+                for &mark in annotations.pencilmarks(index) {
+                    if (1..=size).contains(&mark) &&
+                            (mark - 1) / mark_columns == line {
+                        text[1 + 2 * ((mark - 1) % mark_columns)] =
+                            line_format::to_char(Some(mark));
+                    }
+                }
+            },
+            None => text[layout.cell_width / 2] = line_format::to_char(None)
+        }
+
+        if diagonals && line == 0 {
+            text[0] = diagonal_marker(column, row, size);
+        }
+
+        text.into_iter().collect::<String>()
+    };
+    let mut lines = Vec::new();
+
+    if let Some(clues) = &sandwich {
+        let columns = layout.cells(false, |column|
+            center(&clue_text(clues.columns.get(column)), layout.cell_width));
+        lines.push(format!("{}{}", " ".repeat(margin), columns));
+    }
+
+    for row in 0..=size {
+        if let Some(border) = layout.border(row) {
+            lines.push(format!("{}{}", " ".repeat(margin), border));
+        }
+
+        if row == size {
+            break;
+        }
+
+        for line in 0..layout.cell_height {
+            let prefix = match &sandwich {
+                Some(clues) if line == layout.cell_height / 2 =>
+                    format!("{:>width$} ", clue_text(clues.rows.get(row)),
+                        width = margin - 1),
+                _ => " ".repeat(margin)
+            };
+            let cells = layout.cells(true, |column| cell(column, row, line));
+            lines.push(format!("{}{}", prefix, cells));
+        }
+    }
+
+    lines.iter()
+        .map(|line| format!("{}\n", line.trim_end()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::sudoku;
+
+    const CLASSIC: &str = "53..7....6..195....98....6.8...6...34..8.3..17...\
+        2...6.6....28....419..5....8..79";
+
+    /// Creates annotations for a Sudoku of the given size in which the player
+    /// entered the given digits, each given as column, row, and digit.
+    fn entered(size: usize, digits: &[(usize, usize, usize)]) -> Annotations {
+        let mut annotations = Annotations {
+            digits: vec![None; size * size],
+            pencilmarks: Vec::new()
+        };
+
+        for &(column, row, digit) in digits {
+            annotations.digits[row * size + column] = Some(digit);
+        }
+
+        annotations
+    }
+
+    fn assert_lines(expected: &[&str], text: &str) {
+        assert_eq!(expected, text.lines().collect::<Vec<_>>().as_slice(),
+            "rendered text:\n{}", text);
+    }
+
+    #[test]
+    fn module_doc_example() {
+        let mut clues = SandwichClues {
+            columns: vec![None; 9],
+            rows: vec![None; 9]
+        };
+        clues.columns[0] = Some(3);
+        clues.columns[3] = Some(12);
+        clues.columns[8] = Some(0);
+        clues.rows[0] = Some(7);
+        clues.rows[2] = Some(35);
+        let (grid, _) = line_format::parse(CLASSIC).unwrap().into_raw_parts();
+        let sudoku = Sudoku::new_with_grid(grid,
+            AnyConstraint::Composite(vec![
+                AnyConstraint::Diagonals,
+                AnyConstraint::Sandwich(clues.into_constraint())
+            ]));
+        let text = render_text(&sudoku, &entered(9, &[(2, 0, 4)]),
+            &TextOptions::default());
+
+        assert_lines(&[
+            "     3         12              0",
+            "   ╔═════════╦═════════╦═════════╗",
+            " 7 ║╲5  3  4'║ .  7  . ║ .  . ╱. ║",
+            "   ║ 6 ╲.  . ║ 1  9  5 ║ . ╱.  . ║",
+            "35 ║ .  9 ╲8 ║ .  .  . ║╱.  6  . ║",
+            "   ╠═════════╬═════════╬═════════╣"
+        ], &text.lines().take(6).collect::<Vec<_>>().join("\n"));
+        assert_eq!(14, text.lines().count());
+    }
+
+    #[test]
+    fn candidates_view() {
+        let sudoku = sudoku(2, 2, AnyConstraint::Default);
+        let options = TextOptions {
+            candidates: true
+        };
+        let text = render_text(&sudoku, &entered(4, &[(1, 1, 4)]), &options);
+
+        assert_lines(&[
+            "╔═════╤═════╦═════╤═════╗",
+            "║     │     ║   2 │   2 ║",
+            "║  1  │ 3 4 ║   4 │     ║",
+            "╟─────┼─────╫─────┼─────╢",
+            "║   2 │     ║     │ 1 2 ║",
+            "║   4 │  4' ║  3  │     ║",
+            "╠═════╪═════╬═════╪═════╣",
+            "║     │     ║ 1   │     ║",
+            "║ 3   │  2  ║     │  4  ║",
+            "╟─────┼─────╫─────┼─────╢",
+            "║     │ 1   ║ 1 2 │ 1 2 ║",
+            "║ 3 4 │ 3 4 ║     │ 3   ║",
+            "╚═════╧═════╩═════╧═════╝"
+        ], &text);
+    }
+
+    #[test]
+    fn non_square_blocks() {
+        let sudoku = sudoku(3, 2, AnyConstraint::Default);
+        let text = render_text(&sudoku, &entered(6, &[(2, 5, 4)]),
+            &TextOptions::default());
+
+        assert_lines(&[
+            "╔═════════╦═════════╗",
+            "║ 1  .  . ║ .  .  . ║",
+            "║ .  .  . ║ 3  .  . ║",
+            "╠═════════╬═════════╣",
+            "║ .  .  . ║ .  .  . ║",
+            "║ .  2  . ║ .  .  . ║",
+            "╠═════════╬═════════╣",
+            "║ .  .  . ║ .  .  6 ║",
+            "║ .  .  4'║ .  .  . ║",
+            "╚═════════╩═════════╝"
+        ], &text);
+    }
+}
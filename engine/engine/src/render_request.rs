@@ -0,0 +1,49 @@
+use crate::constraint::AnyConstraint;
+use crate::render::{Annotations, RenderOptions, TextOptions};
+use crate::schema;
+
+use serde::Deserialize;
+
+use sudoku_variants::Sudoku;
+
+/// A request to draw a Sudoku, i.e. the argument to a `render_svg` call to
+/// the engine. To be deserialized from the input.
+#[derive(Deserialize)]
+pub(crate) struct RenderRequest {
+
+    /// The Sudoku to draw.
+    #[serde(deserialize_with = "schema::deserialize")]
+    pub(crate) sudoku: Sudoku<AnyConstraint>,
+
+    /// The digits and pencilmarks entered by the player. Defaults to none.
+    #[serde(default)]
+    pub(crate) annotations: Annotations,
+
+    /// Whether to fill all empty cells with the solution instead of the
+    /// player's digits. Has no effect if the Sudoku is not uniquely
+    /// solvable. Defaults to `false`.
+    #[serde(default)]
+    pub(crate) solution: bool,
+
+    /// Options that control the appearance of the drawing.
+    #[serde(default)]
+    pub(crate) options: RenderOptions
+}
+
+/// A request to render a Sudoku as text, i.e. the argument to a
+/// `render_text` call to the engine. To be deserialized from the input.
+#[derive(Deserialize)]
+pub(crate) struct TextRequest {
+
+    /// The Sudoku to render.
+    #[serde(deserialize_with = "schema::deserialize")]
+    pub(crate) sudoku: Sudoku<AnyConstraint>,
+
+    /// The digits and pencilmarks entered by the player. Defaults to none.
+    #[serde(default)]
+    pub(crate) annotations: Annotations,
+
+    /// Options that control the rendering.
+    #[serde(default)]
+    pub(crate) options: TextOptions
+}
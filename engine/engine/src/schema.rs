@@ -0,0 +1,420 @@
+//! Versioning of the JSON representation of Sudoku. Every Sudoku document
+//! produced by the engine carries a `schema_version` field next to its `grid`
+//! and `constraint`, which identifies the layout of the document. Documents
+//! of older versions, including those without a version which predate this
+//! field, are upgraded by a chain of migrations when they are read, so saved
+//! puzzles remain readable as the format evolves.
+//!
+//! To change the format, increment [SCHEMA_VERSION] and append a migration
+//! from the previous version to `MIGRATIONS`. A JSON Schema describing the
+//! current version is provided by [json_schema].
+//!
+//! # Versions
+//!
+//! * 0: The unversioned format, as derived by serde from
+//! `Sudoku<AnyConstraint>`.
+//! * 1: Identical to version 0 except for the `schema_version` field.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de;
+
+use serde_json::{Map, Value, json};
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::Sudoku;
+
+/// The version of the JSON representation of Sudoku written by this engine.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The name of the field that holds the schema version.
+pub const VERSION_FIELD: &str = "schema_version";
+
+const JSON_SCHEMA_DIALECT: &str =
+    "https://json-schema.org/draft/2020-12/schema";
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), SchemaError>;
+
+/// The migrations between consecutive versions, where the migration at index
+/// `i` upgrades a document from version `i` to version `i + 1`. It does not
+/// need to update the version field.
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    migrate_unversioned
+];
+
+/// The reasons why a Sudoku document cannot be read.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum SchemaError {
+
+    /// The input is not valid JSON. The message of the parser is provided.
+    #[serde(rename = "invalid-json")]
+    InvalidJson(String),
+
+    /// The document is not a JSON object.
+    #[serde(rename = "not-an-object")]
+    NotAnObject,
+
+    /// The `schema_version` field is not a non-negative integer.
+    #[serde(rename = "invalid-version")]
+    InvalidVersion,
+
+    /// The document was written by a newer version of the engine, which uses
+    /// a schema version that is not known to this one. The version is
+    /// provided.
+    #[serde(rename = "unsupported-version")]
+    UnsupportedVersion(u64),
+
+    /// The document, after migrating it to the current version, does not
+    /// describe a Sudoku. The message of the parser is provided.
+    #[serde(rename = "invalid-document")]
+    InvalidDocument(String)
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::InvalidJson(message) =>
+                write!(f, "invalid JSON: {}", message),
+            SchemaError::NotAnObject =>
+                write!(f, "Sudoku document is not an object"),
+            SchemaError::InvalidVersion =>
+                write!(f, "invalid schema version"),
+            SchemaError::UnsupportedVersion(version) =>
+                write!(f, "unsupported schema version {}", version),
+            SchemaError::InvalidDocument(message) =>
+                write!(f, "invalid Sudoku document: {}", message)
+        }
+    }
+}
+
+impl Error for SchemaError { }
+
+/// Documents without a version have the same layout as those of version 1,
+/// so there is nothing to change apart from the version itself.
+fn migrate_unversioned(_: &mut Map<String, Value>)
+        -> Result<(), SchemaError> {
+    Ok(())
+}
+
+/// Upgrades the given Sudoku document to the current [SCHEMA_VERSION]. A
+/// missing `schema_version` field is treated as version 0. Fields other than
+/// those of the Sudoku, such as generation metadata, are kept.
+///
+/// # Errors
+///
+/// If the document is not an object, its version is invalid, or it is newer
+/// than [SCHEMA_VERSION]. An appropriate [SchemaError] is returned.
+pub fn migrate(document: Value) -> Result<Value, SchemaError> {
+    let mut object = match document {
+        Value::Object(object) => object,
+        _ => return Err(SchemaError::NotAnObject)
+    };
+    let version = match object.get(VERSION_FIELD) {
+        Some(version) => version.as_u64().ok_or(SchemaError::InvalidVersion)?,
+        None => 0
+    };
+
+    if version > SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut object)?;
+    }
+
+    object.insert(VERSION_FIELD.to_owned(), SCHEMA_VERSION.into());
+    Ok(Value::Object(object))
+}
+
+/// Reads a Sudoku from a document of any supported version, upgrading it
+/// with [migrate] first.
+///
+/// # Errors
+///
+/// If the document cannot be migrated or does not describe a Sudoku. An
+/// appropriate [SchemaError] is returned.
+pub fn from_value(document: Value)
+        -> Result<Sudoku<AnyConstraint>, SchemaError> {
+    let mut document = migrate(document)?;
+
+    if let Value::Object(object) = &mut document {
+        object.remove(VERSION_FIELD);
+    }
+
+    serde_json::from_value(document)
+        .map_err(|e| SchemaError::InvalidDocument(e.to_string()))
+}
+
+/// Parses a Sudoku from the JSON code of a document of any supported version.
+/// See [from_value].
+///
+/// # Errors
+///
+/// If the input is not valid JSON or cannot be read by [from_value]. An
+/// appropriate [SchemaError] is returned.
+pub fn from_json(json: &str) -> Result<Sudoku<AnyConstraint>, SchemaError> {
+    let document = serde_json::from_str(json)
+        .map_err(|e| SchemaError::InvalidJson(e.to_string()))?;
+    from_value(document)
+}
+
+/// Converts the given Sudoku into a document of the current [SCHEMA_VERSION].
+pub fn to_value(sudoku: &Sudoku<AnyConstraint>) -> Value {
+    let mut document = serde_json::to_value(sudoku).unwrap();
+
+    if let Value::Object(object) = &mut document {
+        object.insert(VERSION_FIELD.to_owned(), SCHEMA_VERSION.into());
+    }
+
+    document
+}
+
+/// Converts the given Sudoku into the JSON code of a document of the current
+/// [SCHEMA_VERSION].
+pub fn to_json(sudoku: &Sudoku<AnyConstraint>) -> String {
+    serde_json::to_string(&to_value(sudoku)).unwrap()
+}
+
+/// Serializes a Sudoku as a document of the current [SCHEMA_VERSION]. To be
+/// used with `#[serde(serialize_with = "...")]` or `#[serde(with = "...")]`
+/// on fields of type `Sudoku<AnyConstraint>`.
+///
+/// # Errors
+///
+/// If the serializer fails.
+pub fn serialize<S>(sudoku: &Sudoku<AnyConstraint>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer
+{
+    to_value(sudoku).serialize(serializer)
+}
+
+/// Deserializes a Sudoku from a document of any supported version, upgrading
+/// it with [migrate]. To be used with `#[serde(deserialize_with = "...")]` or
+/// `#[serde(with = "...")]` on fields of type `Sudoku<AnyConstraint>`.
+///
+/// # Errors
+///
+/// If the deserializer fails or the document cannot be read by [from_value].
+pub fn deserialize<'de, D>(deserializer: D)
+    -> Result<Sudoku<AnyConstraint>, D::Error>
+where
+    D: Deserializer<'de>
+{
+    from_value(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+fn optional_integers(minimum: usize) -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": ["integer", "null"],
+            "minimum": minimum
+        }
+    })
+}
+
+/// Gets the name of the given constraint variant as written in the `type`
+/// field, as chosen by its serde representation.
+fn variant_name(constraint: &AnyConstraint) -> String {
+    let value = serde_json::to_value(constraint).unwrap();
+    value["type"].as_str().unwrap().to_owned()
+}
+
+fn variant_schema(constraint: &AnyConstraint) -> Value {
+    let name = variant_name(constraint);
+
+    // The exhaustive match ensures that new variants are added to the schema.
+
+    let value = match constraint {
+        AnyConstraint::Default
+            | AnyConstraint::Diagonals
+            | AnyConstraint::KnightsMove
+            | AnyConstraint::KingsMove => None,
+        AnyConstraint::Sandwich(_) => Some(json!({
+            "$ref": "#/$defs/sandwich"
+        })),
+        AnyConstraint::Composite(_) => Some(json!({
+            "type": "array",
+            "items": {
+                "$ref": "#/$defs/constraint"
+            }
+        }))
+    };
+
+    match value {
+        Some(value) => json!({
+            "type": "object",
+            "required": ["type", "value"],
+            "properties": {
+                "type": {
+                    "const": name
+                },
+                "value": value
+            }
+        }),
+        None => json!({
+            "type": "object",
+            "required": ["type"],
+            "properties": {
+                "type": {
+                    "const": name
+                }
+            }
+        })
+    }
+}
+
+/// Generates a JSON Schema (draft 2020-12) describing Sudoku documents of the
+/// current [SCHEMA_VERSION]. The names of the constraint variants are taken
+/// from their serde representation, so the schema stays in sync with the
+/// types.
+pub fn json_schema() -> Value {
+    let sandwich = SandwichClues {
+        columns: Vec::new(),
+        rows: Vec::new()
+    };
+    let constraints = [
+        AnyConstraint::Default,
+        AnyConstraint::Diagonals,
+        AnyConstraint::KnightsMove,
+        AnyConstraint::KingsMove,
+        AnyConstraint::Sandwich(sandwich.into_constraint()),
+        AnyConstraint::Composite(Vec::new())
+    ];
+    let variants = constraints.iter()
+        .map(variant_schema)
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": "Sudoku",
+        "description": "A Sudoku with its grid and constraint, as read and \
+            written by the engine.",
+        "type": "object",
+        "required": [VERSION_FIELD, "grid", "constraint"],
+        "properties": {
+            (VERSION_FIELD): {
+                "const": SCHEMA_VERSION
+            },
+            "grid": {
+                "$ref": "#/$defs/grid"
+            },
+            "constraint": {
+                "$ref": "#/$defs/constraint"
+            }
+        },
+        "$defs": {
+            "grid": {
+                "type": "object",
+                "required": ["block_width", "block_height", "cells"],
+                "properties": {
+                    "block_width": {
+                        "type": "integer",
+                        "minimum": 1
+                    },
+                    "block_height": {
+                        "type": "integer",
+                        "minimum": 1
+                    },
+                    "cells": optional_integers(1)
+                }
+            },
+            "constraint": {
+                "oneOf": variants
+            },
+            "sandwich": {
+                "type": "object",
+                "required": ["columns", "rows"],
+                "properties": {
+                    "columns": optional_integers(0),
+                    "rows": optional_integers(0)
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::{assert_same_sudoku, sandwich, sudoku};
+
+    const PUBLISHED_SCHEMA: &str =
+        include_str!("../schema/sudoku.schema.json");
+
+    fn document(version: Value) -> String {
+        let mut document = to_value(&sudoku(3, 3, sandwich(9)));
+        document[VERSION_FIELD] = version;
+        document.to_string()
+    }
+
+    #[test]
+    fn migrate_adds_version_to_unversioned_document() {
+        let mut document =
+            serde_json::to_value(sudoku(3, 3, sandwich(9))).unwrap();
+        document["difficulty"] = json!(3);
+        let mut expected = document.clone();
+        expected[VERSION_FIELD] = json!(SCHEMA_VERSION);
+
+        assert!(document.get(VERSION_FIELD).is_none());
+        assert_eq!(expected, migrate(document).unwrap());
+    }
+
+    #[test]
+    fn migrate_keeps_current_document() {
+        let document = to_value(&sudoku(3, 3, sandwich(9)));
+
+        assert_eq!(document.clone(), migrate(document).unwrap());
+    }
+
+    #[test]
+    fn from_json_rejects_future_version() {
+        let version = SCHEMA_VERSION + 1;
+
+        assert_eq!(Err(SchemaError::UnsupportedVersion(version)),
+            from_json(&document(json!(version))).map(|_| ()));
+    }
+
+    #[test]
+    fn from_json_rejects_malformed_input() {
+        assert!(matches!(from_json("{\"grid\":"),
+            Err(SchemaError::InvalidJson(_))));
+        assert_eq!(Err(SchemaError::NotAnObject),
+            from_json("[1, 2, 3]").map(|_| ()));
+        assert_eq!(Err(SchemaError::InvalidVersion),
+            from_json(&document(json!(-1))).map(|_| ()));
+        assert_eq!(Err(SchemaError::InvalidVersion),
+            from_json(&document(json!("1"))).map(|_| ()));
+        assert!(matches!(from_json("{\"schema_version\": 1, \"grid\": 3}"),
+            Err(SchemaError::InvalidDocument(_))));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let sudoku = sudoku(3, 3, AnyConstraint::Composite(vec![
+            AnyConstraint::Diagonals,
+            sandwich(9)
+        ]));
+        let unversioned = serde_json::to_string(&sudoku).unwrap();
+
+        assert_same_sudoku(&sudoku, &from_json(&to_json(&sudoku)).unwrap());
+        assert_same_sudoku(&sudoku, &from_json(&unversioned).unwrap());
+    }
+
+    #[test]
+    fn published_schema_is_up_to_date() {
+        let published =
+            serde_json::from_str::<Value>(PUBLISHED_SCHEMA).unwrap();
+
+        assert_eq!(published, json_schema(),
+            "regenerate schema/sudoku.schema.json with the schema binary");
+    }
+}
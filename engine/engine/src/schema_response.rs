@@ -0,0 +1,34 @@
+use crate::schema::SchemaError;
+
+use serde::Serialize;
+
+use serde_json::Value;
+
+/// The response to a `migrate` call to the engine. To be serialized for
+/// return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum SchemaResponse {
+
+    /// Indicates that the document was upgraded successfully. The document of
+    /// the current schema version is provided.
+    #[serde(rename = "ok")]
+    Ok(Value),
+
+    /// Indicates that the document could not be upgraded. The reason is
+    /// provided.
+    #[serde(rename = "error")]
+    Error(SchemaError)
+}
+
+impl SchemaResponse {
+
+    /// Wraps the result of migrating a document in a schema response.
+    pub(crate) fn from_result(result: Result<Value, SchemaError>)
+            -> SchemaResponse {
+        match result {
+            Ok(document) => SchemaResponse::Ok(document),
+            Err(e) => SchemaResponse::Error(e)
+        }
+    }
+}
@@ -0,0 +1,622 @@
+//! Compact, URL-safe share codes for Sudoku with any [AnyConstraint]. A share
+//! code is the base64url encoding (without padding) of the following bytes.
+//!
+//! * A version byte, currently [VERSION].
+//! * A bit-packed payload, most significant bit first, padded with zeros to
+//! full bytes:
+//!     * the block width and height minus one, 4 bits each,
+//!     * the constraint as a tree of 3-bit tags, where a composite
+//! constraint is followed by its number of components (8 bits) and the
+//! components, and a sandwich constraint by its column and row clues, each
+//! as a presence bit and, if present, the sum,
+//!     * one bit per cell in row-major order indicating whether it is filled,
+//!     * the digit minus one of every filled cell.
+//! * The CRC-32 of all preceding bytes, big-endian.
+//!
+//! A classic Sudoku with 25 givens thus takes 39 characters, compared to
+//! several hundred for its JSON representation.
+//!
+//! Consequently, blocks can be at most 16 cells wide and high, composite
+//! constraints can have at most 255 components, and they can be nested at
+//! most [MAX_NESTING] levels deep. Sudoku beyond these limits cannot be
+//! encoded, and codes beyond them are rejected when decoding.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+
+use serde::Serialize;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+
+/// The version of the encoding, stored in the first byte of every share code.
+pub const VERSION: u8 = 1;
+
+const BASE64URL: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const CHECKSUM_LEN: usize = 4;
+const DIMENSION_BITS: usize = 4;
+const TAG_BITS: usize = 3;
+const COMPONENT_COUNT_BITS: usize = 8;
+
+/// The maximum number of composite constraints nested in each other in a
+/// share code. This bounds the recursion when decoding untrusted codes.
+pub const MAX_NESTING: usize = 8;
+
+/// The tags identifying the variants of [AnyConstraint] in a share code.
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum Tag {
+    Default = 0,
+    Diagonals = 1,
+    KnightsMove = 2,
+    KingsMove = 3,
+    Sandwich = 4,
+    Composite = 5
+}
+
+impl Tag {
+    fn from_bits(bits: usize) -> Option<Tag> {
+        match bits {
+            0 => Some(Tag::Default),
+            1 => Some(Tag::Diagonals),
+            2 => Some(Tag::KnightsMove),
+            3 => Some(Tag::KingsMove),
+            4 => Some(Tag::Sandwich),
+            5 => Some(Tag::Composite),
+            _ => None
+        }
+    }
+}
+
+/// The reasons why a Sudoku cannot be encoded as a share code, or a share
+/// code cannot be decoded.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum ShareCodeError {
+
+    /// The code contains a character that is not part of the base64url
+    /// alphabet, or its length is impossible for base64url. The 0-based
+    /// position of the first offending character is provided.
+    #[serde(rename = "invalid-character")]
+    InvalidCharacter(usize),
+
+    /// The code is too short to contain a version and checksum.
+    #[serde(rename = "too-short")]
+    TooShort,
+
+    /// The checksum does not match the content, i.e. the code was mistyped
+    /// or truncated.
+    #[serde(rename = "checksum-mismatch")]
+    ChecksumMismatch,
+
+    /// The code was created by an unknown version of the encoding. The
+    /// version is provided.
+    #[serde(rename = "unsupported-version")]
+    UnsupportedVersion(u8),
+
+    /// The checksum matches, but the content does not describe a valid
+    /// Sudoku.
+    #[serde(rename = "invalid-content")]
+    InvalidContent,
+
+    /// The blocks of the Sudoku to encode are wider or higher than 16 cells.
+    /// Their width and height are provided.
+    #[serde(rename = "blocks-too-large")]
+    BlocksTooLarge(usize, usize),
+
+    /// A composite constraint of the Sudoku to encode has more than 255
+    /// components. Their number is provided.
+    #[serde(rename = "too-many-components")]
+    TooManyComponents(usize),
+
+    /// The composite constraints of the Sudoku to encode are nested deeper
+    /// than [MAX_NESTING].
+    #[serde(rename = "nesting-too-deep")]
+    NestingTooDeep
+}
+
+impl Display for ShareCodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareCodeError::InvalidCharacter(position) =>
+                write!(f, "invalid character at position {}", position),
+            ShareCodeError::TooShort => write!(f, "share code too short"),
+            ShareCodeError::ChecksumMismatch =>
+                write!(f, "checksum mismatch"),
+            ShareCodeError::UnsupportedVersion(version) =>
+                write!(f, "unsupported share code version {}", version),
+            ShareCodeError::InvalidContent =>
+                write!(f, "share code does not describe a valid Sudoku"),
+            ShareCodeError::BlocksTooLarge(width, height) =>
+                write!(f, "blocks too large for a share code: {}x{}", width,
+                    height),
+            ShareCodeError::TooManyComponents(count) =>
+                write!(f, "too many components for a share code: {}", count),
+            ShareCodeError::NestingTooDeep =>
+                write!(f, "constraint nested too deeply for a share code")
+        }
+    }
+}
+
+impl Error for ShareCodeError { }
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of the given bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+
+    !crc
+}
+
+fn encode_base64url(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity((bytes.len() * 4 + 2) / 3);
+
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | ((b as u32) << (16 - 8 * i)));
+        let chars = chunk.len() + 1;
+
+        for i in 0..chars {
+            let index = (value >> (18 - 6 * i)) & 0x3f;
+            result.push(BASE64URL[index as usize] as char);
+        }
+    }
+
+    result
+}
+
+fn decode_base64url(code: &str) -> Result<Vec<u8>, ShareCodeError> {
+    let values = code.bytes().enumerate()
+        .map(|(position, c)| BASE64URL.iter()
+            .position(|&b| b == c)
+            .map(|v| v as u32)
+            .ok_or(ShareCodeError::InvalidCharacter(position)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() % 4 == 1 {
+        return Err(ShareCodeError::InvalidCharacter(values.len() - 1));
+    }
+
+    let mut bytes = Vec::with_capacity(values.len() * 3 / 4);
+
+    for chunk in values.chunks(4) {
+        let value = chunk.iter().enumerate()
+            .fold(0u32, |acc, (i, &v)| acc | (v << (18 - 6 * i)));
+
+        for i in 0..(chunk.len() - 1) {
+            bytes.push((value >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// The number of bits required to store values from 0 to `max`.
+fn bits_for(max: usize) -> usize {
+    (usize::BITS - max.leading_zeros()) as usize
+}
+
+/// The largest possible sandwich sum in a Sudoku of the given size.
+fn max_sandwich_sum(size: usize) -> usize {
+    size * (size + 1) / 2
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize
+}
+
+impl BitWriter {
+    fn write(&mut self, value: usize, bits: usize) {
+        for bit in (0..bits).rev() {
+            if self.bits % 8 == 0 {
+                self.bytes.push(0);
+            }
+
+            if (value >> bit) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+            }
+
+            self.bits += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: usize) -> Result<usize, ShareCodeError> {
+        let mut value = 0;
+
+        for _ in 0..bits {
+            let byte = self.bytes.get(self.bits / 8)
+                .ok_or(ShareCodeError::InvalidContent)?;
+            let bit = (byte >> (7 - self.bits % 8)) & 1;
+            value = (value << 1) | bit as usize;
+            self.bits += 1;
+        }
+
+        Ok(value)
+    }
+
+    /// Indicates whether only the zero padding of the last byte is left.
+    fn at_end(&self) -> bool {
+        let remaining = self.bytes.len() * 8 - self.bits;
+        remaining < 8 && (self.bits % 8 == 0 ||
+            self.bytes[self.bits / 8] & (0xff >> (self.bits % 8)) == 0)
+    }
+}
+
+/// Writes the given constraint, which is nested in the given number of
+/// composite constraints, to the given writer.
+fn write_constraint(writer: &mut BitWriter, constraint: &AnyConstraint,
+        size: usize, nesting: usize) -> Result<(), ShareCodeError> {
+    match constraint {
+        AnyConstraint::Default => writer.write(Tag::Default as usize, TAG_BITS),
+        AnyConstraint::Diagonals =>
+            writer.write(Tag::Diagonals as usize, TAG_BITS),
+        AnyConstraint::KnightsMove =>
+            writer.write(Tag::KnightsMove as usize, TAG_BITS),
+        AnyConstraint::KingsMove =>
+            writer.write(Tag::KingsMove as usize, TAG_BITS),
+        AnyConstraint::Sandwich(c) => {
+            writer.write(Tag::Sandwich as usize, TAG_BITS);
+            let clues = SandwichClues::of(c);
+            let sum_bits = bits_for(max_sandwich_sum(size));
+
+            for clue in clues.columns.iter().chain(clues.rows.iter()) {
+                match clue {
+                    Some(sum) => {
+                        writer.write(1, 1);
+                        writer.write(*sum, sum_bits);
+                    },
+                    None => writer.write(0, 1)
+                }
+            }
+        },
+        AnyConstraint::Composite(cs) => {
+            if nesting >= MAX_NESTING {
+                return Err(ShareCodeError::NestingTooDeep);
+            }
+
+            if cs.len() >= 1 << COMPONENT_COUNT_BITS {
+                return Err(ShareCodeError::TooManyComponents(cs.len()));
+            }
+
+            writer.write(Tag::Composite as usize, TAG_BITS);
+            writer.write(cs.len(), COMPONENT_COUNT_BITS);
+
+            for c in cs {
+                write_constraint(writer, c, size, nesting + 1)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_clues(reader: &mut BitReader, size: usize)
+        -> Result<Vec<Option<usize>>, ShareCodeError> {
+    let sum_bits = bits_for(max_sandwich_sum(size));
+
+    (0..size)
+        .map(|_| match reader.read(1)? {
+            1 => reader.read(sum_bits).map(Some),
+            _ => Ok(None)
+        })
+        .collect()
+}
+
+/// Reads a constraint, which is nested in the given number of composite
+/// constraints, from the given reader.
+fn read_constraint(reader: &mut BitReader, size: usize, nesting: usize)
+        -> Result<AnyConstraint, ShareCodeError> {
+    let tag = Tag::from_bits(reader.read(TAG_BITS)?)
+        .ok_or(ShareCodeError::InvalidContent)?;
+
+    match tag {
+        Tag::Default => Ok(AnyConstraint::Default),
+        Tag::Diagonals => Ok(AnyConstraint::Diagonals),
+        Tag::KnightsMove => Ok(AnyConstraint::KnightsMove),
+        Tag::KingsMove => Ok(AnyConstraint::KingsMove),
+        Tag::Sandwich => {
+            let columns = read_clues(reader, size)?;
+            let rows = read_clues(reader, size)?;
+            let clues = SandwichClues {
+                columns,
+                rows
+            };
+            Ok(AnyConstraint::Sandwich(clues.into_constraint()))
+        },
+        Tag::Composite => {
+            if nesting >= MAX_NESTING {
+                return Err(ShareCodeError::InvalidContent);
+            }
+
+            let count = reader.read(COMPONENT_COUNT_BITS)?;
+            let constraints = (0..count)
+                .map(|_| read_constraint(reader, size, nesting + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(AnyConstraint::Composite(constraints))
+        }
+    }
+}
+
+/// Encodes the given Sudoku as a share code.
+///
+/// # Errors
+///
+/// If the blocks of the Sudoku are wider or higher than 16 cells, a composite
+/// constraint has more than 255 components, or composite constraints are
+/// nested deeper than [MAX_NESTING]. An appropriate [ShareCodeError] is
+/// returned.
+pub fn encode(sudoku: &Sudoku<AnyConstraint>)
+        -> Result<String, ShareCodeError> {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+
+    if block_width > 1 << DIMENSION_BITS ||
+            block_height > 1 << DIMENSION_BITS {
+        return Err(ShareCodeError::BlocksTooLarge(block_width, block_height));
+    }
+
+    let mut writer = BitWriter {
+        bytes: vec![VERSION],
+        bits: 8
+    };
+    writer.write(block_width - 1, DIMENSION_BITS);
+    writer.write(block_height - 1, DIMENSION_BITS);
+    write_constraint(&mut writer, sudoku.constraint(), size, 0)?;
+
+    let cells = (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .map(|(column, row)| grid.get_cell(column, row).unwrap())
+        .collect::<Vec<_>>();
+
+    for cell in &cells {
+        writer.write(cell.is_some() as usize, 1);
+    }
+
+    let digit_bits = bits_for(size - 1);
+
+    for digit in cells.into_iter().flatten() {
+        writer.write(digit - 1, digit_bits);
+    }
+
+    let mut bytes = writer.bytes;
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_be_bytes());
+    Ok(encode_base64url(&bytes))
+}
+
+/// Decodes a Sudoku from the given share code. Surrounding whitespace is
+/// ignored.
+///
+/// # Errors
+///
+/// If the code is not valid base64url, its checksum does not match, it was
+/// created by an unsupported version of the encoding, or its content does not
+/// describe a Sudoku.
+pub fn decode(code: &str) -> Result<Sudoku<AnyConstraint>, ShareCodeError> {
+    let bytes = decode_base64url(code.trim())?;
+
+    if bytes.len() <= CHECKSUM_LEN {
+        return Err(ShareCodeError::TooShort);
+    }
+
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    let checksum = u32::from_be_bytes(checksum.try_into().unwrap());
+
+    if crc32(content) != checksum {
+        return Err(ShareCodeError::ChecksumMismatch);
+    }
+
+    if content[0] != VERSION {
+        return Err(ShareCodeError::UnsupportedVersion(content[0]));
+    }
+
+    let mut reader = BitReader {
+        bytes: content,
+        bits: 8
+    };
+    let block_width = reader.read(DIMENSION_BITS)? + 1;
+    let block_height = reader.read(DIMENSION_BITS)? + 1;
+    let mut grid = SudokuGrid::new(block_width, block_height)
+        .map_err(|_| ShareCodeError::InvalidContent)?;
+    let size = grid.size();
+    let constraint = read_constraint(&mut reader, size, 0)?;
+    let filled = (0..size * size)
+        .map(|_| reader.read(1).map(|bit| bit == 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    let digit_bits = bits_for(size - 1);
+
+    for index in (0..size * size).filter(|&index| filled[index]) {
+        let digit = reader.read(digit_bits)? + 1;
+        grid.set_cell(index % size, index / size, digit)
+            .map_err(|_| ShareCodeError::InvalidContent)?;
+    }
+
+    if !reader.at_end() {
+        return Err(ShareCodeError::InvalidContent);
+    }
+
+    Ok(Sudoku::new_with_grid(grid, constraint))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::{assert_same_sudoku, sandwich, sudoku};
+
+    /// Creates the given number of composite constraints nested in each
+    /// other, the innermost one containing the default constraint.
+    fn nested(depth: usize) -> AnyConstraint {
+        (0..depth).fold(AnyConstraint::Default,
+            |c, _| AnyConstraint::Composite(vec![c]))
+    }
+
+    fn assert_round_trip(sudoku: Sudoku<AnyConstraint>) {
+        let decoded = decode(&encode(&sudoku).unwrap()).unwrap();
+
+        assert_same_sudoku(&sudoku, &decoded);
+    }
+
+    #[test]
+    fn round_trip_constraints() {
+        let constraints = vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            AnyConstraint::KnightsMove,
+            AnyConstraint::KingsMove,
+            sandwich(9),
+            AnyConstraint::Composite(vec![
+                AnyConstraint::Default,
+                AnyConstraint::Diagonals,
+                AnyConstraint::KnightsMove,
+                AnyConstraint::KingsMove,
+                sandwich(9)
+            ]),
+            nested(MAX_NESTING)
+        ];
+
+        for constraint in constraints {
+            assert_round_trip(sudoku(3, 3, constraint));
+        }
+    }
+
+    #[test]
+    fn round_trip_dimensions() {
+        assert_round_trip(sudoku(2, 2, AnyConstraint::Default));
+        assert_round_trip(sudoku(3, 2, sandwich(6)));
+        assert_round_trip(sudoku(2, 3, AnyConstraint::KingsMove));
+        assert_round_trip(sudoku(4, 4, sandwich(16)));
+    }
+
+    #[test]
+    fn round_trip_largest_encodable_sum() {
+        let mut clues = SandwichClues {
+            columns: vec![None; 9],
+            rows: vec![None; 9]
+        };
+        clues.rows[8] = Some(max_sandwich_sum(9));
+        let constraint = AnyConstraint::Sandwich(clues.into_constraint());
+
+        assert_round_trip(sudoku(3, 3, constraint));
+    }
+
+    #[test]
+    fn whitespace_is_ignored() {
+        let sudoku = sudoku(3, 3, AnyConstraint::Default);
+        let code = format!("  {}\n", encode(&sudoku).unwrap());
+
+        assert!(decode(&code).is_ok());
+    }
+
+    #[test]
+    fn corrupted_character() {
+        let code = encode(&sudoku(3, 3, sandwich(9))).unwrap();
+
+        // The last character may only differ in its padding bits, which are
+        // not covered by the checksum.
+
+        for position in 0..code.len() - 1 {
+            let mut corrupted = code.clone().into_bytes();
+            corrupted[position] =
+                if corrupted[position] == b'A' { b'B' } else { b'A' };
+            let corrupted = String::from_utf8(corrupted).unwrap();
+
+            assert_eq!(Err(ShareCodeError::ChecksumMismatch),
+                decode(&corrupted).map(|_| ()));
+        }
+    }
+
+    #[test]
+    fn corrupted_length() {
+        let code = encode(&sudoku(3, 3, AnyConstraint::Default)).unwrap();
+
+        for length in 0..code.len() {
+            assert!(decode(&code[..length]).is_err());
+        }
+
+        assert!(decode(&format!("{}A", code)).is_err());
+    }
+
+    #[test]
+    fn invalid_character() {
+        let code = encode(&sudoku(3, 3, AnyConstraint::Default)).unwrap();
+        let invalid = format!("{}+{}", &code[..5], &code[6..]);
+
+        assert_eq!(Err(ShareCodeError::InvalidCharacter(5)),
+            decode(&invalid).map(|_| ()));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut bytes = vec![VERSION + 1, 0x22, 0];
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        assert_eq!(Err(ShareCodeError::UnsupportedVersion(VERSION + 1)),
+            decode(&encode_base64url(&bytes)).map(|_| ()));
+    }
+
+    #[test]
+    fn deeply_nested_code() {
+        let mut writer = BitWriter {
+            bytes: vec![VERSION],
+            bits: 8
+        };
+        writer.write(2, DIMENSION_BITS);
+        writer.write(2, DIMENSION_BITS);
+
+        for _ in 0..100_000 {
+            writer.write(Tag::Composite as usize, TAG_BITS);
+            writer.write(1, COMPONENT_COUNT_BITS);
+        }
+
+        writer.write(Tag::Default as usize, TAG_BITS);
+
+        for _ in 0..81 {
+            writer.write(0, 1);
+        }
+
+        let mut bytes = writer.bytes;
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        assert_eq!(Err(ShareCodeError::InvalidContent),
+            decode(&encode_base64url(&bytes)).map(|_| ()));
+    }
+
+    #[test]
+    fn unencodable() {
+        let grid = SudokuGrid::new(17, 1).unwrap();
+        let wide = Sudoku::new_with_grid(grid, AnyConstraint::Default);
+        let components = AnyConstraint::Composite(
+            vec![AnyConstraint::Default; 1 << COMPONENT_COUNT_BITS]);
+
+        assert_eq!(Err(ShareCodeError::BlocksTooLarge(17, 1)), encode(&wide));
+        assert_eq!(Err(ShareCodeError::TooManyComponents(256)),
+            encode(&sudoku(3, 3, components)));
+        assert_eq!(Err(ShareCodeError::NestingTooDeep),
+            encode(&sudoku(3, 3, nested(MAX_NESTING + 1))));
+    }
+}
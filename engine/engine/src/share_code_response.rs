@@ -0,0 +1,32 @@
+use crate::share_code::ShareCodeError;
+
+use serde::Serialize;
+
+/// The response to a `share_code` or `from_share_code` call to the engine. To
+/// be serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum ShareCodeResponse<T> {
+
+    /// Indicates that the conversion was successful. The share code or the
+    /// decoded Sudoku is provided.
+    #[serde(rename = "ok")]
+    Ok(T),
+
+    /// Indicates that the conversion failed. The reason is provided.
+    #[serde(rename = "error")]
+    Error(ShareCodeError)
+}
+
+impl<T> ShareCodeResponse<T> {
+
+    /// Wraps the result of encoding or decoding a share code in a share code
+    /// response.
+    pub(crate) fn from_result(result: Result<T, ShareCodeError>)
+            -> ShareCodeResponse<T> {
+        match result {
+            Ok(value) => ShareCodeResponse::Ok(value),
+            Err(e) => ShareCodeResponse::Error(e)
+        }
+    }
+}
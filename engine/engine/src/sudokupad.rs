@@ -0,0 +1,918 @@
+//! Conversion between Sudoku and the puzzle strings of
+//! [SudokuPad](https://sudokupad.app), which appear in share links such as
+//! `https://sudokupad.app/fpuzzlesN4IgzglgXgpiBcBOANCALhNAb...`. A puzzle
+//! string consists of a format prefix followed by the LZ-string compressed,
+//! base64-encoded puzzle.
+//!
+//! The `fpuzzles` format, which wraps the f-puzzles JSON format (see
+//! [crate::fpuzzles]), is supported for import and used for export by
+//! [encode] and [link]. SudokuPad's native `scl` format is supported for
+//! import and used for export by [encode_scl] and [link_scl]. Its payload is
+//! JSON, which SudokuPad may minify by leaving keys unquoted and abbreviating
+//! them, e.g. `ce` for `cells`. Such keys are expanded before import, while
+//! export always writes the full form. The `ctc` format is recognized, but
+//! rejected with [SudokuPadError::UnsupportedFormat].
+//!
+//! An `scl` puzzle is an object whose `cells` are given as a list of rows,
+//! each cell being an object with the `value` of its given, if any. The
+//! `regions` list the cells of every block, each as row and column. All other
+//! coordinates are given in cell units, row first, with the top left corner
+//! of the grid at `[0, 0]`. Both diagonals are `lines` whose `wayPoints` run
+//! from corner to corner, sandwich clues are `overlays` or `underlays` whose
+//! numeric `text` is centred just outside the top or left edge of the
+//! grid, and the anti-knight and anti-king constraints are metadata `cages`
+//! without cells whose `value` is `antiknight: true` or `antiking: true`.
+//! Importing an `scl` puzzle that uses anything else fails with
+//! [SudokuPadError::Unsupported], listing the features, so that no constraint
+//! is silently dropped.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+use crate::fpuzzles::{self, FPuzzlesError};
+use crate::lz_string;
+
+use serde::Serialize;
+
+use serde_json::{json, Map, Value};
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+
+/// The URL to which a puzzle string can be appended to open it in SudokuPad.
+pub const SUDOKUPAD_URL: &str = "https://sudokupad.app/";
+
+const FPUZZLES_PREFIX: &str = "fpuzzles";
+const SCL_PREFIX: &str = "scl";
+
+/// The prefixes of SudokuPad formats which are not supported.
+const UNSUPPORTED_PREFIXES: [&str; 1] = ["ctc"];
+
+/// The entries of an `scl` puzzle that are converted.
+const SCL_SUPPORTED: [&str; 6] =
+    ["cells", "regions", "lines", "overlays", "underlays", "cages"];
+
+/// The entries of an `scl` puzzle that do not affect the puzzle itself and
+/// are thus ignored.
+const SCL_IGNORED: [&str; 2] = ["id", "cellSize"];
+
+/// The abbreviations of keys in minified `scl` puzzles, each given together
+/// with the full key.
+const SCL_ABBREVIATIONS: [(&str, &str); 19] = [
+    ("ar", "arrows"),
+    ("bc", "backgroundColor"),
+    ("c", "color"),
+    ("ca", "cages"),
+    ("ce", "cells"),
+    ("cs", "cellSize"),
+    ("ct", "center"),
+    ("fs", "fontSize"),
+    ("h", "height"),
+    ("li", "lines"),
+    ("ov", "overlays"),
+    ("re", "regions"),
+    ("ro", "rounded"),
+    ("te", "text"),
+    ("th", "thickness"),
+    ("un", "underlays"),
+    ("v", "value"),
+    ("w", "width"),
+    ("wp", "wayPoints")
+];
+
+/// The metadata key of the anti-knight constraint in `scl` puzzles.
+const ANTIKNIGHT: &str = "antiknight";
+
+/// The metadata key of the anti-king constraint in `scl` puzzles.
+const ANTIKING: &str = "antiking";
+
+/// The feature reported for regions which are not the blocks of the grid.
+const IRREGULAR_REGIONS: &str = "regions";
+
+/// The maximum distance by which SudokuPad may inset the ends of a diagonal
+/// line from the corners of the grid, in cell units.
+const DIAGONAL_INSET: f64 = 0.5;
+
+/// The maximum distance of a waypoint from a diagonal for it to be considered
+/// on it, in cell units.
+const EPSILON: f64 = 1e-6;
+
+/// The color of exported diagonal lines.
+const DIAGONAL_COLOR: &str = "#A0A0A0";
+
+/// The thickness of exported diagonal lines in pixels.
+const DIAGONAL_THICKNESS: f64 = 2.0;
+
+/// The cell size of exported `scl` puzzles in pixels.
+const CELL_SIZE: usize = 50;
+
+/// The reasons why a SudokuPad puzzle string cannot be converted.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum SudokuPadError {
+
+    /// The string does not start with a known format prefix.
+    #[serde(rename = "unknown-format")]
+    UnknownFormat,
+
+    /// The string uses a SudokuPad format that is not supported. Its prefix
+    /// is provided.
+    #[serde(rename = "unsupported-format")]
+    UnsupportedFormat(String),
+
+    /// The compressed data is invalid, e.g. because the string was truncated.
+    #[serde(rename = "corrupted")]
+    Corrupted,
+
+    /// The decompressed data of an `scl` puzzle string does not describe a
+    /// SudokuPad puzzle. A description of the problem is provided.
+    #[serde(rename = "invalid-scl")]
+    InvalidScl(String),
+
+    /// The `scl` puzzle uses features that are not supported. Their SudokuPad
+    /// names are provided, with `regions` denoting irregular regions.
+    #[serde(rename = "unsupported")]
+    Unsupported(Vec<String>),
+
+    /// The decompressed puzzle could not be converted from or to the
+    /// f-puzzles format. The reason is provided.
+    #[serde(rename = "fpuzzles")]
+    FPuzzles(FPuzzlesError)
+}
+
+impl Display for SudokuPadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SudokuPadError::UnknownFormat =>
+                write!(f, "not a SudokuPad puzzle string"),
+            SudokuPadError::UnsupportedFormat(prefix) =>
+                write!(f, "unsupported SudokuPad format '{}'", prefix),
+            SudokuPadError::Corrupted =>
+                write!(f, "corrupted puzzle data"),
+            SudokuPadError::InvalidScl(message) =>
+                write!(f, "invalid SudokuPad puzzle: {}", message),
+            SudokuPadError::Unsupported(features) =>
+                write!(f, "unsupported features: {}", features.join(", ")),
+            SudokuPadError::FPuzzles(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl Error for SudokuPadError { }
+
+impl From<FPuzzlesError> for SudokuPadError {
+    fn from(e: FPuzzlesError) -> SudokuPadError {
+        SudokuPadError::FPuzzles(e)
+    }
+}
+
+/// Undoes the percent-encoding of the base64 characters that are not safe in
+/// URLs, which occurs when links are copied from some applications.
+fn percent_decode(s: &str) -> String {
+    s.replace("%2B", "+").replace("%2b", "+")
+        .replace("%2F", "/").replace("%2f", "/")
+        .replace("%3D", "=").replace("%3d", "=")
+}
+
+/// Extracts the puzzle string from the given input, which may be a full share
+/// link (with the puzzle either in the path or in a `puzzleid` parameter) or
+/// the bare puzzle string.
+fn puzzle_string(input: &str) -> String {
+    let input = input.trim();
+    let puzzle = match input.find("puzzleid=") {
+        Some(index) => {
+            let rest = &input[index + "puzzleid=".len()..];
+            rest.split('&').next().unwrap()
+        },
+        None => {
+            let path = input.split(|c| c == '?' || c == '#').next().unwrap();
+            path.rsplit('/').next().unwrap()
+        }
+    };
+
+    percent_decode(puzzle)
+}
+
+/// Gets the list stored under the given key of an `scl` puzzle, which is
+/// empty if the key is missing or `null`.
+fn scl_list<'a>(puzzle: &'a Map<String, Value>, key: &str)
+        -> Result<&'a [Value], SudokuPadError> {
+    match puzzle.get(key) {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(items)) => Ok(items.as_slice()),
+        Some(_) =>
+            Err(SudokuPadError::InvalidScl(format!("{} is not a list", key)))
+    }
+}
+
+/// Parses a cell of an `scl` region, given as row and column.
+fn scl_cell(value: &Value) -> Option<(usize, usize)> {
+    match value.as_array()?.as_slice() {
+        [row, column] =>
+            Some((row.as_u64()? as usize, column.as_u64()? as usize)),
+        _ => None
+    }
+}
+
+/// Parses a point of an `scl` puzzle, such as a waypoint of a line, given as
+/// row and column in cell units.
+fn scl_point(value: &Value) -> Option<(f64, f64)> {
+    match value.as_array()?.as_slice() {
+        [row, column] => Some((row.as_f64()?, column.as_f64()?)),
+        _ => None
+    }
+}
+
+/// Indicates whether the given cells, given as row and column, are exactly
+/// one block of the given dimensions.
+fn is_block(cells: &[(usize, usize)], block_width: usize,
+        block_height: usize) -> bool {
+    let (top, left) = match cells.iter().min() {
+        Some(&top_left) => top_left,
+        None => return false
+    };
+    let distinct = cells.iter().collect::<HashSet<_>>().len();
+
+    top % block_height == 0 && left % block_width == 0 &&
+        distinct == cells.len() &&
+        distinct == block_width * block_height &&
+        cells.iter().all(|&(row, column)|
+            row < top + block_height &&
+                (left..left + block_width).contains(&column))
+}
+
+/// Determines the block dimensions of an `scl` puzzle of the given size from
+/// its regions. Puzzles without regions use the same default dimensions as
+/// f-puzzles. If the regions are not the blocks of any dimensions, they are
+/// reported as unsupported.
+fn scl_block_dimensions(puzzle: &Map<String, Value>, size: usize,
+        unsupported: &mut Vec<String>)
+        -> Result<(usize, usize), SudokuPadError> {
+    let regions = scl_list(puzzle, "regions")?;
+
+    if regions.is_empty() {
+        return Ok(fpuzzles::default_dimensions(size));
+    }
+
+    let regions = regions.iter()
+        .map(|region| region.as_array()?.iter()
+            .map(scl_cell)
+            .collect::<Option<Vec<_>>>())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(||
+            SudokuPadError::InvalidScl("malformed regions".to_owned()))?;
+    let block_origins = regions.iter()
+        .filter_map(|region| region.iter().min())
+        .collect::<HashSet<_>>();
+    let dimensions = regions.iter()
+        .find(|region| region.contains(&(0, 0)))
+        .map(|region| {
+            let rows = region.iter()
+                .map(|&(row, _)| row)
+                .collect::<HashSet<_>>();
+            let columns = region.iter()
+                .map(|&(_, column)| column)
+                .collect::<HashSet<_>>();
+            (columns.len(), rows.len())
+        })
+        .filter(|&(block_width, block_height)|
+            block_width * block_height == size &&
+                regions.len() == size &&
+                block_origins.len() == size &&
+                regions.iter().all(|region|
+                    is_block(region, block_width, block_height)));
+
+    match dimensions {
+        Some(dimensions) => Ok(dimensions),
+        None => {
+            unsupported.push(IRREGULAR_REGIONS.to_owned());
+            Ok(fpuzzles::default_dimensions(size))
+        }
+    }
+}
+
+/// Reads the grid of an `scl` puzzle, including its givens. Cell features
+/// other than givens, such as pencil marks, are reported as unsupported.
+fn parse_scl_grid(puzzle: &Map<String, Value>,
+        unsupported: &mut Vec<String>) -> Result<SudokuGrid, SudokuPadError> {
+    let rows = scl_list(puzzle, "cells")?;
+
+    if rows.is_empty() {
+        return Err(SudokuPadError::InvalidScl("missing cells".to_owned()));
+    }
+
+    let size = rows.len();
+    let (block_width, block_height) =
+        scl_block_dimensions(puzzle, size, unsupported)?;
+    let mut grid = SudokuGrid::new(block_width, block_height)
+        .map_err(|_| SudokuPadError::InvalidScl(
+            format!("unsupported size {}", size)))?;
+
+    for (row, cells) in rows.iter().enumerate() {
+        let cells = cells.as_array()
+            .filter(|cells| cells.len() == size)
+            .ok_or_else(|| SudokuPadError::InvalidScl(
+                "cells do not form a square".to_owned()))?;
+
+        for (column, cell) in cells.iter().enumerate() {
+            let cell = cell.as_object()
+                .ok_or_else(|| SudokuPadError::InvalidScl(
+                    "malformed cell".to_owned()))?;
+
+            for (key, value) in cell {
+                if key != "value" && fpuzzles::is_present(value) {
+                    unsupported.push(key.clone());
+                }
+            }
+
+            let value = match cell.get("value") {
+                Some(value) if fpuzzles::is_present(value) => value,
+                _ => continue
+            };
+            let invalid = || SudokuPadError::InvalidScl(format!(
+                "invalid given in row {}, column {}", row + 1, column + 1));
+            let digit = fpuzzles::parse_sum(value).ok_or_else(invalid)?;
+            grid.set_cell(column, row, digit).map_err(|_| invalid())?;
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Determines which diagonal of a grid of the given size the given `scl`
+/// line covers, if any, by its f-puzzles name. All waypoints must lie on the
+/// diagonal, and the line must run from one end of the grid to the other.
+fn scl_diagonal(line: &Value, size: usize) -> Option<&'static str> {
+    let points = line.get("wayPoints")?.as_array()?.iter()
+        .map(scl_point)
+        .collect::<Option<Vec<_>>>()?;
+    let size = size as f64;
+    let rows = points.iter().map(|&(row, _)| row);
+    let first = rows.clone().fold(f64::INFINITY, f64::min);
+    let last = rows.fold(f64::NEG_INFINITY, f64::max);
+
+    if first > DIAGONAL_INSET || last < size - DIAGONAL_INSET {
+        return None;
+    }
+
+    if points.iter().all(|&(row, column)| (row - column).abs() < EPSILON) {
+        Some("diagonal-")
+    }
+    else if points.iter()
+            .all(|&(row, column)| (row + column - size).abs() < EPSILON) {
+        Some("diagonal+")
+    }
+    else {
+        None
+    }
+}
+
+/// Reads the sandwich clues among the given `scl` overlays or underlays,
+/// which are stored under the given key, into the given clues. Any other
+/// overlay is reported as unsupported under that key.
+fn parse_scl_clues(items: &[Value], key: &str, clues: &mut SandwichClues,
+        unsupported: &mut Vec<String>) {
+    let size = clues.columns.len() as f64;
+
+    for item in items {
+        let value = item.get("text").and_then(fpuzzles::parse_sum);
+        let center = item.get("center").and_then(scl_point);
+
+        match (value, center) {
+            (Some(value), Some((row, column)))
+                    if row < 0.0 && (0.0..size).contains(&column) =>
+                clues.columns[column as usize] = Some(value),
+            (Some(value), Some((row, column)))
+                    if column < 0.0 && (0.0..size).contains(&row) =>
+                clues.rows[row as usize] = Some(value),
+            _ => unsupported.push(key.to_owned())
+        }
+    }
+}
+
+/// Reads the given `scl` cages, which may only be metadata cages without
+/// cells, and returns whether they enable the anti-knight and anti-king
+/// constraints, in this order. Other metadata, such as the title or rules, is
+/// ignored, and cages with cells are reported as unsupported.
+fn parse_scl_cages(cages: &[Value], unsupported: &mut Vec<String>)
+        -> (bool, bool) {
+    let mut antiknight = false;
+    let mut antiking = false;
+
+    for cage in cages {
+        let has_cells = cage.get("cells").is_some_and(fpuzzles::is_present);
+        let metadata = cage.get("value")
+            .and_then(Value::as_str)
+            .and_then(|value| value.split_once(':'));
+
+        match metadata {
+            Some((key, value)) if !has_cells => {
+                let enabled = value.trim() != "false";
+
+                match key.trim() {
+                    ANTIKNIGHT => antiknight |= enabled,
+                    ANTIKING => antiking |= enabled,
+                    _ => { }
+                }
+            },
+            _ => unsupported.push("cages".to_owned())
+        }
+    }
+
+    (antiknight, antiking)
+}
+
+/// Quotes all keys of the given minified `scl` code that are bare
+/// identifiers, such as `ce` in `{ce:[]}`, so it becomes valid JSON. Strings
+/// and literals such as `true` are left unchanged.
+fn quote_keys(code: &str) -> String {
+    let mut quoted = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            quoted.push(c);
+
+            if escaped {
+                escaped = false;
+            }
+            else if c == '\\' {
+                escaped = true;
+            }
+            else if c == '"' {
+                in_string = false;
+            }
+
+            continue;
+        }
+
+        if !(c.is_ascii_alphabetic() || c == '_' || c == '$') {
+            in_string = c == '"';
+            quoted.push(c);
+            continue;
+        }
+
+        let mut identifier = c.to_string();
+
+        while let Some(&c) = chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+                break;
+            }
+
+            identifier.push(c);
+            chars.next();
+        }
+
+        let rest = chars.clone().find(|c| !c.is_whitespace());
+
+        if rest == Some(':') {
+            quoted.push('"');
+            quoted.push_str(&identifier);
+            quoted.push('"');
+        }
+        else {
+            quoted.push_str(&identifier);
+        }
+    }
+
+    quoted
+}
+
+/// Replaces all abbreviated keys in the given `scl` value, including nested
+/// ones, by the full keys (see [SCL_ABBREVIATIONS]).
+fn expand_keys(value: Value) -> Value {
+    match value {
+        Value::Object(entries) => Value::Object(entries.into_iter()
+            .map(|(key, value)| {
+                let key = SCL_ABBREVIATIONS.iter()
+                    .find(|(abbreviation, _)| *abbreviation == key)
+                    .map_or(key, |(_, full)| (*full).to_owned());
+                (key, expand_keys(value))
+            })
+            .collect()),
+        Value::Array(items) =>
+            Value::Array(items.into_iter().map(expand_keys).collect()),
+        value => value
+    }
+}
+
+/// Parses the JSON code of an `scl` puzzle, which may be minified, into an
+/// object with the full keys.
+fn parse_scl(json: &str) -> Result<Map<String, Value>, SudokuPadError> {
+    let value = serde_json::from_str(json)
+        .or_else(|_| serde_json::from_str(&quote_keys(json)))
+        .map_err(|e| SudokuPadError::InvalidScl(e.to_string()))?;
+
+    match expand_keys(value) {
+        Value::Object(puzzle) => Ok(puzzle),
+        _ => Err(SudokuPadError::InvalidScl("not an object".to_owned()))
+    }
+}
+
+/// Imports a Sudoku from the JSON code of an `scl` puzzle, see the module
+/// documentation for the supported features. The constraint is the default
+/// constraint combined with all supported constraints used by the puzzle.
+fn import_scl(json: &str) -> Result<Sudoku<AnyConstraint>, SudokuPadError> {
+    let puzzle = parse_scl(json)?;
+    let mut unsupported = puzzle.iter()
+        .filter(|(key, value)| fpuzzles::is_present(value) &&
+            !SCL_SUPPORTED.contains(&key.as_str()) &&
+            !SCL_IGNORED.contains(&key.as_str()))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let grid = parse_scl_grid(&puzzle, &mut unsupported)?;
+    let size = grid.size();
+    let mut diagonals = HashSet::new();
+
+    for line in scl_list(&puzzle, "lines")? {
+        match scl_diagonal(line, size) {
+            Some(diagonal) => {
+                diagonals.insert(diagonal);
+            },
+            None => unsupported.push("lines".to_owned())
+        }
+    }
+
+    let mut clues = SandwichClues {
+        columns: vec![None; size],
+        rows: vec![None; size]
+    };
+
+    for key in ["overlays", "underlays"] {
+        parse_scl_clues(scl_list(&puzzle, key)?, key, &mut clues,
+            &mut unsupported);
+    }
+
+    let (antiknight, antiking) =
+        parse_scl_cages(scl_list(&puzzle, "cages")?, &mut unsupported);
+    let mut constraints = vec![AnyConstraint::Default];
+
+    // A single diagonal is not supported, so it is reported as a line.
+
+    match diagonals.len() {
+        0 => { },
+        1 => unsupported.push("lines".to_owned()),
+        _ => constraints.push(AnyConstraint::Diagonals)
+    }
+
+    if antiknight {
+        constraints.push(AnyConstraint::KnightsMove);
+    }
+
+    if antiking {
+        constraints.push(AnyConstraint::KingsMove);
+    }
+
+    if clues.columns.iter().chain(clues.rows.iter()).any(Option::is_some) {
+        constraints.push(AnyConstraint::Sandwich(clues.into_constraint()));
+    }
+
+    if !unsupported.is_empty() {
+        unsupported.sort();
+        unsupported.dedup();
+        return Err(SudokuPadError::Unsupported(unsupported));
+    }
+
+    let constraint = if constraints.len() == 1 {
+        AnyConstraint::Default
+    }
+    else {
+        AnyConstraint::Composite(constraints)
+    };
+
+    Ok(Sudoku::new_with_grid(grid, constraint))
+}
+
+/// Appends the given item to the list stored under the given key of an `scl`
+/// puzzle, creating the list if necessary.
+fn push_scl(puzzle: &mut Map<String, Value>, key: &str, item: Value) {
+    let list = puzzle.entry(key)
+        .or_insert_with(|| Value::Array(Vec::new()));
+
+    if let Value::Array(items) = list {
+        items.push(item);
+    }
+}
+
+/// Adds the `scl` entries of the given constraint, including those nested in
+/// composite constraints, to the given puzzle object.
+fn export_scl_constraint(constraint: &AnyConstraint, size: usize,
+        puzzle: &mut Map<String, Value>) {
+    match constraint {
+        AnyConstraint::Default => { },
+        AnyConstraint::Diagonals => {
+            let diagonals =
+                [[[0, 0], [size, size]], [[size, 0], [0, size]]];
+
+            for way_points in diagonals {
+                push_scl(puzzle, "lines", json!({
+                    "wayPoints": way_points,
+                    "color": DIAGONAL_COLOR,
+                    "thickness": DIAGONAL_THICKNESS
+                }));
+            }
+        },
+        AnyConstraint::KnightsMove => push_scl(puzzle, "cages", json!({
+            "value": format!("{}: true", ANTIKNIGHT)
+        })),
+        AnyConstraint::KingsMove => push_scl(puzzle, "cages", json!({
+            "value": format!("{}: true", ANTIKING)
+        })),
+        AnyConstraint::Sandwich(c) => {
+            let clues = SandwichClues::of(c);
+            let columns = clues.columns.iter().enumerate()
+                .map(|(column, &clue)| (-0.5, column as f64 + 0.5, clue));
+            let rows = clues.rows.iter().enumerate()
+                .map(|(row, &clue)| (row as f64 + 0.5, -0.5, clue));
+
+            for (row, column, clue) in columns.chain(rows) {
+                if let Some(clue) = clue {
+                    push_scl(puzzle, "overlays", json!({
+                        "center": [row, column],
+                        "width": 1,
+                        "height": 1,
+                        "text": clue.to_string()
+                    }));
+                }
+            }
+        },
+        AnyConstraint::Composite(cs) => {
+            for c in cs {
+                export_scl_constraint(c, size, puzzle);
+            }
+        }
+    }
+}
+
+/// Exports the given Sudoku to the JSON code of an `scl` puzzle. Filled cells
+/// are exported as givens, and every block is exported as a region.
+fn export_scl(sudoku: &Sudoku<AnyConstraint>) -> String {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+    let cells = (0..size)
+        .map(|row| (0..size)
+            .map(|column| match grid.get_cell(column, row).unwrap() {
+                Some(digit) => json!({ "value": digit }),
+                None => json!({})
+            })
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let regions = (0..size)
+        .map(|block| {
+            let top = block / (size / block_width) * block_height;
+            let left = block % (size / block_width) * block_width;
+
+            (top..top + block_height)
+                .flat_map(|row| (left..left + block_width)
+                    .map(move |column| [row, column]))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut puzzle = Map::new();
+    puzzle.insert("cellSize".to_owned(), Value::from(CELL_SIZE));
+    puzzle.insert("cells".to_owned(), json!(cells));
+    puzzle.insert("regions".to_owned(), json!(regions));
+    export_scl_constraint(sudoku.constraint(), size, &mut puzzle);
+
+    Value::Object(puzzle).to_string()
+}
+
+/// Decodes a Sudoku from a SudokuPad puzzle string or share link.
+///
+/// # Errors
+///
+/// If the string uses neither the `fpuzzles` nor the `scl` format, its data
+/// is corrupted, or the contained puzzle cannot be imported, e.g. because it
+/// uses unsupported features (see [fpuzzles::import] and the module
+/// documentation).
+pub fn decode(input: &str) -> Result<Sudoku<AnyConstraint>, SudokuPadError> {
+    let puzzle = puzzle_string(input);
+
+    if let Some(data) = puzzle.strip_prefix(FPUZZLES_PREFIX) {
+        let json = lz_string::decompress_from_base64(data)
+            .ok_or(SudokuPadError::Corrupted)?;
+        return Ok(fpuzzles::import(&json)?);
+    }
+
+    if let Some(data) = puzzle.strip_prefix(SCL_PREFIX) {
+        let json = lz_string::decompress_from_base64(data)
+            .ok_or(SudokuPadError::Corrupted)?;
+        return import_scl(&json);
+    }
+
+    match UNSUPPORTED_PREFIXES.iter().find(|p| puzzle.starts_with(*p)) {
+        Some(prefix) =>
+            Err(SudokuPadError::UnsupportedFormat((*prefix).to_owned())),
+        None => Err(SudokuPadError::UnknownFormat)
+    }
+}
+
+/// Encodes the given Sudoku as a SudokuPad puzzle string in the `fpuzzles`
+/// format. Appended to [SUDOKUPAD_URL], it opens the puzzle in SudokuPad.
+///
+/// # Errors
+///
+/// If the Sudoku cannot be exported to the f-puzzles format (see
+/// [fpuzzles::export]).
+pub fn encode(sudoku: &Sudoku<AnyConstraint>)
+        -> Result<String, SudokuPadError> {
+    let json = fpuzzles::export(sudoku)?;
+    Ok(format!("{}{}", FPUZZLES_PREFIX,
+        lz_string::compress_to_base64(&json)))
+}
+
+/// Creates a SudokuPad share link for the given Sudoku, i.e. [SUDOKUPAD_URL]
+/// followed by the puzzle string created by [encode].
+///
+/// # Errors
+///
+/// If the Sudoku cannot be exported to the f-puzzles format (see
+/// [fpuzzles::export]).
+pub fn link(sudoku: &Sudoku<AnyConstraint>) -> Result<String, SudokuPadError> {
+    encode(sudoku).map(|puzzle| format!("{}{}", SUDOKUPAD_URL, puzzle))
+}
+
+/// Encodes the given Sudoku as a SudokuPad puzzle string in the native `scl`
+/// format, see the module documentation. Appended to [SUDOKUPAD_URL], it opens
+/// the puzzle in SudokuPad. Unlike [encode], this supports Sudoku of any size.
+pub fn encode_scl(sudoku: &Sudoku<AnyConstraint>) -> String {
+    let json = export_scl(sudoku);
+    format!("{}{}", SCL_PREFIX, lz_string::compress_to_base64(&json))
+}
+
+/// Creates a SudokuPad share link for the given Sudoku in the native `scl`
+/// format, i.e. [SUDOKUPAD_URL] followed by the puzzle string created by
+/// [encode_scl].
+pub fn link_scl(sudoku: &Sudoku<AnyConstraint>) -> String {
+    format!("{}{}", SUDOKUPAD_URL, encode_scl(sudoku))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::{assert_same_sudoku, sandwich, sudoku};
+
+    /// A minified `scl` puzzle with givens, both diagonals, sandwich clues,
+    /// and the anti-king constraint.
+    const MINIFIED_SCL: &str =
+        include_str!("../fixtures/sudokupad/minified.scl");
+
+    fn composite(constraint: AnyConstraint) -> AnyConstraint {
+        AnyConstraint::Composite(vec![AnyConstraint::Default, constraint])
+    }
+
+    fn assert_round_trip(sudoku: Sudoku<AnyConstraint>) {
+        let decoded = decode(&link_scl(&sudoku)).unwrap();
+
+        assert_same_sudoku(&sudoku, &decoded);
+    }
+
+    fn scl(puzzle: Value) -> String {
+        format!("{}{}", SCL_PREFIX,
+            lz_string::compress_to_base64(&puzzle.to_string()))
+    }
+
+    #[test]
+    fn scl_classic() {
+        assert_round_trip(sudoku(3, 3, AnyConstraint::Default));
+    }
+
+    #[test]
+    fn scl_diagonals() {
+        assert_round_trip(sudoku(3, 3, composite(AnyConstraint::Diagonals)));
+    }
+
+    #[test]
+    fn scl_knights_move() {
+        assert_round_trip(sudoku(3, 3, composite(AnyConstraint::KnightsMove)));
+    }
+
+    #[test]
+    fn scl_kings_move() {
+        assert_round_trip(sudoku(3, 3, composite(AnyConstraint::KingsMove)));
+    }
+
+    #[test]
+    fn scl_sandwich() {
+        assert_round_trip(sudoku(3, 3, composite(sandwich(9))));
+    }
+
+    #[test]
+    fn scl_composite() {
+        let constraint = AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            AnyConstraint::KnightsMove,
+            AnyConstraint::KingsMove
+        ]);
+
+        assert_round_trip(sudoku(3, 3, constraint));
+    }
+
+    #[test]
+    fn scl_non_square_blocks() {
+        assert_round_trip(sudoku(3, 2, AnyConstraint::Default));
+        assert_round_trip(sudoku(2, 3, composite(AnyConstraint::KingsMove)));
+    }
+
+    #[test]
+    fn scl_without_regions() {
+        let cells = vec![vec![json!({})]; 4];
+        let sudoku = decode(&scl(json!({ "cells": cells }))).unwrap();
+
+        assert_eq!(2, sudoku.grid().block_width());
+        assert_eq!(2, sudoku.grid().block_height());
+    }
+
+    #[test]
+    fn scl_inset_diagonals() {
+        let cells = vec![vec![json!({})]; 9];
+        let puzzle = json!({
+            "cells": cells,
+            "lines": [
+                { "wayPoints": [[0.3, 0.3], [8.7, 8.7]] },
+                { "wayPoints": [[8.7, 0.3], [0.3, 8.7]] }
+            ]
+        });
+        let sudoku = decode(&scl(puzzle)).unwrap();
+
+        assert_eq!(serde_json::to_value(composite(AnyConstraint::Diagonals))
+            .unwrap(), serde_json::to_value(sudoku.constraint()).unwrap());
+    }
+
+    #[test]
+    fn scl_irregular_regions() {
+        let cells = vec![vec![json!({})]; 4];
+        let regions = json!([
+            [[0, 0], [0, 1], [0, 2], [0, 3]],
+            [[1, 0], [1, 1], [1, 2], [1, 3]],
+            [[2, 0], [2, 1], [2, 2], [2, 3]],
+            [[3, 0], [3, 1], [3, 2], [3, 3]]
+        ]);
+        let puzzle = json!({ "cells": cells, "regions": regions });
+
+        assert_eq!(Err(SudokuPadError::Unsupported(vec!["regions".to_owned()])),
+            decode(&scl(puzzle)).map(|_| ()));
+    }
+
+    #[test]
+    fn scl_unsupported_features() {
+        let cells = vec![vec![json!({})]; 9];
+        let puzzle = json!({
+            "cells": cells,
+            "lines": [{ "wayPoints": [[0.5, 0.5], [0.5, 4.5]] }],
+            "cages": [{ "cells": [[0, 0], [0, 1]], "value": "10" }],
+            "arrows": [{ "wayPoints": [[0.5, 0.5], [1.5, 1.5]] }]
+        });
+        let expected = vec!["arrows".to_owned(), "cages".to_owned(),
+            "lines".to_owned()];
+
+        assert_eq!(Err(SudokuPadError::Unsupported(expected)),
+            decode(&scl(puzzle)).map(|_| ()));
+    }
+
+    #[test]
+    fn scl_minified() {
+        let mut clues = SandwichClues {
+            columns: vec![None; 9],
+            rows: vec![None; 9]
+        };
+        clues.columns[2] = Some(12);
+        clues.rows[3] = Some(0);
+        let constraint = AnyConstraint::Composite(vec![
+            AnyConstraint::Default,
+            AnyConstraint::Diagonals,
+            AnyConstraint::KingsMove,
+            AnyConstraint::Sandwich(clues.into_constraint())
+        ]);
+        let puzzle = format!("{}{}", SCL_PREFIX,
+            lz_string::compress_to_base64(MINIFIED_SCL));
+        let sudoku = decode(&puzzle).unwrap();
+        let grid = sudoku.grid();
+
+        assert_eq!(Some(1), grid.get_cell(0, 0).unwrap());
+        assert_eq!(Some(5), grid.get_cell(4, 4).unwrap());
+        assert_eq!(Some(9), grid.get_cell(8, 7).unwrap());
+        assert_eq!(serde_json::to_value(constraint).unwrap(),
+            serde_json::to_value(sudoku.constraint()).unwrap());
+    }
+
+    #[test]
+    fn quote_keys_leaves_strings_and_literals() {
+        assert_eq!(r#"{"a":"b:c","d":[true,null],"e\"f":"x\"g:"}"#,
+            quote_keys(r#"{a:"b:c",d:[true,null],"e\"f":"x\"g:"}"#));
+    }
+
+    #[test]
+    fn ctc_is_unsupported() {
+        assert_eq!(Err(SudokuPadError::UnsupportedFormat("ctc".to_owned())),
+            decode("ctcN4IgzglgXgpiBcA").map(|_| ()));
+    }
+}
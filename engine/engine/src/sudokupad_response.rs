@@ -0,0 +1,31 @@
+use crate::sudokupad::SudokuPadError;
+
+use serde::Serialize;
+
+/// The response to an `import_sudokupad`, `export_sudokupad`, or
+/// `export_sudokupad_scl` call to the engine. To be serialized for return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum SudokuPadResponse<T> {
+
+    /// Indicates that the conversion was successful. The imported Sudoku or
+    /// the share link is provided.
+    #[serde(rename = "ok")]
+    Ok(T),
+
+    /// Indicates that the conversion failed. The reason is provided.
+    #[serde(rename = "error")]
+    Error(SudokuPadError)
+}
+
+impl<T> SudokuPadResponse<T> {
+
+    /// Wraps the result of a conversion in a SudokuPad response.
+    pub(crate) fn from_result(result: Result<T, SudokuPadError>)
+            -> SudokuPadResponse<T> {
+        match result {
+            Ok(value) => SudokuPadResponse::Ok(value),
+            Err(e) => SudokuPadResponse::Error(e)
+        }
+    }
+}
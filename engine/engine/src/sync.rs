@@ -1,31 +1,103 @@
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// The error returned by operations that stopped early because their
+/// [CancelHandle] was cancelled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Cancelled;
 
 /// A shared handle that allows signaling that an operation was canceled.
+/// Handles can form a hierarchy, where cancelling a parent also cancels all
+/// its children, but not vice versa. A handle may also have a deadline after
+/// which it counts as cancelled.
+///
+/// Cancellation is cooperative: long-running operations poll the handle and
+/// return [Cancelled] or give up on their own, so nothing needs to unwind.
 #[derive(Clone)]
-pub(crate) struct CancelHandle(Arc<RwLock<bool>>);
+pub(crate) struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    parent: Option<Box<CancelHandle>>
+}
 
 impl CancelHandle {
 
     /// Creates a new cancel handle. Initially, it is not cancelled.
     pub(crate) fn new() -> CancelHandle {
-        CancelHandle(Arc::new(RwLock::new(false)))
+        CancelHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            parent: None
+        }
+    }
+
+    /// Creates a new cancel handle which is cancelled automatically once the
+    /// given deadline has passed. Before that, it can be cancelled like any
+    /// other handle.
+    pub(crate) fn with_deadline(deadline: Instant) -> CancelHandle {
+        CancelHandle {
+            deadline: Some(deadline),
+            ..CancelHandle::new()
+        }
+    }
+
+    /// Creates a new cancel handle that is cancelled whenever this handle is
+    /// cancelled, but which can also be cancelled on its own without affecting
+    /// this handle. Initially, it is cancelled only if this handle is.
+    pub(crate) fn child(&self) -> CancelHandle {
+        CancelHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            parent: Some(Box::new(self.clone()))
+        }
     }
 
     /// Indicates whether the operation associated with this handle is
     /// cancelled.
     pub(crate) fn is_cancelled(&self) -> bool {
-        *self.0.read().unwrap()
+        self.cancelled.load(Ordering::Relaxed) ||
+            self.deadline.is_some_and(|deadline| Instant::now() >= deadline) ||
+            self.parent.as_ref().map_or(false, |p| p.is_cancelled())
     }
 
-    /// Panics if the operation associated with this handle is cancelled.
-    pub(crate) fn assert_not_cancelled(&self) {
+    /// Returns `Err(Cancelled)` if the operation associated with this handle
+    /// is cancelled, so callers can stop with the `?` operator.
+    pub(crate) fn check(&self) -> Result<(), Cancelled> {
         if self.is_cancelled() {
-            panic!("cancelled")
+            Err(Cancelled)
+        }
+        else {
+            Ok(())
         }
     }
 
     /// Cancels the operation associated with this handle.
     pub(crate) fn cancel(&self) {
-        *self.0.write().unwrap() = true;
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn deadline_cancels_handle_and_children() {
+        let expired = CancelHandle::with_deadline(Instant::now());
+        let pending = CancelHandle::with_deadline(
+            Instant::now() + Duration::from_secs(3600));
+
+        assert_eq!(Err(Cancelled), expired.check());
+        assert!(expired.child().is_cancelled());
+        assert!(!pending.is_cancelled());
+        assert!(!pending.child().is_cancelled());
+
+        pending.cancel();
+
+        assert!(pending.is_cancelled());
     }
 }
@@ -0,0 +1,43 @@
+//! Fixtures and assertions shared by the unit tests of several modules.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+
+/// Creates a Sudoku with the given block dimensions and constraint. It has
+/// four givens, including the digits 1 and the size, spread over different
+/// rows, columns, and blocks. The size must be at least 4.
+pub(crate) fn sudoku(block_width: usize, block_height: usize,
+        constraint: AnyConstraint) -> Sudoku<AnyConstraint> {
+    let mut grid = SudokuGrid::new(block_width, block_height).unwrap();
+    let size = grid.size();
+    grid.set_cell(0, 0, 1).unwrap();
+    grid.set_cell(1, size / 2, 2).unwrap();
+    grid.set_cell(size / 2, 1, 3).unwrap();
+    grid.set_cell(size - 1, size - 2, size).unwrap();
+    Sudoku::new_with_grid(grid, constraint)
+}
+
+/// Creates a sandwich constraint for Sudoku of the given size, which must be
+/// at least 4. The first column has the smallest possible clue 0, the last
+/// column the largest possible one, and the second row a clue in between.
+/// All other clues are missing.
+pub(crate) fn sandwich(size: usize) -> AnyConstraint {
+    let mut clues = SandwichClues {
+        columns: vec![None; size],
+        rows: vec![None; size]
+    };
+    clues.columns[0] = Some(0);
+    clues.columns[size - 1] = Some((2..size).sum());
+    clues.rows[1] = Some(size - 2);
+    AnyConstraint::Sandwich(clues.into_constraint())
+}
+
+/// Asserts that the given Sudoku have the same grid and constraint. They are
+/// compared by their serialized form, since constraints cannot be compared
+/// directly.
+pub(crate) fn assert_same_sudoku(expected: &Sudoku<AnyConstraint>,
+        actual: &Sudoku<AnyConstraint>) {
+    assert_eq!(serde_json::to_value(expected).unwrap(),
+        serde_json::to_value(actual).unwrap());
+}
@@ -0,0 +1,734 @@
+//! Transformations of Sudoku into equivalent ones, such as rotations or
+//! relabelling the digits. A transformation is only applied if it preserves
+//! the constraint of the Sudoku, in which case the transformed Sudoku has
+//! exactly the transformed solution and requires the same techniques. Clues
+//! of the constraint, such as sandwich sums, are moved along with the cells.
+
+use crate::constraint::{AnyConstraint, SandwichClues};
+use crate::generate::Relation;
+
+use serde::{Deserialize, Serialize};
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use sudoku_variants::{Sudoku, SudokuGrid};
+use sudoku_variants::constraint::SandwichConstraint;
+
+type Cell = (usize, usize);
+
+/// A transformation of a Sudoku. Bands are the horizontal rows of blocks and
+/// stacks the vertical columns of blocks. Permutations are given as the list
+/// of old indices in their new order, e.g. `[2, 0, 1]` moves the last band to
+/// the top.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum Transform {
+
+    /// Replaces every digit `d` by the entry at index `d - 1`, which must be a
+    /// permutation of all digits.
+    #[serde(rename = "relabel")]
+    Relabel(Vec<usize>),
+
+    /// Rotates the Sudoku clockwise by the given number of quarter turns. Odd
+    /// numbers require square blocks.
+    #[serde(rename = "rotate")]
+    Rotate(usize),
+
+    /// Mirrors the Sudoku at its vertical axis, i.e. swaps left and right.
+    #[serde(rename = "reflect-horizontally")]
+    ReflectHorizontally,
+
+    /// Mirrors the Sudoku at its horizontal axis, i.e. swaps top and bottom.
+    #[serde(rename = "reflect-vertically")]
+    ReflectVertically,
+
+    /// Mirrors the Sudoku at its main diagonal. Requires square blocks.
+    #[serde(rename = "transpose")]
+    Transpose,
+
+    /// Permutes the bands.
+    #[serde(rename = "permute-bands")]
+    PermuteBands(Vec<usize>),
+
+    /// Permutes the stacks.
+    #[serde(rename = "permute-stacks")]
+    PermuteStacks(Vec<usize>),
+
+    /// Permutes the rows within one band.
+    #[serde(rename = "permute-rows")]
+    PermuteRows {
+
+        /// The index of the band whose rows are permuted.
+        band: usize,
+
+        /// The permutation of the rows within the band.
+        order: Vec<usize>
+    },
+
+    /// Permutes the columns within one stack.
+    #[serde(rename = "permute-columns")]
+    PermuteColumns {
+
+        /// The index of the stack whose columns are permuted.
+        stack: usize,
+
+        /// The permutation of the columns within the stack.
+        order: Vec<usize>
+    },
+
+    /// Applies all wrapped transformations in the given order. Only the
+    /// combined transformation must preserve the constraint, so this allows
+    /// for example permuting rows and columns symmetrically in diagonals
+    /// Sudoku.
+    #[serde(rename = "composite")]
+    Composite(Vec<Transform>)
+}
+
+/// The reasons why a [Transform] cannot be applied to a Sudoku.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum TransformError {
+
+    /// The transformation does not fit the dimensions of the Sudoku, e.g. it
+    /// contains an invalid permutation.
+    InvalidTransform,
+
+    /// The transformation does not preserve the constraint of the Sudoku.
+    NotPreserving
+}
+
+/// A transformation resolved for specific dimensions. `cells` contains the
+/// new position of each cell in row-major order, and `digits` the new value
+/// of each digit at its index, where index 0 is unused.
+struct Mapping {
+    cells: Vec<Cell>,
+    digits: Vec<usize>
+}
+
+fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+
+    order.len() == len && order.iter().all(|&index|
+        index < len && !std::mem::replace(&mut seen[index], true))
+}
+
+/// Gets the new index of the element at the given old index, split into
+/// chunks of the given length that are permuted as a whole.
+fn permuted(order: &[usize], index: usize, chunk: usize) -> usize {
+    let position = order.iter().position(|&i| i == index / chunk).unwrap();
+    position * chunk + index % chunk
+}
+
+/// Gets all pairs of distinct indices below the given length, each in
+/// ascending order.
+fn pairs(len: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..len).flat_map(move |i| (i + 1..len).map(move |j| (i, j)))
+}
+
+fn swap_order(len: usize, i: usize, j: usize) -> Vec<usize> {
+    let mut order = (0..len).collect::<Vec<_>>();
+    order.swap(i, j);
+    order
+}
+
+impl Mapping {
+
+    fn identity(size: usize) -> Mapping {
+        Mapping {
+            cells: (0..size)
+                .flat_map(|row| (0..size).map(move |column| (column, row)))
+                .collect(),
+            digits: (0..=size).collect()
+        }
+    }
+
+    fn map_cells<F>(mut self, f: F) -> Mapping
+    where
+        F: Fn(Cell) -> Cell
+    {
+        self.cells.iter_mut().for_each(|cell| *cell = f(*cell));
+        self
+    }
+
+    fn cell(&self, (column, row): Cell) -> Cell {
+        let size = self.digits.len() - 1;
+        self.cells[row * size + column]
+    }
+}
+
+impl Transform {
+
+    /// Appends this transformation to the given mapping.
+    fn extend(&self, mapping: Mapping, grid: &SudokuGrid)
+            -> Result<Mapping, TransformError> {
+        let block_width = grid.block_width();
+        let block_height = grid.block_height();
+        let size = grid.size();
+        let last = size - 1;
+
+        match self {
+            Transform::Relabel(digits) => {
+                let valid = digits.len() == size &&
+                    is_permutation(
+                        &digits.iter().map(|&d| d.wrapping_sub(1))
+                            .collect::<Vec<_>>(),
+                        size);
+
+                if !valid {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                let mut mapping = mapping;
+                mapping.digits.iter_mut()
+                    .skip(1)
+                    .for_each(|digit| *digit = digits[*digit - 1]);
+                Ok(mapping)
+            },
+            Transform::Rotate(turns) => {
+                if turns % 2 == 1 && block_width != block_height {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|cell| (0..turns % 4)
+                    .fold(cell, |(column, row), _| (last - row, column))))
+            },
+            Transform::ReflectHorizontally =>
+                Ok(mapping.map_cells(|(column, row)| (last - column, row))),
+            Transform::ReflectVertically =>
+                Ok(mapping.map_cells(|(column, row)| (column, last - row))),
+            Transform::Transpose => {
+                if block_width != block_height {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|(column, row)| (row, column)))
+            },
+            Transform::PermuteBands(order) => {
+                if !is_permutation(order, block_width) {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|(column, row)|
+                    (column, permuted(order, row, block_height))))
+            },
+            Transform::PermuteStacks(order) => {
+                if !is_permutation(order, block_height) {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|(column, row)|
+                    (permuted(order, column, block_width), row)))
+            },
+            Transform::PermuteRows { band, order } => {
+                if *band >= block_width ||
+                        !is_permutation(order, block_height) {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|(column, row)| {
+                    if row / block_height == *band {
+                        let offset = permuted(order, row % block_height, 1);
+                        (column, band * block_height + offset)
+                    }
+                    else {
+                        (column, row)
+                    }
+                }))
+            },
+            Transform::PermuteColumns { stack, order } => {
+                if *stack >= block_height ||
+                        !is_permutation(order, block_width) {
+                    return Err(TransformError::InvalidTransform);
+                }
+
+                Ok(mapping.map_cells(|(column, row)| {
+                    if column / block_width == *stack {
+                        let offset = permuted(order, column % block_width, 1);
+                        (stack * block_width + offset, row)
+                    }
+                    else {
+                        (column, row)
+                    }
+                }))
+            },
+            Transform::Composite(transforms) => transforms.iter()
+                .try_fold(mapping, |mapping, t| t.extend(mapping, grid))
+        }
+    }
+}
+
+fn preserves_diagonals(mapping: &Mapping, size: usize) -> bool {
+    let last = size - 1;
+    let is_diagonal = |cells: Vec<Cell>|
+        cells.iter().all(|&(column, row)| column == row) ||
+            cells.iter().all(|&(column, row)| column + row == last);
+    let main = (0..size).map(|i| mapping.cell((i, i))).collect();
+    let anti = (0..size).map(|i| mapping.cell((last - i, i))).collect();
+
+    is_diagonal(main) && is_diagonal(anti)
+}
+
+fn preserves_relation(mapping: &Mapping, size: usize, relation: Relation)
+        -> bool {
+    (0..size)
+        .flat_map(|row| (0..size).map(move |column| (column, row)))
+        .all(|cell| relation.neighbours(cell.0, cell.1, size)
+            .all(|neighbour| relation.relates(mapping.cell(cell),
+                mapping.cell(neighbour))))
+}
+
+/// A line of the grid with a sandwich clue.
+enum Line {
+    Column(usize),
+    Row(usize)
+}
+
+/// Gets the line onto which the given cells of a line are mapped, if they are
+/// mapped onto one line in the same or reverse order, which keeps the digits
+/// between 1 and the largest digit.
+fn line_image(mapping: &Mapping, cells: Vec<Cell>) -> Option<Line> {
+    let images = cells.into_iter()
+        .map(|cell| mapping.cell(cell))
+        .collect::<Vec<_>>();
+    let (column, row) = images[0];
+    let (line, positions) =
+        if images.iter().all(|&(c, _)| c == column) {
+            (Line::Column(column),
+                images.iter().map(|&(_, r)| r).collect::<Vec<_>>())
+        }
+        else if images.iter().all(|&(_, r)| r == row) {
+            (Line::Row(row), images.iter().map(|&(c, _)| c).collect())
+        }
+        else {
+            return None;
+        };
+    let ordered = positions.windows(2).all(|w| w[0] < w[1]) ||
+        positions.windows(2).all(|w| w[0] > w[1]);
+
+    if ordered {
+        Some(line)
+    }
+    else {
+        None
+    }
+}
+
+fn transform_sandwich(constraint: &SandwichConstraint, mapping: &Mapping,
+        size: usize) -> Option<SandwichConstraint> {
+    // Sums depend on the values between the buns, so the only allowed
+    // relabelling swaps the buns.
+    let digits = &mapping.digits;
+    let keeps_values = (2..size).all(|d| digits[d] == d);
+    let keeps_buns = (digits[1] == 1 && digits[size] == size) ||
+        (digits[1] == size && digits[size] == 1);
+
+    if !keeps_values || !keeps_buns {
+        return None;
+    }
+
+    let clues = SandwichClues::of(constraint);
+    let mut transformed = SandwichClues {
+        columns: vec![None; size],
+        rows: vec![None; size]
+    };
+
+    for index in 0..size {
+        let column = (0..size).map(|row| (index, row)).collect();
+        let row = (0..size).map(|column| (column, index)).collect();
+
+        for (cells, clue) in
+                [(column, clues.columns[index]), (row, clues.rows[index])] {
+            match line_image(mapping, cells)? {
+                Line::Column(column) => transformed.columns[column] = clue,
+                Line::Row(row) => transformed.rows[row] = clue
+            }
+        }
+    }
+
+    Some(transformed.into_constraint())
+}
+
+fn transform_constraint(constraint: &AnyConstraint, mapping: &Mapping,
+        size: usize) -> Option<AnyConstraint> {
+    // All transformations keep rows, columns, and blocks, so the default
+    // constraint is always preserved.
+    let preserved = match constraint {
+        AnyConstraint::Default => true,
+        AnyConstraint::Diagonals => preserves_diagonals(mapping, size),
+        AnyConstraint::KnightsMove =>
+            preserves_relation(mapping, size, Relation::Knight),
+        AnyConstraint::KingsMove =>
+            preserves_relation(mapping, size, Relation::King),
+        AnyConstraint::Sandwich(c) =>
+            return transform_sandwich(c, mapping, size)
+                .map(AnyConstraint::Sandwich),
+        AnyConstraint::Composite(cs) =>
+            return cs.iter()
+                .map(|c| transform_constraint(c, mapping, size))
+                .collect::<Option<Vec<_>>>()
+                .map(AnyConstraint::Composite)
+    };
+
+    if preserved {
+        Some(constraint.clone())
+    }
+    else {
+        None
+    }
+}
+
+/// Applies the given transformation to the given Sudoku.
+///
+/// # Errors
+///
+/// * [TransformError::InvalidTransform] if the transformation does not fit
+/// the dimensions of the Sudoku.
+/// * [TransformError::NotPreserving] if the transformation does not preserve
+/// the constraint of the Sudoku.
+pub(crate) fn transform(sudoku: &Sudoku<AnyConstraint>, transform: &Transform)
+        -> Result<Sudoku<AnyConstraint>, TransformError> {
+    let grid = sudoku.grid();
+    let size = grid.size();
+    let mapping = transform.extend(Mapping::identity(size), grid)?;
+    let constraint = transform_constraint(sudoku.constraint(), &mapping, size)
+        .ok_or(TransformError::NotPreserving)?;
+    let mut transformed =
+        SudokuGrid::new(grid.block_width(), grid.block_height()).unwrap();
+
+    for row in 0..size {
+        for column in 0..size {
+            if let Some(digit) = grid.get_cell(column, row).unwrap() {
+                let (new_column, new_row) = mapping.cell((column, row));
+                transformed.set_cell(new_column, new_row,
+                    mapping.digits[digit]).unwrap();
+            }
+        }
+    }
+
+    Ok(Sudoku::new_with_grid(transformed, constraint))
+}
+
+/// Gets the transformations which swap two elements of a group of the given
+/// size, where `cons` creates the transformation for a permutation.
+fn swaps<F>(len: usize, cons: F) -> Vec<Transform>
+where
+    F: Fn(Vec<usize>) -> Transform
+{
+    pairs(len)
+        .map(|(i, j)| cons(swap_order(len, i, j)))
+        .collect()
+}
+
+/// Gets a set of elementary transformations from which all supported ones can
+/// be composed, including symmetric row and column swaps for square blocks.
+fn elementary_transforms(grid: &SudokuGrid) -> Vec<Transform> {
+    let block_width = grid.block_width();
+    let block_height = grid.block_height();
+    let size = grid.size();
+    let mut transforms = swaps(size, |order| Transform::Relabel(
+        order.into_iter().map(|i| i + 1).collect()));
+    transforms.extend([
+        Transform::Rotate(1),
+        Transform::Rotate(2),
+        Transform::Rotate(3),
+        Transform::ReflectHorizontally,
+        Transform::ReflectVertically,
+        Transform::Transpose
+    ]);
+    transforms.extend(swaps(block_width, Transform::PermuteBands));
+    transforms.extend(swaps(block_height, Transform::PermuteStacks));
+
+    for band in 0..block_width {
+        transforms.extend(swaps(block_height,
+            |order| Transform::PermuteRows { band, order }));
+    }
+
+    for stack in 0..block_height {
+        transforms.extend(swaps(block_width,
+            |order| Transform::PermuteColumns { stack, order }));
+    }
+
+    if block_width != block_height {
+        return transforms;
+    }
+
+    // Swapping rows and the columns with the same indices, together with the
+    // mirrored swap, keeps both diagonals. Combinations where the swaps do
+    // not commute are filtered out later.
+    let blocks = block_width;
+    let mut symmetric = Vec::new();
+
+    for (i, j) in pairs(blocks) {
+        let mut parts = vec![
+            Transform::PermuteBands(swap_order(blocks, i, j)),
+            Transform::PermuteStacks(swap_order(blocks, i, j))
+        ];
+        let (mi, mj) = (blocks - 1 - j, blocks - 1 - i);
+
+        if (mi, mj) != (i, j) {
+            parts.push(Transform::PermuteBands(swap_order(blocks, mi, mj)));
+            parts.push(Transform::PermuteStacks(swap_order(blocks, mi, mj)));
+        }
+
+        symmetric.push(Transform::Composite(parts));
+
+        for block in 0..blocks {
+            let mirrored = blocks - 1 - block;
+            let mut parts = vec![
+                Transform::PermuteRows {
+                    band: block,
+                    order: swap_order(blocks, i, j)
+                },
+                Transform::PermuteColumns {
+                    stack: block,
+                    order: swap_order(blocks, i, j)
+                }
+            ];
+
+            if (mirrored, mi, mj) != (block, i, j) {
+                parts.push(Transform::PermuteRows {
+                    band: mirrored,
+                    order: swap_order(blocks, mi, mj)
+                });
+                parts.push(Transform::PermuteColumns {
+                    stack: mirrored,
+                    order: swap_order(blocks, mi, mj)
+                });
+            }
+
+            symmetric.push(Transform::Composite(parts));
+        }
+    }
+
+    transforms.extend(symmetric);
+    transforms
+}
+
+/// Gets elementary transformations which preserve the constraint of the
+/// given Sudoku. Every composition of them does so as well.
+pub(crate) fn available_transforms(sudoku: &Sudoku<AnyConstraint>)
+        -> Vec<Transform> {
+    elementary_transforms(sudoku.grid()).into_iter()
+        .filter(|t| transform(sudoku, t).is_ok())
+        .collect()
+}
+
+/// Applies a random composition of transformations which preserve the
+/// constraint of the given Sudoku to it.
+pub(crate) fn random_transform<R>(sudoku: &Sudoku<AnyConstraint>, rng: &mut R)
+    -> Sudoku<AnyConstraint>
+where
+    R: Rng
+{
+    let mut transforms = available_transforms(sudoku);
+    transforms.shuffle(rng);
+    let mut result = sudoku.clone();
+
+    for t in transforms {
+        if rng.gen() {
+            result = transform(&result, &t).unwrap();
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::test_util::{sandwich, sudoku};
+
+    fn clues(sudoku: &Sudoku<AnyConstraint>) -> SandwichClues {
+        match sudoku.constraint() {
+            AnyConstraint::Sandwich(c) => SandwichClues::of(c),
+            _ => panic!("not a sandwich constraint")
+        }
+    }
+
+    /// Creates the clues of 9 lines, all missing except for the given ones,
+    /// each given as index and clue.
+    fn line_clues(present: &[(usize, usize)]) -> Vec<Option<usize>> {
+        let mut clues = vec![None; 9];
+
+        for &(index, clue) in present {
+            clues[index] = Some(clue);
+        }
+
+        clues
+    }
+
+    fn mapping(transform: &Transform, grid: &SudokuGrid) -> Mapping {
+        transform.extend(Mapping::identity(grid.size()), grid).unwrap()
+    }
+
+    fn first_column() -> Vec<Cell> {
+        (0..9).map(|row| (0, row)).collect()
+    }
+
+    #[test]
+    fn line_image_follows_lines() {
+        let grid = SudokuGrid::new(3, 3).unwrap();
+        let rotated = mapping(&Transform::Rotate(1), &grid);
+        let reflected = mapping(&Transform::ReflectHorizontally, &grid);
+        let permuted = mapping(&Transform::PermuteRows {
+            band: 0,
+            order: vec![1, 0, 2]
+        }, &grid);
+
+        assert!(matches!(line_image(&rotated, first_column()),
+            Some(Line::Row(0))));
+        assert!(matches!(line_image(&reflected, first_column()),
+            Some(Line::Column(8))));
+        assert!(line_image(&permuted, first_column()).is_none());
+    }
+
+    #[test]
+    fn sandwich_clues_move_with_lines() {
+        let sudoku = sudoku(3, 3, sandwich(9));
+        let rotated = transform(&sudoku, &Transform::Rotate(1)).unwrap();
+        let rotated = clues(&rotated);
+        let reflected =
+            transform(&sudoku, &Transform::ReflectHorizontally).unwrap();
+        let reflected = clues(&reflected);
+
+        assert_eq!(line_clues(&[(7, 7)]), rotated.columns);
+        assert_eq!(line_clues(&[(0, 0), (8, 35)]), rotated.rows);
+        assert_eq!(line_clues(&[(0, 35), (8, 0)]), reflected.columns);
+        assert_eq!(line_clues(&[(1, 7)]), reflected.rows);
+    }
+
+    #[test]
+    fn sandwich_rejects_reordered_lines() {
+        let sudoku = sudoku(3, 3, sandwich(9));
+        let transforms = [
+            Transform::PermuteBands(vec![1, 0, 2]),
+            Transform::PermuteRows {
+                band: 0,
+                order: vec![2, 0, 1]
+            }
+        ];
+
+        for t in transforms {
+            assert_eq!(Some(TransformError::NotPreserving),
+                transform(&sudoku, &t).err());
+        }
+    }
+
+    #[test]
+    fn sandwich_relabel_only_swaps_buns() {
+        let sudoku = sudoku(3, 3, sandwich(9));
+        let swapped =
+            transform(&sudoku, &Transform::Relabel(vec![9, 2, 3, 4, 5, 6, 7,
+                8, 1])).unwrap();
+        let grid = swapped.grid();
+
+        assert_eq!(Some(9), grid.get_cell(0, 0).unwrap());
+        assert_eq!(Some(2), grid.get_cell(1, 4).unwrap());
+        assert_eq!(Some(1), grid.get_cell(8, 7).unwrap());
+        assert_eq!(Some(TransformError::NotPreserving),
+            transform(&sudoku, &Transform::Relabel(vec![1, 3, 2, 4, 5, 6, 7,
+                8, 9])).err());
+        assert_eq!(Some(TransformError::NotPreserving),
+            transform(&sudoku, &Transform::Relabel(vec![2, 1, 3, 4, 5, 6, 7,
+                8, 9])).err());
+    }
+
+    #[test]
+    fn only_symmetric_permutations_preserve_diagonals() {
+        let sudoku = sudoku(3, 3, AnyConstraint::Diagonals);
+        let symmetric = [
+            Transform::Composite(vec![
+                Transform::PermuteBands(vec![2, 1, 0]),
+                Transform::PermuteStacks(vec![2, 1, 0])
+            ]),
+            Transform::Composite(vec![
+                Transform::PermuteRows {
+                    band: 0,
+                    order: vec![1, 0, 2]
+                },
+                Transform::PermuteColumns {
+                    stack: 0,
+                    order: vec![1, 0, 2]
+                },
+                Transform::PermuteRows {
+                    band: 2,
+                    order: vec![0, 2, 1]
+                },
+                Transform::PermuteColumns {
+                    stack: 2,
+                    order: vec![0, 2, 1]
+                }
+            ])
+        ];
+        let asymmetric = [
+            Transform::PermuteBands(vec![2, 1, 0]),
+            Transform::Composite(vec![
+                Transform::PermuteBands(vec![1, 0, 2]),
+                Transform::PermuteStacks(vec![1, 0, 2])
+            ]),
+            Transform::Composite(vec![
+                Transform::PermuteRows {
+                    band: 0,
+                    order: vec![1, 0, 2]
+                },
+                Transform::PermuteColumns {
+                    stack: 0,
+                    order: vec![1, 0, 2]
+                }
+            ])
+        ];
+
+        for t in symmetric {
+            assert!(transform(&sudoku, &t).is_ok());
+        }
+
+        for t in asymmetric {
+            assert_eq!(Some(TransformError::NotPreserving),
+                transform(&sudoku, &t).err());
+        }
+    }
+
+    #[test]
+    fn non_square_blocks_reject_quarter_turns() {
+        let sudoku = sudoku(3, 2, AnyConstraint::Default);
+
+        for t in [Transform::Rotate(1), Transform::Rotate(3),
+                Transform::Transpose] {
+            assert_eq!(Some(TransformError::InvalidTransform),
+                transform(&sudoku, &t).err());
+        }
+
+        assert!(transform(&sudoku, &Transform::Rotate(2)).is_ok());
+    }
+
+    #[test]
+    fn non_permutations_are_rejected() {
+        let sudoku = sudoku(3, 3, AnyConstraint::Default);
+        let transforms = [
+            Transform::Relabel(vec![1, 1, 3, 4, 5, 6, 7, 8, 9]),
+            Transform::Relabel(vec![0, 2, 3, 4, 5, 6, 7, 8, 9]),
+            Transform::Relabel(vec![1, 2, 3, 4, 5, 6, 7, 8]),
+            Transform::PermuteBands(vec![0, 1]),
+            Transform::PermuteStacks(vec![0, 1, 3]),
+            Transform::PermuteRows {
+                band: 3,
+                order: vec![0, 1, 2]
+            },
+            Transform::PermuteColumns {
+                stack: 0,
+                order: vec![0, 0, 1]
+            },
+            Transform::Composite(vec![
+                Transform::Rotate(2),
+                Transform::PermuteBands(vec![2, 2, 0])
+            ])
+        ];
+
+        for t in transforms {
+            assert_eq!(Some(TransformError::InvalidTransform),
+                transform(&sudoku, &t).err());
+        }
+    }
+}
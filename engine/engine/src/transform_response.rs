@@ -0,0 +1,45 @@
+use crate::constraint::AnyConstraint;
+use crate::schema;
+use crate::transform::TransformError;
+
+use serde::Serialize;
+
+use sudoku_variants::Sudoku;
+
+/// The response to a `transform` call to the engine. To be serialized for
+/// return.
+#[derive(Serialize)]
+#[serde(tag = "type", content = "value")]
+pub(crate) enum TransformResponse {
+
+    /// Indicates that the transformation was applied. The transformed Sudoku
+    /// is provided.
+    #[serde(rename = "ok")]
+    Ok(#[serde(serialize_with = "schema::serialize")] Sudoku<AnyConstraint>),
+
+    /// Indicates that the transformation does not fit the dimensions of the
+    /// Sudoku.
+    #[serde(rename = "invalid-transform")]
+    InvalidTransform,
+
+    /// Indicates that the transformation does not preserve the constraint of
+    /// the Sudoku.
+    #[serde(rename = "not-preserving")]
+    NotPreserving
+}
+
+impl TransformResponse {
+
+    /// Wraps the result of a transformation in a transform response.
+    pub(crate) fn from_result(
+            result: Result<Sudoku<AnyConstraint>, TransformError>)
+            -> TransformResponse {
+        match result {
+            Ok(sudoku) => TransformResponse::Ok(sudoku),
+            Err(TransformError::InvalidTransform) =>
+                TransformResponse::InvalidTransform,
+            Err(TransformError::NotPreserving) =>
+                TransformResponse::NotPreserving
+        }
+    }
+}
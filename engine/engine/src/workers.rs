@@ -0,0 +1,362 @@
+use crate::sync::CancelHandle;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// The priority of a job submitted to a [WorkerPool]. Queued jobs of higher
+/// priority are started first, jobs of equal priority in submission order.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Priority {
+
+    /// Work that nobody is currently waiting for, such as refilling the
+    /// puzzle pool. Requests of this priority only occupy a single worker,
+    /// and running jobs of this priority are cancelled when interactive work
+    /// would otherwise have to wait for them.
+    Background,
+
+    /// Work that is awaited, but not by an interactive user, such as batch
+    /// generation.
+    Normal,
+
+    /// Work that a user is actively waiting for.
+    Interactive
+}
+
+struct Job {
+    priority: Priority,
+    sequence: u64,
+    cancel_handle: CancelHandle,
+    body: Box<dyn FnOnce() + Send>
+}
+
+/// A job that a worker is currently executing.
+struct RunningJob {
+    priority: Priority,
+    sequence: u64,
+    cancel_handle: CancelHandle
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Job) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Job { }
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Job) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Job) -> Ordering {
+        // The heap pops the maximum, so earlier jobs must compare greater.
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+struct PoolState {
+    queue: BinaryHeap<Job>,
+    running: Vec<RunningJob>,
+    target_threads: usize,
+    threads: usize,
+    next_sequence: u64
+}
+
+impl PoolState {
+
+    /// Cancels all running jobs of [Priority::Background] if there are more
+    /// queued jobs of [Priority::Interactive] than idle workers, so the
+    /// interactive jobs do not have to wait for the background ones to finish.
+    fn preempt_background(&self) {
+        let idle = self.target_threads.saturating_sub(self.running.len());
+        let interactive = self.queue.iter()
+            .filter(|job| job.priority == Priority::Interactive)
+            .count();
+
+        if interactive <= idle {
+            return;
+        }
+
+        for job in &self.running {
+            if job.priority == Priority::Background {
+                job.cancel_handle.cancel();
+            }
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<PoolState>,
+    job_available: Condvar
+}
+
+fn worker_loop(shared: Arc<Shared>) {
+    loop {
+        let job = {
+            let mut state = shared.state.lock().unwrap();
+
+            loop {
+                if state.threads > state.target_threads {
+                    state.threads -= 1;
+                    return;
+                }
+
+                if let Some(job) = state.queue.pop() {
+                    state.running.push(RunningJob {
+                        priority: job.priority,
+                        sequence: job.sequence,
+                        cancel_handle: job.cancel_handle.clone()
+                    });
+                    break job;
+                }
+
+                state = shared.job_available.wait(state).unwrap();
+            }
+        };
+        let sequence = job.sequence;
+
+        // A panicking job must not take the worker down with it.
+        let _ = panic::catch_unwind(AssertUnwindSafe(job.body));
+
+        shared.state.lock().unwrap().running
+            .retain(|running| running.sequence != sequence);
+    }
+}
+
+/// A pool of long-lived worker threads which execute submitted jobs in the
+/// order of their [Priority]. Every worker runs one job at a time, so work
+/// that was cancelled but is still winding down finishes before the worker
+/// starts anything new. Running background jobs are cancelled via their
+/// [CancelHandle] when interactive jobs would otherwise wait for them, and
+/// are expected to wind down quickly.
+pub(crate) struct WorkerPool {
+    shared: Arc<Shared>
+}
+
+impl WorkerPool {
+
+    /// Creates a new worker pool with the given number of threads, which must
+    /// be at least one.
+    pub(crate) fn new(threads: usize) -> WorkerPool {
+        let pool = WorkerPool {
+            shared: Arc::new(Shared {
+                state: Mutex::new(PoolState {
+                    queue: BinaryHeap::new(),
+                    running: Vec::new(),
+                    target_threads: 0,
+                    threads: 0,
+                    next_sequence: 0
+                }),
+                job_available: Condvar::new()
+            })
+        };
+
+        pool.set_threads(threads);
+        pool
+    }
+
+    /// Gets the number of threads this pool is configured to use.
+    pub(crate) fn threads(&self) -> usize {
+        self.shared.state.lock().unwrap().target_threads
+    }
+
+    /// Changes the number of threads of this pool. If it is reduced, surplus
+    /// workers exit once they have finished their current job. The number is
+    /// clamped to at least one.
+    pub(crate) fn set_threads(&self, threads: usize) {
+        let threads = threads.max(1);
+        let mut state = self.shared.state.lock().unwrap();
+        state.target_threads = threads;
+
+        while state.threads < threads {
+            state.threads += 1;
+            let shared = Arc::clone(&self.shared);
+            thread::spawn(move || worker_loop(shared));
+        }
+
+        drop(state);
+        self.shared.job_available.notify_all();
+    }
+
+    /// Queues the given job with the given priority. If interactive jobs
+    /// would have to wait for running background jobs, the latter are
+    /// cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority`: The [Priority] of the job.
+    /// * `cancel_handle`: The [CancelHandle] the job polls. It is cancelled if
+    /// the job has [Priority::Background] and is preempted.
+    /// * `body`: The work to execute.
+    pub(crate) fn submit<F>(&self, priority: Priority,
+        cancel_handle: CancelHandle, body: F)
+    where
+        F: FnOnce() + Send + 'static
+    {
+        let mut state = self.shared.state.lock().unwrap();
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.queue.push(Job {
+            priority,
+            sequence,
+            cancel_handle,
+            body: Box::new(body)
+        });
+        state.preempt_background();
+        drop(state);
+        self.shared.job_available.notify_one();
+    }
+}
+
+/// The worker pool shared by all generation in the engine.
+static WORKERS: Mutex<Option<Arc<WorkerPool>>> = Mutex::new(None);
+
+/// Gets the worker pool shared by all generation in the engine. It is created
+/// on first use with one thread per CPU.
+pub(crate) fn workers() -> Arc<WorkerPool> {
+    let mut workers = WORKERS.lock().unwrap();
+
+    // TODO replace with thread::available_parallelism once stable
+    Arc::clone(workers.get_or_insert_with(
+        || Arc::new(WorkerPool::new(num_cpus::get()))))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::time::{Duration, Instant};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Submits a job that signals when it has started and then blocks until
+    /// the returned sender is used or dropped, so it occupies one worker.
+    fn block_worker(pool: &WorkerPool, priority: Priority) -> Sender<()> {
+        let (started_sender, started) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        pool.submit(priority, CancelHandle::new(), move || {
+            started_sender.send(()).unwrap();
+            let _ = released.recv();
+        });
+        started.recv_timeout(TIMEOUT).unwrap();
+        release
+    }
+
+    /// Submits a job that waits until its cancel handle is cancelled and then
+    /// reports it, or gives up after the timeout.
+    fn cancellable_job(pool: &WorkerPool, priority: Priority,
+            cancel_handle: &CancelHandle) -> Receiver<bool> {
+        let (started_sender, started) = mpsc::channel();
+        let (result_sender, result) = mpsc::channel();
+        let handle = cancel_handle.clone();
+        pool.submit(priority, cancel_handle.clone(), move || {
+            started_sender.send(()).unwrap();
+            let start = Instant::now();
+
+            while !handle.is_cancelled() && start.elapsed() < TIMEOUT {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            result_sender.send(handle.is_cancelled()).unwrap();
+        });
+        started.recv_timeout(TIMEOUT).unwrap();
+        result
+    }
+
+    #[test]
+    fn queued_jobs_run_in_priority_order() {
+        let pool = WorkerPool::new(1);
+        let release = block_worker(&pool, Priority::Normal);
+        let (sender, order) = mpsc::channel();
+        let jobs = [
+            ("background", Priority::Background),
+            ("normal 1", Priority::Normal),
+            ("interactive", Priority::Interactive),
+            ("normal 2", Priority::Normal)
+        ];
+
+        for (name, priority) in jobs {
+            let sender = sender.clone();
+            pool.submit(priority, CancelHandle::new(),
+                move || sender.send(name).unwrap());
+        }
+
+        drop(release);
+        let order = (0..jobs.len())
+            .map(|_| order.recv_timeout(TIMEOUT).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec!["interactive", "normal 1", "normal 2", "background"],
+            order);
+    }
+
+    #[test]
+    fn interactive_job_preempts_background_job() {
+        let pool = WorkerPool::new(1);
+        let cancel_handle = CancelHandle::new();
+        let background =
+            cancellable_job(&pool, Priority::Background, &cancel_handle);
+        let (sender, interactive) = mpsc::channel();
+        pool.submit(Priority::Interactive, CancelHandle::new(),
+            move || sender.send(()).unwrap());
+
+        assert!(background.recv_timeout(TIMEOUT).unwrap());
+        interactive.recv_timeout(TIMEOUT).unwrap();
+    }
+
+    #[test]
+    fn interactive_job_on_idle_worker_does_not_preempt() {
+        let pool = WorkerPool::new(2);
+        let cancel_handle = CancelHandle::new();
+        let background =
+            cancellable_job(&pool, Priority::Background, &cancel_handle);
+        let (sender, interactive) = mpsc::channel();
+        pool.submit(Priority::Interactive, CancelHandle::new(),
+            move || sender.send(()).unwrap());
+        interactive.recv_timeout(TIMEOUT).unwrap();
+
+        assert!(!cancel_handle.is_cancelled());
+
+        cancel_handle.cancel();
+        assert!(background.recv_timeout(TIMEOUT).unwrap());
+    }
+
+    #[test]
+    fn normal_job_does_not_preempt() {
+        let pool = WorkerPool::new(1);
+        let cancel_handle = CancelHandle::new();
+        let background =
+            cancellable_job(&pool, Priority::Background, &cancel_handle);
+        pool.submit(Priority::Normal, CancelHandle::new(), || { });
+
+        assert!(!cancel_handle.is_cancelled());
+
+        cancel_handle.cancel();
+        assert!(background.recv_timeout(TIMEOUT).unwrap());
+    }
+
+    #[test]
+    fn panicking_job_does_not_kill_worker() {
+        let pool = WorkerPool::new(1);
+        let (sender, done) = mpsc::channel();
+        pool.submit(Priority::Normal, CancelHandle::new(),
+            || panic!("job failed"));
+        pool.submit(Priority::Normal, CancelHandle::new(),
+            move || sender.send(()).unwrap());
+        done.recv_timeout(TIMEOUT).unwrap();
+
+        let state = pool.shared.state.lock().unwrap();
+
+        assert_eq!(1, state.threads);
+        assert!(state.running.iter().all(|job| job.sequence != 0));
+    }
+}